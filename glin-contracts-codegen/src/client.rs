@@ -0,0 +1,179 @@
+// Typed contract client with one method per message and constructor
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use heck::ToSnakeCase;
+use ink_metadata::InkProject;
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use scale_info::form::PortableForm;
+
+use crate::types::{ident, scale_path, TypeGenerator};
+
+type MessageParamSpec = ink_metadata::MessageParamSpec<PortableForm>;
+
+/// Methods generated on every client
+const RESERVED_METHODS: &[&str] = &["at", "from_instance", "instance"];
+
+/// Parameters generated before or after the message arguments
+const RESERVED_ARGS: &[&str] = &["client", "signer", "wasm", "options"];
+
+/// Generate the client struct and its impl block
+pub(crate) fn generate(
+    project: &InkProject,
+    types: &TypeGenerator,
+    client: &Ident,
+) -> Result<TokenStream> {
+    let private = quote!(::glin_contracts::__private);
+    let mut used: BTreeSet<String> = RESERVED_METHODS.iter().map(|s| s.to_string()).collect();
+
+    let mut methods = Vec::new();
+
+    for constructor in project.spec().constructors() {
+        let name = method_name(&format!("deploy_{}", constructor.label()), &mut used);
+        let docs = constructor.docs();
+        let (params, encode) = args(constructor.args(), types)?;
+        let selector = constructor.selector().to_bytes();
+
+        methods.push(quote! {
+            #(#[doc = #docs])*
+            pub async fn #name(
+                client: &#private::GlinClient,
+                signer: &#private::Keypair,
+                wasm: &[u8],
+                #(#params,)*
+                options: ::glin_contracts::DeployOptions,
+            ) -> #private::anyhow::Result<Self> {
+                let mut __data = ::std::vec![#(#selector),*];
+                #encode
+                let __deployed =
+                    ::glin_contracts::deploy_wasm_with_data(client, signer, wasm, __data, options)
+                        .await?;
+                Self::at(client, &__deployed.contract_address)
+            }
+        });
+    }
+
+    for message in project.spec().messages() {
+        let label = message.label();
+        let name = method_name(label, &mut used);
+        let docs = message.docs();
+        let (params, encode) = args(message.args(), types)?;
+        let selector = message.selector().to_bytes();
+
+        if message.mutates() {
+            methods.push(quote! {
+                #(#[doc = #docs])*
+                pub async fn #name(
+                    &self,
+                    signer: &#private::Keypair,
+                    #(#params,)*
+                    options: ::glin_contracts::CallOptions,
+                ) -> #private::anyhow::Result<::glin_contracts::CallResult> {
+                    let mut __data = ::std::vec![#(#selector),*];
+                    #encode
+                    self.instance.call_raw(__data, signer, options).await
+                }
+            });
+        } else {
+            let ret =
+                types.type_path(message.return_type().ret_type().ty().id, &quote!(types::))?;
+            let scale = scale_path();
+
+            methods.push(quote! {
+                #(#[doc = #docs])*
+                pub async fn #name(
+                    &self,
+                    #(#params,)*
+                ) -> #private::anyhow::Result<#ret> {
+                    let mut __data = ::std::vec![#(#selector),*];
+                    #encode
+                    let __output = self
+                        .instance
+                        .query_raw(__data, None, ::std::default::Default::default())
+                        .await?;
+                    match <#ret as #scale::Decode>::decode(&mut &__output.data[..]) {
+                        Ok(value) => Ok(value),
+                        Err(_) if __output.reverted => {
+                            #private::anyhow::bail!("Message '{}' reverted", #label)
+                        }
+                        Err(err) => Err(err.into()),
+                    }
+                }
+            });
+        }
+    }
+
+    Ok(quote! {
+        /// Typed client for the contract
+        #[derive(Clone)]
+        pub struct #client {
+            instance: ::glin_contracts::ContractInstance,
+        }
+
+        impl #client {
+            /// Attach to a deployed contract
+            pub fn at(
+                client: &#private::GlinClient,
+                address: &str,
+            ) -> #private::anyhow::Result<Self> {
+                Ok(Self {
+                    instance: ::glin_contracts::ContractInstance::new(client, address, metadata()?)?,
+                })
+            }
+
+            /// Wrap an existing contract instance
+            pub fn from_instance(instance: ::glin_contracts::ContractInstance) -> Self {
+                Self { instance }
+            }
+
+            /// The underlying untyped contract instance
+            pub fn instance(&self) -> &::glin_contracts::ContractInstance {
+                &self.instance
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+/// Unique snake_case method name for a message or constructor label
+///
+/// Trait messages such as `PSP22::transfer` become `psp22_transfer`.
+fn method_name(label: &str, used: &mut BTreeSet<String>) -> Ident {
+    let base = label.replace("::", "_").to_snake_case();
+
+    let mut name = base.clone();
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{}_{}", base, n);
+        n += 1;
+    }
+
+    ident(&name)
+}
+
+/// Typed parameters and the statements appending them to `__data`
+fn args(
+    specs: &[MessageParamSpec],
+    types: &TypeGenerator,
+) -> Result<(Vec<TokenStream>, TokenStream)> {
+    let scale = scale_path();
+    let mut params = Vec::with_capacity(specs.len());
+    let mut encode = TokenStream::new();
+
+    for spec in specs {
+        let mut name = spec.label().to_snake_case();
+        if RESERVED_ARGS.contains(&name.as_str()) {
+            name.push_str("_arg");
+        }
+        let name = ident(&name);
+        let ty = types.type_path(spec.ty().ty().id, &quote!(types::))?;
+
+        params.push(quote!(#name: #ty));
+        encode.extend(quote!(#scale::Encode::encode_to(&#name, &mut __data);));
+    }
+
+    Ok((params, encode))
+}
@@ -0,0 +1,289 @@
+//! GLIN Contract Code Generation
+//!
+//! Generates Rust code from ink! contract metadata: typed definitions for the
+//! contract's registry types and a typed client with one method per message
+//! and constructor. The generated code uses `glin-contracts` at runtime.
+//!
+//! Use [`generate_file`] from a build script, or the `contract!` macro
+//! re-exported by `glin-contracts`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! // build.rs
+//! fn main() -> anyhow::Result<()> {
+//!     let out_dir = std::env::var("OUT_DIR")?;
+//!     glin_contracts_codegen::generate_file(
+//!         "contracts/flipper.contract",
+//!         format!("{}/flipper.rs", out_dir),
+//!     )
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/flipper.rs"));
+//!
+//! let flipper = flipper::Flipper::at(&client, address)?;
+//! let value = flipper.get().await?;
+//! ```
+
+mod client;
+mod types;
+mod versions;
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde_json::Value as JsonValue;
+
+use crate::types::TypeGenerator;
+
+pub use crate::versions::{upgrade_project, MetadataVersion};
+
+/// Options for code generation
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// Name of the generated module (defaults to the snake_case contract name)
+    pub module_name: Option<String>,
+    /// Name of the generated client type (defaults to the UpperCamelCase contract name)
+    pub client_name: Option<String>,
+}
+
+/// Generate a Rust module from ink! metadata JSON or a `.contract` bundle
+///
+/// v3 and v4 metadata is upgraded to v5. The WASM code of a bundle is not
+/// embedded in the generated module.
+pub fn generate(metadata_json: &str, options: &CodegenOptions) -> Result<TokenStream> {
+    let mut json: JsonValue =
+        serde_json::from_str(metadata_json).context("Invalid metadata JSON")?;
+
+    if let Some(source) = json.get_mut("source").and_then(|s| s.as_object_mut()) {
+        source.remove("wasm");
+    }
+
+    // The embedded copy keeps its source format and is upgraded when parsed
+    let mut root = json
+        .as_object()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Contract metadata must be a JSON object"))?;
+    let (_, project) = upgrade_project(&mut root).context("Invalid ink! metadata")?;
+
+    let contract_name = json
+        .get("contract")
+        .and_then(|c| c.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or("contract");
+
+    let module_name = options
+        .module_name
+        .clone()
+        .unwrap_or_else(|| contract_name.to_snake_case());
+    let client_name = options
+        .client_name
+        .clone()
+        .unwrap_or_else(|| contract_name.to_upper_camel_case());
+
+    let module = types::ident(&module_name);
+    let client = types::ident(&client_name);
+
+    let types = TypeGenerator::new(project.registry());
+    let definitions = types.definitions()?;
+    let client = client::generate(&project, &types, &client)?;
+
+    let embedded = serde_json::to_string(&json)?;
+
+    Ok(quote! {
+        pub mod #module {
+            #![allow(dead_code, unused_imports, clippy::all)]
+
+            /// Contract metadata this module was generated from
+            pub const METADATA: &str = #embedded;
+
+            /// Parse the embedded contract metadata
+            pub fn metadata() -> ::glin_contracts::__private::anyhow::Result<
+                ::glin_contracts::__private::InkProject,
+            > {
+                ::glin_contracts::metadata::parse_metadata(METADATA)
+            }
+
+            /// Types from the contract's type registry
+            pub mod types {
+                #definitions
+            }
+
+            #client
+        }
+    })
+}
+
+/// Generate a Rust module as source text
+pub fn generate_to_string(metadata_json: &str, options: &CodegenOptions) -> Result<String> {
+    Ok(generate(metadata_json, options)?.to_string())
+}
+
+/// Generate a Rust module from a metadata file and write it to `output`
+///
+/// Intended for build scripts: also tells Cargo to re-run when `input` changes.
+pub fn generate_file(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<()> {
+    let input = input.as_ref();
+    let output = output.as_ref();
+
+    let metadata_json = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read metadata file: {}", input.display()))?;
+    let code = generate_to_string(&metadata_json, &CodegenOptions::default())?;
+
+    std::fs::write(output, code)
+        .with_context(|| format!("Failed to write generated code: {}", output.display()))?;
+
+    println!("cargo:rerun-if-changed={}", input.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink_metadata::layout::{Layout, StructLayout};
+    use ink_metadata::InkProject;
+    use ink_metadata::{
+        ConstructorSpec, ContractSpec, EnvironmentSpec, LangError, MessageParamSpec, MessageSpec,
+        ReturnTypeSpec, TypeSpec,
+    };
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct AccountId([u8; 32]);
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Error {
+        InsufficientBalance,
+        Custom(String),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Entry {
+        owner: AccountId,
+        amounts: Vec<u128>,
+        r#type: Option<u8>,
+    }
+
+    /// Metadata JSON for a small token contract
+    fn token_metadata() -> String {
+        let spec = ContractSpec::new()
+            .constructors(vec![ConstructorSpec::from_label("new")
+                .selector([0x9b, 0xae, 0x9d, 0x5e])
+                .payable(false)
+                .args(vec![MessageParamSpec::new("total_supply")
+                    .of_type(TypeSpec::of_type::<u128>())
+                    .done()])
+                .returns(ReturnTypeSpec::new(TypeSpec::of_type::<
+                    Result<(), LangError>,
+                >()))
+                .done()])
+            .messages(vec![
+                MessageSpec::from_label("get_entry")
+                    .selector([0, 0, 0, 1])
+                    .mutates(false)
+                    .payable(false)
+                    .args(vec![MessageParamSpec::new("owner")
+                        .of_type(TypeSpec::of_type::<AccountId>())
+                        .done()])
+                    .returns(ReturnTypeSpec::new(TypeSpec::of_type::<
+                        Result<Option<Entry>, LangError>,
+                    >()))
+                    .done(),
+                MessageSpec::from_label("PSP22::transfer")
+                    .selector([0, 0, 0, 2])
+                    .mutates(true)
+                    .payable(false)
+                    .args(vec![
+                        MessageParamSpec::new("to")
+                            .of_type(TypeSpec::of_type::<AccountId>())
+                            .done(),
+                        MessageParamSpec::new("options")
+                            .of_type(TypeSpec::of_type::<(u8, bool)>())
+                            .done(),
+                    ])
+                    .returns(ReturnTypeSpec::new(TypeSpec::of_type::<
+                        Result<Result<(), Error>, LangError>,
+                    >()))
+                    .done(),
+            ])
+            .lang_error(TypeSpec::of_type::<LangError>())
+            .environment(
+                EnvironmentSpec::new()
+                    .account_id(TypeSpec::of_type::<AccountId>())
+                    .balance(TypeSpec::of_type::<u128>())
+                    .hash(TypeSpec::of_type::<[u8; 32]>())
+                    .timestamp(TypeSpec::of_type::<u64>())
+                    .block_number(TypeSpec::of_type::<u32>())
+                    .chain_extension(TypeSpec::of_type::<()>())
+                    .max_event_topics(4)
+                    .static_buffer_size(16 * 1024)
+                    .done(),
+            )
+            .done();
+
+        let project = InkProject::new(Layout::Struct(StructLayout::new("Token", Vec::new())), spec);
+
+        let mut json = serde_json::to_value(project).unwrap();
+        json["contract"] = serde_json::json!({ "name": "my_token", "version": "1.0.0" });
+        json["source"] = serde_json::json!({ "wasm": "0x0061736d" });
+        json.to_string()
+    }
+
+    #[test]
+    fn test_generate_token_module() {
+        let code = generate(&token_metadata(), &CodegenOptions::default()).unwrap();
+        let file: syn::File = syn::parse2(code.clone()).unwrap();
+        let code = code.to_string();
+
+        assert!(matches!(&file.items[0], syn::Item::Mod(m) if m.ident == "my_token"));
+        assert!(code.contains("pub struct MyToken"));
+        assert!(code.contains("pub struct Entry"));
+        assert!(code.contains("pub enum Error"));
+        assert!(code.contains("pub async fn deploy_new"));
+        assert!(code.contains("pub async fn get_entry"));
+        assert!(code.contains("pub async fn psp22_transfer"));
+        assert!(code.contains("options_arg"));
+        assert!(code.contains("r#type"));
+        assert!(!code.contains("0061736d"));
+    }
+
+    #[test]
+    fn test_generate_from_v4_metadata() {
+        let mut json: JsonValue = serde_json::from_str(&token_metadata()).unwrap();
+        json["version"] = serde_json::json!("4");
+        let spec = json["spec"].as_object_mut().unwrap();
+        spec.remove("environment");
+        for kind in ["constructors", "messages"] {
+            for item in spec[kind].as_array_mut().unwrap() {
+                item.as_object_mut().unwrap().remove("default");
+            }
+        }
+
+        let code = generate_to_string(&json.to_string(), &CodegenOptions::default()).unwrap();
+        assert!(code.contains("pub struct MyToken"));
+        assert!(code.contains("pub async fn psp22_transfer"));
+        assert!(code.contains(r#"\"version\":\"4\""#));
+    }
+
+    #[test]
+    fn test_generate_options_override_names() {
+        let options = CodegenOptions {
+            module_name: Some("token".to_string()),
+            client_name: Some("Token".to_string()),
+        };
+        let code = generate_to_string(&token_metadata(), &options).unwrap();
+
+        assert!(code.starts_with("pub mod token"));
+        assert!(code.contains("pub struct Token"));
+        assert!(generate("{}", &options).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+// Rust type definitions for the contract's type registry
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use heck::ToUpperCamelCase;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{quote, ToTokens};
+use scale_info::form::PortableForm;
+use scale_info::{Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+
+/// Path to the SCALE codec re-exported by `glin-contracts`
+pub(crate) fn scale_path() -> TokenStream {
+    quote!(::glin_contracts::__private::scale)
+}
+
+/// Build an identifier, escaping Rust keywords
+pub(crate) fn ident(name: &str) -> Ident {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    };
+
+    match name.as_str() {
+        // Cannot be raw identifiers
+        "self" | "Self" | "super" | "crate" | "_" => {
+            Ident::new(&format!("{}_", name), Span::call_site())
+        }
+        _ if is_keyword(&name) => Ident::new_raw(&name, Span::call_site()),
+        _ => Ident::new(&name, Span::call_site()),
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "static"
+            | "struct"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "gen"
+            | "macro"
+            | "override"
+            | "priv"
+            | "try"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+    )
+}
+
+/// Maps registry type IDs to Rust types and generates their definitions
+pub(crate) struct TypeGenerator<'a> {
+    registry: &'a PortableRegistry,
+    /// Names of the types that get a generated definition
+    names: BTreeMap<u32, Ident>,
+}
+
+impl<'a> TypeGenerator<'a> {
+    /// Assign a unique name to every struct and enum in the registry
+    pub(crate) fn new(registry: &'a PortableRegistry) -> Self {
+        let mut names = BTreeMap::new();
+        let mut used = BTreeSet::new();
+
+        for ty in &registry.types {
+            if !needs_definition(&ty.ty) {
+                continue;
+            }
+
+            let base = ty
+                .ty
+                .path
+                .segments
+                .last()
+                .map(|s| s.to_upper_camel_case())
+                .unwrap_or_else(|| "Anonymous".to_string());
+
+            // Generic instantiations share a path, so number the duplicates
+            let mut name = base.clone();
+            let mut n = 2;
+            while !used.insert(name.clone()) {
+                name = format!("{}{}", base, n);
+                n += 1;
+            }
+
+            names.insert(ty.id, ident(&name));
+        }
+
+        Self { registry, names }
+    }
+
+    /// Rust type for a registry type, with generated types under `prefix`
+    pub(crate) fn type_path(&self, type_id: u32, prefix: &TokenStream) -> Result<TokenStream> {
+        let ty = self
+            .registry
+            .resolve(type_id)
+            .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", type_id))?;
+
+        if let Some(name) = self.names.get(&type_id) {
+            return Ok(quote!(#prefix #name));
+        }
+
+        let scale = scale_path();
+
+        Ok(match &ty.type_def {
+            TypeDef::Primitive(prim) => primitive_type(prim),
+            TypeDef::Sequence(seq) => {
+                let inner = self.type_path(seq.type_param.id, prefix)?;
+                quote!(::std::vec::Vec<#inner>)
+            }
+            TypeDef::Array(arr) => {
+                let inner = self.type_path(arr.type_param.id, prefix)?;
+                let len = Literal::usize_unsuffixed(arr.len as usize);
+                quote!([#inner; #len])
+            }
+            TypeDef::Tuple(tuple) => {
+                let elements = tuple
+                    .fields
+                    .iter()
+                    .map(|field| self.type_path(field.id, prefix))
+                    .collect::<Result<Vec<_>>>()?;
+                quote!((#(#elements,)*))
+            }
+            TypeDef::Compact(compact) => {
+                let inner = self.type_path(compact.type_param.id, prefix)?;
+                quote!(#scale::Compact<#inner>)
+            }
+            TypeDef::BitSequence(_) => {
+                anyhow::bail!(
+                    "Bit sequences are not supported by code generation (type {})",
+                    type_id
+                )
+            }
+            TypeDef::Composite(_) | TypeDef::Variant(_) => {
+                let params = ty
+                    .type_params
+                    .iter()
+                    .map(|param| match &param.ty {
+                        Some(param_ty) => self.type_path(param_ty.id, prefix),
+                        None => anyhow::bail!("Unresolved type parameter in type {}", type_id),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                match ty.path.segments.last().map(|s| s.as_str()) {
+                    Some("Option") => quote!(::core::option::Option<#(#params),*>),
+                    Some("Result") => quote!(::core::result::Result<#(#params),*>),
+                    _ => anyhow::bail!("No definition generated for type {}", type_id),
+                }
+            }
+        })
+    }
+
+    /// Definitions of all generated structs and enums
+    pub(crate) fn definitions(&self) -> Result<TokenStream> {
+        let mut definitions = TokenStream::new();
+
+        for (type_id, name) in &self.names {
+            let ty = self
+                .registry
+                .resolve(*type_id)
+                .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", type_id))?;
+            self.definition(ty, name)?.to_tokens(&mut definitions);
+        }
+
+        Ok(definitions)
+    }
+
+    fn definition(&self, ty: &Type<PortableForm>, name: &Ident) -> Result<TokenStream> {
+        let scale = scale_path();
+        let docs = &ty.docs;
+        let generated_from = format!("Generated from `{}`", ty.path.segments.join("::"));
+        let attrs = quote! {
+            #(#[doc = #docs])*
+            #[doc = #generated_from]
+            #[derive(Debug, Clone, PartialEq, Eq, #scale::Encode, #scale::Decode)]
+            #[codec(crate = #scale)]
+        };
+
+        match &ty.type_def {
+            TypeDef::Composite(composite) => {
+                let body = self.fields(&composite.fields, true)?;
+                let semicolon = if composite.fields.iter().any(|f| f.name.is_some()) {
+                    quote!()
+                } else {
+                    quote!(;)
+                };
+                Ok(quote! {
+                    #attrs
+                    pub struct #name #body #semicolon
+                })
+            }
+            TypeDef::Variant(variant_def) => {
+                let variants = variant_def
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let variant_name = ident(&variant.name);
+                        let index = Literal::u8_unsuffixed(variant.index);
+                        let docs = &variant.docs;
+                        let body = self.fields(&variant.fields, false)?;
+                        Ok(quote! {
+                            #(#[doc = #docs])*
+                            #[codec(index = #index)]
+                            #variant_name #body
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(quote! {
+                    #attrs
+                    pub enum #name {
+                        #(#variants,)*
+                    }
+                })
+            }
+            _ => anyhow::bail!("Expected composite or variant type"),
+        }
+    }
+
+    /// Struct or variant body: `{ named }`, `(unnamed)` or nothing
+    fn fields(&self, fields: &[Field<PortableForm>], public: bool) -> Result<TokenStream> {
+        if fields.is_empty() {
+            return Ok(quote!());
+        }
+
+        let vis = if public { quote!(pub) } else { quote!() };
+        let prefix = quote!();
+
+        if fields.iter().all(|f| f.name.is_some()) {
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    let name = ident(field.name.as_deref().unwrap_or_default());
+                    let ty = self.type_path(field.ty.id, &prefix)?;
+                    Ok(quote!(#vis #name: #ty))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(quote!({ #(#fields,)* }));
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let ty = self.type_path(field.ty.id, &prefix)?;
+                Ok(quote!(#vis #ty))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(quote!((#(#fields,)*)))
+    }
+}
+
+/// Whether a type gets its own struct or enum definition
+fn needs_definition(ty: &Type<PortableForm>) -> bool {
+    let builtin = matches!(
+        ty.path.segments.last().map(|s| s.as_str()),
+        Some("Option" | "Result")
+    ) && ty.path.segments.len() == 1;
+
+    matches!(ty.type_def, TypeDef::Composite(_) | TypeDef::Variant(_)) && !builtin
+}
+
+/// Rust type for a primitive
+///
+/// 256-bit integers have no native Rust type and are represented as their
+/// little-endian bytes.
+fn primitive_type(prim: &TypeDefPrimitive) -> TokenStream {
+    match prim {
+        TypeDefPrimitive::Bool => quote!(bool),
+        TypeDefPrimitive::Char => quote!(char),
+        TypeDefPrimitive::Str => quote!(::std::string::String),
+        TypeDefPrimitive::U8 => quote!(u8),
+        TypeDefPrimitive::U16 => quote!(u16),
+        TypeDefPrimitive::U32 => quote!(u32),
+        TypeDefPrimitive::U64 => quote!(u64),
+        TypeDefPrimitive::U128 => quote!(u128),
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => quote!([u8; 32]),
+        TypeDefPrimitive::I8 => quote!(i8),
+        TypeDefPrimitive::I16 => quote!(i16),
+        TypeDefPrimitive::I32 => quote!(i32),
+        TypeDefPrimitive::I64 => quote!(i64),
+        TypeDefPrimitive::I128 => quote!(i128),
+    }
+}
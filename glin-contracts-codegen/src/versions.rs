@@ -0,0 +1,276 @@
+//! Versioned ink! metadata
+//!
+//! cargo-contract emits metadata in several formats over the ink! releases:
+//!
+//! - **v3** (ink! 3.x): the project is nested under a `"V3"` key and the storage
+//!   layout uses 32-byte cell keys
+//! - **v4** (ink! 4.x): `"version": "4"`, no `default` flags, event module paths
+//!   or signature topics, and the `environment` section may be missing
+//! - **v5** (ink! 5.x): `"version": 5`, the format of [`ink_metadata`]
+//!
+//! [`upgrade_project`] reads any of them into an [`InkProject`], upgrading
+//! older formats to v5. It is shared by code generation and the
+//! `glin-contracts` bundle loader. The upgraded project keeps its source
+//! version number.
+//!
+//! ink! 3 storage differs fundamentally from the ink! 4+ layout, so v3 storage
+//! layouts are replaced by an empty struct.
+
+use std::fmt;
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+
+/// Default `staticBufferSize` of ink! environments
+const DEFAULT_STATIC_BUFFER_SIZE: u64 = 16 * 1024;
+
+/// Default `maxEventTopics` of ink! environments
+const DEFAULT_MAX_EVENT_TOPICS: u64 = 4;
+
+/// ink! metadata format version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MetadataVersion {
+    V3,
+    V4,
+    V5,
+}
+
+impl MetadataVersion {
+    /// Version number, as reported by [`InkProject::version`]
+    pub fn number(self) -> u64 {
+        match self {
+            MetadataVersion::V3 => 3,
+            MetadataVersion::V4 => 4,
+            MetadataVersion::V5 => 5,
+        }
+    }
+
+    /// Version of a (possibly upgraded) project
+    pub fn of(metadata: &InkProject) -> Self {
+        match metadata.version() {
+            3 => MetadataVersion::V3,
+            4 => MetadataVersion::V4,
+            _ => MetadataVersion::V5,
+        }
+    }
+}
+
+impl fmt::Display for MetadataVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// Take the project out of a metadata or bundle object, upgraded to v5
+///
+/// The bundle sections (`source`, `contract`, `image`) are left in `root`.
+pub fn upgrade_project(root: &mut Map<String, JsonValue>) -> Result<(MetadataVersion, InkProject)> {
+    let version = detect_version(root)?;
+
+    let mut project = match version {
+        MetadataVersion::V3 => match root.remove("V3") {
+            Some(JsonValue::Object(project)) => project,
+            _ => anyhow::bail!("Invalid ink! v3 metadata: `V3` must be an object"),
+        },
+        _ => {
+            let sections = ["source", "contract", "image"]
+                .into_iter()
+                .filter_map(|key| root.remove_entry(key))
+                .collect();
+            std::mem::replace(root, sections)
+        }
+    };
+    upgrade(&mut project, version)?;
+
+    let metadata = serde_json::from_value(JsonValue::Object(project))
+        .with_context(|| format!("Failed to parse ink! v{} contract metadata", version))?;
+
+    Ok((version, metadata))
+}
+
+/// Determine the metadata format from the `version` field (or `V3` key)
+fn detect_version(root: &Map<String, JsonValue>) -> Result<MetadataVersion> {
+    if root.contains_key("V3") {
+        return Ok(MetadataVersion::V3);
+    }
+    if root.contains_key("V1") || root.contains_key("V2") {
+        anyhow::bail!("ink! metadata versions 1 and 2 are not supported");
+    }
+
+    let version = match root.get("version") {
+        Some(JsonValue::Number(n)) => n.as_u64(),
+        Some(JsonValue::String(s)) => s.parse().ok(),
+        Some(_) => None,
+        None => anyhow::bail!("Missing `version` field in ink! contract metadata"),
+    };
+
+    match version {
+        Some(4) => Ok(MetadataVersion::V4),
+        Some(5) => Ok(MetadataVersion::V5),
+        _ => anyhow::bail!("Unsupported ink! metadata version: {}", root["version"]),
+    }
+}
+
+/// Bring an older metadata project up to the v5 format
+///
+/// The `version` field is normalized to a number but keeps the source version.
+fn upgrade(project: &mut Map<String, JsonValue>, version: MetadataVersion) -> Result<()> {
+    if version == MetadataVersion::V5 {
+        return Ok(());
+    }
+
+    project.insert("version".into(), json!(version.number()));
+
+    let mut types = match project.remove("types") {
+        Some(JsonValue::Array(types)) => types,
+        _ => anyhow::bail!("Missing `types` in ink! v{} metadata", version),
+    };
+    let spec = project
+        .get_mut("spec")
+        .and_then(|spec| spec.as_object_mut())
+        .ok_or_else(|| anyhow::anyhow!("Missing `spec` in ink! v{} metadata", version))?;
+
+    let unit = type_spec(
+        ensure_type(&mut types, json!({ "def": { "tuple": [] } })),
+        &[],
+    );
+
+    for kind in ["constructors", "messages"] {
+        for item in array_items(spec, kind) {
+            item.entry("default").or_insert(json!(false));
+            match item.get("returnType") {
+                None | Some(JsonValue::Null) => {
+                    item.insert("returnType".into(), unit.clone());
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    for event in array_items(spec, "events") {
+        event.entry("module_path").or_insert(json!(""));
+        event.entry("signature_topic").or_insert(JsonValue::Null);
+    }
+
+    if !spec.contains_key("lang_error") {
+        let lang_error = ensure_type(
+            &mut types,
+            json!({
+                "path": ["ink_primitives", "LangError"],
+                "def": { "variant": { "variants": [{ "name": "CouldNotReadInput", "index": 1 }] } }
+            }),
+        );
+        spec.insert(
+            "lang_error".into(),
+            type_spec(lang_error, &["ink", "LangError"]),
+        );
+    }
+
+    match spec
+        .get_mut("environment")
+        .and_then(|env| env.as_object_mut())
+    {
+        Some(environment) => {
+            environment
+                .entry("maxEventTopics")
+                .or_insert(json!(DEFAULT_MAX_EVENT_TOPICS));
+            environment
+                .entry("staticBufferSize")
+                .or_insert(json!(DEFAULT_STATIC_BUFFER_SIZE));
+        }
+        None => {
+            let environment = default_environment(&mut types);
+            spec.insert("environment".into(), environment);
+        }
+    }
+
+    if version == MetadataVersion::V3 {
+        project.insert(
+            "storage".into(),
+            json!({ "struct": { "name": "Storage", "fields": [] } }),
+        );
+    }
+
+    project.insert("types".into(), JsonValue::Array(types));
+    Ok(())
+}
+
+/// Environment section of `ink::env::DefaultEnvironment`
+fn default_environment(types: &mut Vec<JsonValue>) -> JsonValue {
+    let u8_ty = ensure_type(types, json!({ "def": { "primitive": "u8" } }));
+    let bytes32 = ensure_type(
+        types,
+        json!({ "def": { "array": { "len": 32, "type": u8_ty } } }),
+    );
+    let mut hash_type = |name: &str| {
+        ensure_type(
+            types,
+            json!({
+                "path": ["ink_primitives", "types", name],
+                "def": { "composite": { "fields": [{ "type": bytes32, "typeName": "[u8; 32]" }] } }
+            }),
+        )
+    };
+    let account_id = hash_type("AccountId");
+    let hash = hash_type("Hash");
+
+    let balance = ensure_type(types, json!({ "def": { "primitive": "u128" } }));
+    let timestamp = ensure_type(types, json!({ "def": { "primitive": "u64" } }));
+    let block_number = ensure_type(types, json!({ "def": { "primitive": "u32" } }));
+    let chain_extension = ensure_type(
+        types,
+        json!({ "path": ["ink_env", "types", "NoChainExtension"], "def": { "variant": {} } }),
+    );
+
+    json!({
+        "accountId": type_spec(account_id, &["AccountId"]),
+        "balance": type_spec(balance, &["Balance"]),
+        "hash": type_spec(hash, &["Hash"]),
+        "timestamp": type_spec(timestamp, &["Timestamp"]),
+        "blockNumber": type_spec(block_number, &["BlockNumber"]),
+        "chainExtension": type_spec(chain_extension, &["ChainExtension"]),
+        "maxEventTopics": DEFAULT_MAX_EVENT_TOPICS,
+        "staticBufferSize": DEFAULT_STATIC_BUFFER_SIZE,
+    })
+}
+
+/// ID of a type in the registry, appending it if not present
+///
+/// Types with a path are matched by path, anonymous types by definition.
+fn ensure_type(types: &mut Vec<JsonValue>, ty: JsonValue) -> u32 {
+    let existing = types.iter().find(|entry| {
+        let candidate = &entry["type"];
+        match ty.get("path") {
+            Some(path) => candidate.get("path") == Some(path),
+            None => {
+                candidate.get("path").is_none_or(|p| p == &json!([]))
+                    && candidate["def"] == ty["def"]
+            }
+        }
+    });
+
+    if let Some(id) = existing.and_then(|entry| entry["id"].as_u64()) {
+        return id as u32;
+    }
+
+    let id = types.len() as u32;
+    types.push(json!({ "id": id, "type": ty }));
+    id
+}
+
+fn type_spec(id: u32, display_name: &[&str]) -> JsonValue {
+    json!({ "type": id, "displayName": display_name })
+}
+
+fn array_items<'a>(
+    spec: &'a mut Map<String, JsonValue>,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Map<String, JsonValue>> {
+    spec.get_mut(key)
+        .and_then(|items| items.as_array_mut())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_object_mut())
+}
@@ -0,0 +1,55 @@
+//! GLIN Contract Macros
+//!
+//! Provides the `contract!` macro, which generates a typed contract client from
+//! ink! metadata at compile time. Use it through `glin_contracts::contract!`.
+
+use std::path::Path;
+
+use anyhow::Context;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+use glin_contracts_codegen::CodegenOptions;
+
+/// Generate a typed contract module from an ink! metadata file
+///
+/// The path is relative to the crate root (`CARGO_MANIFEST_DIR`) and may point
+/// to a metadata `.json` file or a `.contract` bundle. The generated module is
+/// named after the contract.
+///
+/// ```rust,ignore
+/// glin_contracts::contract!("contracts/flipper.json");
+///
+/// let flipper = flipper::Flipper::at(&client, address)?;
+/// flipper.flip(&signer, Default::default()).await?;
+/// let value = flipper.get().await?;
+/// ```
+#[proc_macro]
+pub fn contract(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+
+    match expand(&path) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => syn::Error::new(path.span(), format!("{:#}", err))
+            .to_compile_error()
+            .into(),
+    }
+}
+
+fn expand(path: &LitStr) -> anyhow::Result<proc_macro2::TokenStream> {
+    let manifest_dir =
+        std::env::var("CARGO_MANIFEST_DIR").context("CARGO_MANIFEST_DIR is not set")?;
+    let full_path = Path::new(&manifest_dir).join(path.value());
+
+    let metadata_json = std::fs::read_to_string(&full_path)
+        .with_context(|| format!("Failed to read metadata file: {}", full_path.display()))?;
+    let module = glin_contracts_codegen::generate(&metadata_json, &CodegenOptions::default())?;
+
+    // Recompile when the metadata file changes
+    let full_path = full_path.to_string_lossy();
+    Ok(quote! {
+        const _: &str = include_str!(#full_path);
+        #module
+    })
+}
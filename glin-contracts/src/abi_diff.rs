@@ -0,0 +1,783 @@
+//! ABI compatibility diff between two versions of a contract's metadata
+//!
+//! Before upgrading a contract with `set_code`, compare the metadata of the
+//! deployed and the new code to find out whether existing clients, indexers
+//! and the stored data stay compatible. Types are compared structurally (field
+//! names, variants and primitive layout), so renamed or reordered registry
+//! entries with the same shape are not reported.
+//!
+//! Changes are classified as:
+//!
+//! - **breaking**: removed or re-selected messages, changed argument or return
+//!   types, messages that start mutating or stop accepting value, changed
+//!   events and changes to how existing storage is encoded
+//! - **non-breaking**: additions, renamed arguments, relaxed mutability or
+//!   payability and storage fields in new cells
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use ink_metadata::layout::Layout;
+use ink_metadata::InkProject;
+use scale_info::form::PortableForm;
+use scale_info::{PortableRegistry, TypeDef};
+use serde::{Deserialize, Serialize};
+
+use crate::metadata;
+
+/// Whether a change breaks existing clients or stored data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    NonBreaking,
+    Breaking,
+}
+
+/// Part of the ABI a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AbiItem {
+    Message,
+    Constructor,
+    Event,
+    Storage,
+}
+
+/// What changed about an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Selector,
+    Arguments,
+    ReturnType,
+    Mutability,
+    Payable,
+    SignatureTopic,
+    Fields,
+    Layout,
+}
+
+/// A single difference between two metadata versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiChange {
+    /// Kind of item that changed
+    pub item: AbiItem,
+    /// Label of the message, constructor or event, or storage field path
+    pub name: String,
+    /// What changed
+    pub kind: ChangeKind,
+    /// Human-readable description, e.g. `selector 0x84a15da1 -> 0x00000001`
+    pub detail: String,
+    /// Breaking or non-breaking
+    pub severity: Severity,
+}
+
+impl fmt::Display for AbiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Breaking => "breaking",
+            Severity::NonBreaking => "non-breaking",
+        };
+        let item = match self.item {
+            AbiItem::Message => "message",
+            AbiItem::Constructor => "constructor",
+            AbiItem::Event => "event",
+            AbiItem::Storage => "storage",
+        };
+        write!(
+            f,
+            "[{}] {} `{}`: {}",
+            severity, item, self.name, self.detail
+        )
+    }
+}
+
+/// Differences between two metadata versions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiDiff {
+    /// All changes, in the order messages, constructors, events, storage
+    pub changes: Vec<AbiChange>,
+}
+
+impl AbiDiff {
+    /// Whether the two versions have the same ABI
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change is breaking
+    pub fn is_breaking(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| change.severity == Severity::Breaking)
+    }
+
+    /// Breaking changes only
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &AbiChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.severity == Severity::Breaking)
+    }
+
+    /// Changes to a single item
+    pub fn changes_for(&self, item: AbiItem, name: &str) -> Vec<&AbiChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.item == item && change.name == name)
+            .collect()
+    }
+
+    fn push(
+        &mut self,
+        item: AbiItem,
+        name: &str,
+        kind: ChangeKind,
+        detail: String,
+        severity: Severity,
+    ) {
+        self.changes.push(AbiChange {
+            item,
+            name: name.to_string(),
+            kind,
+            detail,
+            severity,
+        });
+    }
+}
+
+impl fmt::Display for AbiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No ABI changes");
+        }
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the metadata of the currently deployed code (`old`) with `new`
+pub fn diff_metadata(old: &InkProject, new: &InkProject) -> AbiDiff {
+    let mut diff = AbiDiff::default();
+
+    diff_callables(&mut diff, AbiItem::Message, &messages(old), &messages(new));
+    diff_callables(
+        &mut diff,
+        AbiItem::Constructor,
+        &constructors(old),
+        &constructors(new),
+    );
+    diff_events(&mut diff, &events(old), &events(new));
+    diff_storage(&mut diff, &storage_cells(old), &storage_cells(new));
+
+    diff
+}
+
+/// Message or constructor, reduced to what matters for compatibility
+struct Callable {
+    label: String,
+    selector: [u8; 4],
+    args: Vec<(String, String)>,
+    return_type: String,
+    mutates: Option<bool>,
+    payable: bool,
+}
+
+fn messages(project: &InkProject) -> Vec<Callable> {
+    let registry = project.registry();
+    project
+        .spec()
+        .messages()
+        .iter()
+        .map(|m| Callable {
+            label: m.label().clone(),
+            selector: metadata::selector_bytes(m.selector()),
+            args: args(registry, m.args()),
+            return_type: type_shape(registry, m.return_type().ret_type().ty().id),
+            mutates: Some(m.mutates()),
+            payable: m.payable(),
+        })
+        .collect()
+}
+
+fn constructors(project: &InkProject) -> Vec<Callable> {
+    let registry = project.registry();
+    project
+        .spec()
+        .constructors()
+        .iter()
+        .map(|c| Callable {
+            label: c.label().clone(),
+            selector: metadata::selector_bytes(c.selector()),
+            args: args(registry, c.args()),
+            return_type: type_shape(registry, c.return_type().ret_type().ty().id),
+            mutates: None,
+            payable: *c.payable(),
+        })
+        .collect()
+}
+
+fn args(
+    registry: &PortableRegistry,
+    specs: &[ink_metadata::MessageParamSpec<PortableForm>],
+) -> Vec<(String, String)> {
+    specs
+        .iter()
+        .map(|arg| (arg.label().clone(), type_shape(registry, arg.ty().ty().id)))
+        .collect()
+}
+
+fn diff_callables(diff: &mut AbiDiff, item: AbiItem, old: &[Callable], new: &[Callable]) {
+    for old_item in old {
+        let name = old_item.label.as_str();
+        let Some(new_item) = new.iter().find(|c| c.label == old_item.label) else {
+            diff.push(
+                item,
+                name,
+                ChangeKind::Removed,
+                "removed".into(),
+                Severity::Breaking,
+            );
+            continue;
+        };
+
+        if old_item.selector != new_item.selector {
+            diff.push(
+                item,
+                name,
+                ChangeKind::Selector,
+                format!(
+                    "selector 0x{} -> 0x{}",
+                    hex::encode(old_item.selector),
+                    hex::encode(new_item.selector)
+                ),
+                Severity::Breaking,
+            );
+        }
+
+        let old_types: Vec<_> = old_item.args.iter().map(|(_, ty)| ty).collect();
+        let new_types: Vec<_> = new_item.args.iter().map(|(_, ty)| ty).collect();
+        if old_types != new_types {
+            diff.push(
+                item,
+                name,
+                ChangeKind::Arguments,
+                format!(
+                    "arguments {} -> {}",
+                    format_args_list(&old_item.args),
+                    format_args_list(&new_item.args)
+                ),
+                Severity::Breaking,
+            );
+        } else if old_item.args != new_item.args {
+            diff.push(
+                item,
+                name,
+                ChangeKind::Arguments,
+                format!(
+                    "arguments renamed {} -> {}",
+                    format_args_list(&old_item.args),
+                    format_args_list(&new_item.args)
+                ),
+                Severity::NonBreaking,
+            );
+        }
+
+        if old_item.return_type != new_item.return_type {
+            diff.push(
+                item,
+                name,
+                ChangeKind::ReturnType,
+                format!(
+                    "return type {} -> {}",
+                    old_item.return_type, new_item.return_type
+                ),
+                Severity::Breaking,
+            );
+        }
+
+        if let (Some(old_mutates), Some(new_mutates)) = (old_item.mutates, new_item.mutates) {
+            if old_mutates != new_mutates {
+                // A query that starts mutating can no longer be used via dry-run
+                let (detail, severity) = if new_mutates {
+                    ("now mutates state", Severity::Breaking)
+                } else {
+                    ("no longer mutates state", Severity::NonBreaking)
+                };
+                diff.push(item, name, ChangeKind::Mutability, detail.into(), severity);
+            }
+        }
+
+        if old_item.payable != new_item.payable {
+            let (detail, severity) = if new_item.payable {
+                ("now payable", Severity::NonBreaking)
+            } else {
+                ("no longer payable", Severity::Breaking)
+            };
+            diff.push(item, name, ChangeKind::Payable, detail.into(), severity);
+        }
+    }
+
+    for new_item in new {
+        if !old.iter().any(|c| c.label == new_item.label) {
+            diff.push(
+                item,
+                &new_item.label,
+                ChangeKind::Added,
+                format!("added with selector 0x{}", hex::encode(new_item.selector)),
+                Severity::NonBreaking,
+            );
+        }
+    }
+}
+
+fn format_args_list(args: &[(String, String)]) -> String {
+    let args: Vec<_> = args
+        .iter()
+        .map(|(label, ty)| format!("{}: {}", label, ty))
+        .collect();
+    format!("({})", args.join(", "))
+}
+
+/// Event, reduced to what matters for decoding
+struct Event {
+    label: String,
+    signature_topic: Option<String>,
+    /// `(label, indexed, type)` of each field
+    fields: Vec<(String, bool, String)>,
+}
+
+fn events(project: &InkProject) -> Vec<Event> {
+    let registry = project.registry();
+    project
+        .spec()
+        .events()
+        .iter()
+        .map(|e| Event {
+            label: e.label().clone(),
+            signature_topic: e
+                .signature_topic()
+                .map(|topic| format!("0x{}", hex::encode(topic.as_bytes()))),
+            fields: e
+                .args()
+                .iter()
+                .map(|arg| {
+                    (
+                        arg.label().clone(),
+                        arg.indexed(),
+                        type_shape(registry, arg.ty().ty().id),
+                    )
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn diff_events(diff: &mut AbiDiff, old: &[Event], new: &[Event]) {
+    for old_event in old {
+        let name = old_event.label.as_str();
+        let Some(new_event) = new.iter().find(|e| e.label == old_event.label) else {
+            diff.push(
+                AbiItem::Event,
+                name,
+                ChangeKind::Removed,
+                "removed".into(),
+                Severity::Breaking,
+            );
+            continue;
+        };
+
+        if old_event.signature_topic != new_event.signature_topic {
+            diff.push(
+                AbiItem::Event,
+                name,
+                ChangeKind::SignatureTopic,
+                format!(
+                    "signature topic {} -> {}",
+                    old_event.signature_topic.as_deref().unwrap_or("none"),
+                    new_event.signature_topic.as_deref().unwrap_or("none")
+                ),
+                Severity::Breaking,
+            );
+        }
+
+        if old_event.fields != new_event.fields {
+            diff.push(
+                AbiItem::Event,
+                name,
+                ChangeKind::Fields,
+                format!(
+                    "fields {} -> {}",
+                    format_event_fields(&old_event.fields),
+                    format_event_fields(&new_event.fields)
+                ),
+                Severity::Breaking,
+            );
+        }
+    }
+
+    for new_event in new {
+        if !old.iter().any(|e| e.label == new_event.label) {
+            diff.push(
+                AbiItem::Event,
+                &new_event.label,
+                ChangeKind::Added,
+                "added".into(),
+                Severity::NonBreaking,
+            );
+        }
+    }
+}
+
+fn format_event_fields(fields: &[(String, bool, String)]) -> String {
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(label, indexed, ty)| {
+            let topic = if *indexed { "#[topic] " } else { "" };
+            format!("{}{}: {}", topic, label, ty)
+        })
+        .collect();
+    format!("({})", fields.join(", "))
+}
+
+/// Storage cell a field path is stored in and the shape of its value
+#[derive(PartialEq)]
+struct Cell {
+    root_key: u32,
+    shape: String,
+}
+
+/// Flatten a storage layout into field path → cell
+fn storage_cells(project: &InkProject) -> BTreeMap<String, Cell> {
+    let mut cells = BTreeMap::new();
+    collect_cells(
+        project.registry(),
+        project.layout(),
+        String::new(),
+        0,
+        &mut cells,
+    );
+    cells
+}
+
+fn collect_cells(
+    registry: &PortableRegistry,
+    layout: &Layout<PortableForm>,
+    path: String,
+    root_key: u32,
+    cells: &mut BTreeMap<String, Cell>,
+) {
+    let child = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+
+    match layout {
+        Layout::Root(root) => {
+            collect_cells(registry, root.layout(), path, *root.root_key().key(), cells)
+        }
+        Layout::Leaf(leaf) => {
+            cells.insert(
+                path,
+                Cell {
+                    root_key,
+                    shape: type_shape(registry, leaf.ty().id),
+                },
+            );
+        }
+        Layout::Struct(layout) => {
+            for field in layout.fields() {
+                collect_cells(
+                    registry,
+                    field.layout(),
+                    child(field.name()),
+                    root_key,
+                    cells,
+                );
+            }
+        }
+        Layout::Enum(layout) => {
+            for (discriminant, variant) in layout.variants() {
+                let variant_path = child(&format!("{}#{}", variant.name(), discriminant.value()));
+                for field in variant.fields() {
+                    collect_cells(
+                        registry,
+                        field.layout(),
+                        format!("{}.{}", variant_path, field.name()),
+                        root_key,
+                        cells,
+                    );
+                }
+            }
+        }
+        Layout::Array(layout) => collect_cells(
+            registry,
+            layout.layout(),
+            format!("{}[{}]", path, layout.len()),
+            root_key,
+            cells,
+        ),
+        Layout::Hash(layout) => collect_cells(
+            registry,
+            layout.layout(),
+            format!("{}[]", path),
+            root_key,
+            cells,
+        ),
+    }
+}
+
+fn diff_storage(diff: &mut AbiDiff, old: &BTreeMap<String, Cell>, new: &BTreeMap<String, Cell>) {
+    let old_keys: BTreeSet<u32> = old.values().map(|cell| cell.root_key).collect();
+
+    for (path, old_cell) in old {
+        match new.get(path) {
+            None => diff.push(
+                AbiItem::Storage,
+                path,
+                ChangeKind::Removed,
+                format!("removed from cell 0x{:08x}", old_cell.root_key),
+                Severity::Breaking,
+            ),
+            Some(new_cell) if new_cell != old_cell => diff.push(
+                AbiItem::Storage,
+                path,
+                ChangeKind::Layout,
+                format!(
+                    "{} in cell 0x{:08x} -> {} in cell 0x{:08x}",
+                    old_cell.shape, old_cell.root_key, new_cell.shape, new_cell.root_key
+                ),
+                Severity::Breaking,
+            ),
+            Some(_) => {}
+        }
+    }
+
+    for (path, new_cell) in new {
+        if old.contains_key(path) {
+            continue;
+        }
+
+        // Fields packed into an existing cell change how that cell is decoded
+        let (detail, severity) = if old_keys.contains(&new_cell.root_key) {
+            (
+                format!("added to existing cell 0x{:08x}", new_cell.root_key),
+                Severity::Breaking,
+            )
+        } else {
+            (
+                format!("added in new cell 0x{:08x}", new_cell.root_key),
+                Severity::NonBreaking,
+            )
+        };
+        diff.push(AbiItem::Storage, path, ChangeKind::Added, detail, severity);
+    }
+}
+
+/// Structural description of a type, independent of registry IDs and paths
+fn type_shape(registry: &PortableRegistry, type_id: u32) -> String {
+    shape_of(registry, type_id, &mut Vec::new())
+}
+
+fn shape_of(registry: &PortableRegistry, type_id: u32, visiting: &mut Vec<u32>) -> String {
+    if visiting.contains(&type_id) {
+        return "Self".into();
+    }
+    let Some(ty) = registry.resolve(type_id) else {
+        return format!("<unknown type {}>", type_id);
+    };
+
+    visiting.push(type_id);
+    let shape = match &ty.type_def {
+        TypeDef::Composite(composite) => fields_shape(registry, &composite.fields, visiting),
+        TypeDef::Variant(variant) => {
+            let variants: Vec<_> = variant
+                .variants
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}#{}{}",
+                        v.name,
+                        v.index,
+                        fields_shape(registry, &v.fields, visiting)
+                    )
+                })
+                .collect();
+            format!("<{}>", variants.join(" | "))
+        }
+        TypeDef::Sequence(seq) => {
+            format!("Vec<{}>", shape_of(registry, seq.type_param.id, visiting))
+        }
+        TypeDef::Array(array) => format!(
+            "[{}; {}]",
+            shape_of(registry, array.type_param.id, visiting),
+            array.len
+        ),
+        TypeDef::Tuple(tuple) => {
+            let fields: Vec<_> = tuple
+                .fields
+                .iter()
+                .map(|field| shape_of(registry, field.id, visiting))
+                .collect();
+            format!("({})", fields.join(", "))
+        }
+        TypeDef::Primitive(primitive) => format!("{:?}", primitive).to_lowercase(),
+        TypeDef::Compact(compact) => format!(
+            "Compact<{}>",
+            shape_of(registry, compact.type_param.id, visiting)
+        ),
+        TypeDef::BitSequence(bits) => format!(
+            "BitVec<{}, {}>",
+            shape_of(registry, bits.bit_store_type.id, visiting),
+            shape_of(registry, bits.bit_order_type.id, visiting)
+        ),
+    };
+    visiting.pop();
+
+    shape
+}
+
+fn fields_shape(
+    registry: &PortableRegistry,
+    fields: &[scale_info::Field<PortableForm>],
+    visiting: &mut Vec<u32>,
+) -> String {
+    if fields.is_empty() {
+        return String::new();
+    }
+
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|field| {
+            let shape = shape_of(registry, field.ty.id, visiting);
+            match &field.name {
+                Some(name) => format!("{}: {}", name, shape),
+                None => shape,
+            }
+        })
+        .collect();
+
+    if fields.iter().all(|f| !f.contains(": ")) {
+        format!("({})", fields.join(", "))
+    } else {
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{erc20, flipper};
+    use serde_json::json;
+
+    fn upgraded_erc20() -> InkProject {
+        let mut json = serde_json::to_value(erc20()).unwrap();
+        let spec = &mut json["spec"];
+
+        // transfer: new selector; balance_of: payable, argument renamed
+        spec["messages"][2]["selector"] = json!("0x00000001");
+        spec["messages"][1]["payable"] = json!(true);
+        spec["messages"][1]["args"][0]["label"] = json!("account");
+        // total_supply -> name: `total_supply` removed, `name` added
+        spec["messages"][0]["label"] = json!("name");
+        spec["messages"][0]["selector"] = json!("0x00000002");
+
+        // Approval: `value` no longer a u128 but the AccountId type of `owner`
+        let account_id = spec["events"][1]["args"][0]["type"].clone();
+        spec["events"][1]["args"][2]["type"] = account_id;
+
+        // New `paused` field in its own cell, `owner` packed as a u128
+        let fields = json["storage"]["root"]["layout"]["struct"]["fields"]
+            .as_array_mut()
+            .unwrap();
+        let total_supply = fields[0]["layout"].clone();
+        fields[2]["layout"] = total_supply;
+        let mut paused = fields[3].clone();
+        paused["name"] = json!("paused");
+        paused["layout"]["root"]["root_key"] = json!("0x00000077");
+        fields.push(paused);
+
+        crate::metadata::parse_metadata_from_json(&json).unwrap()
+    }
+
+    #[test]
+    fn test_diff_classifies_changes() {
+        assert!(diff_metadata(&erc20(), &erc20()).is_empty());
+
+        let diff = diff_metadata(&erc20(), &upgraded_erc20());
+        assert!(diff.is_breaking());
+
+        let kinds = |item, name| -> Vec<(ChangeKind, Severity)> {
+            diff.changes_for(item, name)
+                .into_iter()
+                .map(|c| (c.kind, c.severity))
+                .collect()
+        };
+
+        assert_eq!(
+            kinds(AbiItem::Message, "transfer"),
+            [(ChangeKind::Selector, Severity::Breaking)]
+        );
+        assert_eq!(
+            kinds(AbiItem::Message, "balance_of"),
+            [
+                (ChangeKind::Arguments, Severity::NonBreaking),
+                (ChangeKind::Payable, Severity::NonBreaking)
+            ]
+        );
+        assert_eq!(
+            kinds(AbiItem::Message, "total_supply"),
+            [(ChangeKind::Removed, Severity::Breaking)]
+        );
+        assert_eq!(
+            kinds(AbiItem::Message, "name"),
+            [(ChangeKind::Added, Severity::NonBreaking)]
+        );
+        assert_eq!(
+            kinds(AbiItem::Event, "Approval"),
+            [(ChangeKind::Fields, Severity::Breaking)]
+        );
+        assert!(kinds(AbiItem::Event, "Transfer").is_empty());
+        assert_eq!(
+            kinds(AbiItem::Storage, "owner"),
+            [(ChangeKind::Layout, Severity::Breaking)]
+        );
+        assert_eq!(
+            kinds(AbiItem::Storage, "paused"),
+            [(ChangeKind::Added, Severity::NonBreaking)]
+        );
+
+        let change = &diff.changes_for(AbiItem::Message, "transfer")[0];
+        assert_eq!(
+            change.to_string(),
+            "[breaking] message `transfer`: selector 0x84a15da1 -> 0x00000001"
+        );
+    }
+
+    #[test]
+    fn test_type_shape_is_structural() {
+        let metadata = erc20();
+        let registry = metadata.registry();
+        let transfer = metadata::get_message_spec(&metadata, "transfer").unwrap();
+
+        assert_eq!(
+            type_shape(registry, transfer.args()[0].ty().ty().id),
+            "([u8; 32])"
+        );
+        assert_eq!(
+            type_shape(registry, transfer.return_type().ret_type().ty().id),
+            "<Ok#0(()) | Err#1(<CouldNotReadInput#1>)>"
+        );
+
+        // Same shape from a different registry
+        let flipper = flipper();
+        let flip = metadata::get_message_spec(&flipper, "flip").unwrap();
+        assert_eq!(
+            type_shape(flipper.registry(), flip.return_type().ret_type().ty().id),
+            type_shape(registry, transfer.return_type().ret_type().ty().id)
+        );
+    }
+}
@@ -0,0 +1,481 @@
+//! Isolated contract builds for verification
+//!
+//! A [`BuildRunner`] runs `cargo contract build --release` for a
+//! [`BuildTarget`] in a project directory. [`ContainerRunner`] builds inside a
+//! pinned image with docker or podman, so the result does not depend on the
+//! tools installed on the host; [`LocalRunner`] runs the host's
+//! `cargo contract` and is the fallback when no container engine is available.
+//!
+//! The image and toolchain come from the [`Toolchain`] recorded in the deployed
+//! contract's bundle: by default the official verifiable build image
+//! `paritytech/contracts-verifiable:<cargo-contract version>`.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::bundle::ContractBundle;
+
+/// Repository of the verifiable build images published with cargo-contract
+pub const VERIFIABLE_IMAGE_REPOSITORY: &str = "paritytech/contracts-verifiable";
+
+/// Toolchain a contract was built with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Toolchain {
+    /// rustc version, e.g. `1.78.0`
+    pub rustc: Option<String>,
+    /// cargo-contract version, e.g. `4.1.1`
+    pub cargo_contract: Option<String>,
+    /// Build image recorded by a verifiable build
+    pub image: Option<String>,
+}
+
+impl Toolchain {
+    /// Toolchain recorded in a bundle's `source` section
+    pub fn from_bundle(bundle: &ContractBundle) -> Self {
+        let rustc = bundle
+            .source
+            .compiler
+            .as_deref()
+            .and_then(|compiler| compiler.strip_prefix("rustc "))
+            .map(|version| version.trim().to_string());
+
+        let cargo_contract = bundle
+            .source
+            .build_info
+            .as_ref()
+            .and_then(|info| info.get("cargo_contract_version"))
+            .and_then(|version| version.as_str())
+            .map(String::from);
+
+        Self {
+            rustc,
+            cargo_contract,
+            image: bundle.image.clone(),
+        }
+    }
+
+    /// Image to build in: the recorded image, or the verifiable image of the
+    /// recorded cargo-contract version
+    pub fn build_image(&self) -> Option<String> {
+        self.image.clone().or_else(|| {
+            self.cargo_contract
+                .as_ref()
+                .map(|version| format!("{}:{}", VERIFIABLE_IMAGE_REPOSITORY, version))
+        })
+    }
+}
+
+/// What to build within a project directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildTarget {
+    /// Manifest of the contract, relative to the project directory
+    /// (`Cargo.toml` if unset)
+    pub manifest_path: Option<PathBuf>,
+    /// Build with `--locked`, requiring an up-to-date `Cargo.lock`
+    pub locked: bool,
+}
+
+impl BuildTarget {
+    /// `cargo` arguments building this target
+    fn cargo_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "contract".to_string(),
+            "build".to_string(),
+            "--release".to_string(),
+        ];
+        if let Some(manifest_path) = &self.manifest_path {
+            // Forward slashes, so the path also works inside containers
+            let path: Vec<_> = manifest_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            args.push("--manifest-path".to_string());
+            args.push(path.join("/"));
+        }
+        if self.locked {
+            args.push("--locked".to_string());
+        }
+        args
+    }
+}
+
+/// Limits applied to a build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildLimits {
+    /// Wall-clock limit; the build is killed when exceeded
+    pub timeout: Duration,
+    /// Memory limit in MiB (containers only)
+    pub memory_mb: Option<u64>,
+    /// Number of CPUs (containers) or parallel jobs (local builds)
+    pub cpus: Option<u32>,
+    /// Allow network access, needed unless dependencies are vendored
+    pub network: bool,
+}
+
+impl Default for BuildLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15 * 60),
+            memory_mb: Some(4096),
+            cpus: Some(2),
+            network: true,
+        }
+    }
+}
+
+/// Outcome of a build
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildOutput {
+    /// Whether the build exited successfully
+    pub success: bool,
+    /// Whether the build was killed after exceeding the timeout
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs contract builds
+#[async_trait]
+pub trait BuildRunner: Send + Sync {
+    /// Runner name for logs and reports
+    fn name(&self) -> &str;
+
+    /// Build `target` in `project_dir` with `cargo contract build --release`
+    ///
+    /// Build failures are reported in the [`BuildOutput`]; errors mean the
+    /// build could not be run at all.
+    async fn build(
+        &self,
+        project_dir: &Path,
+        target: &BuildTarget,
+        toolchain: &Toolchain,
+        limits: &BuildLimits,
+    ) -> Result<BuildOutput>;
+}
+
+/// Container engine used by [`ContainerRunner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    /// Executable name
+    pub fn command(&self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+
+    /// Whether the engine is installed and its daemon reachable
+    pub async fn is_available(&self) -> bool {
+        Command::new(self.command())
+            .arg("version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok_and(|status| status.success())
+    }
+}
+
+/// Builds in a pinned image with docker or podman
+#[derive(Debug, Clone)]
+pub struct ContainerRunner {
+    engine: ContainerEngine,
+    image: Option<String>,
+}
+
+impl ContainerRunner {
+    /// Runner using the image derived from the contract's toolchain
+    pub fn new(engine: ContainerEngine) -> Self {
+        Self {
+            engine,
+            image: None,
+        }
+    }
+
+    /// Always build in `image`, e.g. a mirror of the verifiable image
+    pub fn with_image(mut self, image: impl Into<String>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+
+    /// First available engine, docker before podman
+    pub async fn detect() -> Option<Self> {
+        for engine in [ContainerEngine::Docker, ContainerEngine::Podman] {
+            if engine.is_available().await {
+                return Some(Self::new(engine));
+            }
+        }
+        None
+    }
+
+    fn run_args(
+        &self,
+        project_dir: &Path,
+        target: &BuildTarget,
+        image: &str,
+        limits: &BuildLimits,
+        container_name: &str,
+    ) -> Vec<String> {
+        let mut args = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            container_name.to_string(),
+        ];
+
+        if let Some(memory) = limits.memory_mb {
+            args.push(format!("--memory={}m", memory));
+        }
+        if let Some(cpus) = limits.cpus {
+            args.push(format!("--cpus={}", cpus));
+        }
+        if !limits.network {
+            args.push("--network=none".to_string());
+        }
+
+        args.extend([
+            "--volume".to_string(),
+            format!("{}:/contract", project_dir.display()),
+            "--workdir".to_string(),
+            "/contract".to_string(),
+            "--entrypoint".to_string(),
+            "cargo".to_string(),
+            image.to_string(),
+        ]);
+        args.extend(target.cargo_args());
+
+        args
+    }
+}
+
+#[async_trait]
+impl BuildRunner for ContainerRunner {
+    fn name(&self) -> &str {
+        self.engine.command()
+    }
+
+    async fn build(
+        &self,
+        project_dir: &Path,
+        target: &BuildTarget,
+        toolchain: &Toolchain,
+        limits: &BuildLimits,
+    ) -> Result<BuildOutput> {
+        let image = self
+            .image
+            .clone()
+            .or_else(|| toolchain.build_image())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No build image: the cargo-contract version is unknown, configure an image"
+                )
+            })?;
+
+        let project_dir = project_dir
+            .canonicalize()
+            .with_context(|| format!("Invalid project directory: {}", project_dir.display()))?;
+        let container_name = format!("glin-verify-{}", unique_suffix());
+
+        let mut command = Command::new(self.engine.command());
+        command.args(self.run_args(&project_dir, target, &image, limits, &container_name));
+
+        // Killing the client does not stop the container, neither on timeout
+        // nor when this future is dropped, e.g. by cancelling the job
+        let container = ContainerGuard {
+            engine: self.engine,
+            name: container_name,
+            running: true,
+        };
+
+        let output = run_command(command, limits.timeout).await?;
+        if output.timed_out {
+            container.kill().await;
+        } else {
+            container.finish();
+        }
+
+        Ok(output)
+    }
+}
+
+/// Kills a build container that is still running when dropped
+struct ContainerGuard {
+    engine: ContainerEngine,
+    name: String,
+    running: bool,
+}
+
+impl ContainerGuard {
+    /// The container exited on its own
+    fn finish(mut self) {
+        self.running = false;
+    }
+
+    async fn kill(mut self) {
+        self.running = false;
+        kill_container(self.engine, &self.name).await;
+    }
+}
+
+impl Drop for ContainerGuard {
+    fn drop(&mut self) {
+        if !self.running {
+            return;
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let (engine, name) = (self.engine, std::mem::take(&mut self.name));
+            runtime.spawn(async move { kill_container(engine, &name).await });
+        }
+    }
+}
+
+async fn kill_container(engine: ContainerEngine, name: &str) {
+    let _ = Command::new(engine.command())
+        .args(["kill", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+}
+
+/// Builds with the host's `cargo contract`
+///
+/// The recorded rustc version is selected through rustup (`cargo +1.78.0`);
+/// the cargo-contract version and memory limit cannot be enforced locally.
+#[derive(Debug, Clone, Default)]
+pub struct LocalRunner;
+
+#[async_trait]
+impl BuildRunner for LocalRunner {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn build(
+        &self,
+        project_dir: &Path,
+        target: &BuildTarget,
+        toolchain: &Toolchain,
+        limits: &BuildLimits,
+    ) -> Result<BuildOutput> {
+        let mut command = Command::new("cargo");
+        if let Some(rustc) = &toolchain.rustc {
+            command.arg(format!("+{}", rustc));
+        }
+        command.args(target.cargo_args()).current_dir(project_dir);
+        if let Some(cpus) = limits.cpus {
+            command.env("CARGO_BUILD_JOBS", cpus.to_string());
+        }
+
+        run_command(command, limits.timeout).await
+    }
+}
+
+/// Container runner if docker or podman is available, local builds otherwise
+pub async fn detect_runner() -> Arc<dyn BuildRunner> {
+    match ContainerRunner::detect().await {
+        Some(runner) => Arc::new(runner),
+        None => Arc::new(LocalRunner),
+    }
+}
+
+/// Run a command to completion, killing it after `timeout`
+async fn run_command(mut command: Command, timeout: Duration) -> Result<BuildOutput> {
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run {:?}", command.as_std().get_program()))?;
+
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output?;
+            Ok(BuildOutput {
+                success: output.status.success(),
+                timed_out: false,
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            })
+        }
+        Err(_) => Ok(BuildOutput {
+            success: false,
+            timed_out: true,
+            stdout: String::new(),
+            stderr: format!("Build timed out after {}s", timeout.as_secs()),
+        }),
+    }
+}
+
+fn unique_suffix() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::flipper;
+
+    #[test]
+    fn test_toolchain_from_bundle() {
+        let mut json = serde_json::to_value(flipper()).unwrap();
+        json["source"] = serde_json::json!({
+            "compiler": "rustc 1.78.0",
+            "build_info": { "cargo_contract_version": "4.1.1" },
+        });
+        let bundle = ContractBundle::from_value(json).unwrap();
+
+        let toolchain = Toolchain::from_bundle(&bundle);
+        assert_eq!(toolchain.rustc.as_deref(), Some("1.78.0"));
+        assert_eq!(
+            toolchain.build_image().as_deref(),
+            Some("paritytech/contracts-verifiable:4.1.1")
+        );
+
+        let runner = ContainerRunner::new(ContainerEngine::Podman);
+        let limits = BuildLimits {
+            network: false,
+            ..Default::default()
+        };
+        let target = BuildTarget {
+            manifest_path: Some(PathBuf::from("contracts/flipper/Cargo.toml")),
+            locked: true,
+        };
+        let args = runner.run_args(Path::new("/src"), &target, "img:1", &limits, "c1");
+        assert_eq!(
+            args.join(" "),
+            "run --rm --name c1 --memory=4096m --cpus=2 --network=none --volume /src:/contract \
+             --workdir /contract --entrypoint cargo img:1 contract build --release \
+             --manifest-path contracts/flipper/Cargo.toml --locked"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_command_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("5");
+
+        let output = run_command(command, Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(output.timed_out);
+        assert!(!output.success);
+    }
+}
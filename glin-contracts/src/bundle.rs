@@ -0,0 +1,315 @@
+//! `.contract` bundles and versioned ink! metadata
+//!
+//! [`ContractBundle`] reads ink! v3, v4 and v5 metadata, upgrading older
+//! formats to v5 with [`upgrade_project`] so the rest of the crate only deals
+//! with one [`InkProject`] representation, and keeps the `source` and
+//! `contract` sections of the bundle alongside it. The upgraded project keeps
+//! its source version number, which decides how events are encoded (see
+//! [`crate::events`]).
+//!
+//! Storage layouts of v3 metadata are replaced by an empty struct; storage
+//! decoding is not available for such contracts.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::metadata;
+
+pub use glin_contracts_codegen::{upgrade_project, MetadataVersion};
+
+/// `source` section of a bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BundleSource {
+    /// Blake2 hash of the wasm code (hex)
+    pub hash: Option<String>,
+    /// Source language, e.g. `ink! 5.0.0`
+    pub language: Option<String>,
+    /// Compiler, e.g. `rustc 1.78.0`
+    pub compiler: Option<String>,
+    /// Contract code, only present in `.contract` bundles
+    #[serde(skip)]
+    pub wasm: Option<Vec<u8>>,
+    /// Build information recorded by cargo-contract
+    pub build_info: Option<JsonValue>,
+}
+
+impl BundleSource {
+    /// Code hash from the `hash` field
+    pub fn code_hash(&self) -> Option<[u8; 32]> {
+        let hash = self.hash.as_deref()?;
+        hex::decode(hash.strip_prefix("0x").unwrap_or(hash))
+            .ok()?
+            .try_into()
+            .ok()
+    }
+}
+
+/// `contract` section of a bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BundleContract {
+    /// Contract (crate) name
+    pub name: String,
+    /// Contract (crate) version
+    pub version: String,
+    /// Contract authors
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub documentation: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+}
+
+/// A parsed `.contract` bundle or metadata `.json` file
+#[derive(Debug)]
+pub struct ContractBundle {
+    /// Format version the metadata was written in
+    pub version: MetadataVersion,
+    /// Code hash, language, compiler and (for bundles) the wasm code
+    pub source: BundleSource,
+    /// Contract name, version and authors
+    pub contract: BundleContract,
+    /// Build image, recorded by `cargo contract build --verifiable`
+    pub image: Option<String>,
+    /// The contract metadata, upgraded to the v5 format
+    ///
+    /// Its `version` is still the source version, so that legacy events are
+    /// decoded by index rather than by signature topic.
+    pub metadata: InkProject,
+}
+
+impl ContractBundle {
+    /// Parse a bundle or metadata file from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: JsonValue =
+            serde_json::from_str(json).context("Failed to parse ink! contract metadata JSON")?;
+        Self::from_value(value)
+    }
+
+    /// Parse a bundle or metadata file from a JSON value
+    pub fn from_value(mut value: JsonValue) -> Result<Self> {
+        let root = value
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("Contract metadata must be a JSON object"))?;
+
+        let (version, metadata) = upgrade_project(root)?;
+        let source = parse_source(root.remove("source"))?;
+        let contract = match root.remove("contract") {
+            Some(contract) => {
+                serde_json::from_value(contract).context("Invalid `contract` section")?
+            }
+            None => BundleContract::default(),
+        };
+        let image = match root.remove("image") {
+            Some(JsonValue::String(image)) => Some(image),
+            _ => None,
+        };
+
+        Ok(Self {
+            version,
+            source,
+            contract,
+            image,
+            metadata,
+        })
+    }
+
+    /// Wrap bare metadata, without `source` or `contract` sections
+    pub fn from_metadata(metadata: InkProject) -> Self {
+        Self {
+            version: MetadataVersion::of(&metadata),
+            source: BundleSource::default(),
+            contract: BundleContract::default(),
+            image: None,
+            metadata,
+        }
+    }
+
+    /// Serialize the bundle back to JSON, without the wasm code
+    ///
+    /// The metadata is written in its upgraded form and parses back with
+    /// [`from_value`](Self::from_value).
+    pub fn to_value(&self) -> Result<JsonValue> {
+        let mut value = serde_json::to_value(&self.metadata)?;
+        value["source"] = serde_json::to_value(&self.source)?;
+        if !self.contract.name.is_empty() {
+            value["contract"] = serde_json::to_value(&self.contract)?;
+        }
+        if let Some(image) = &self.image {
+            value["image"] = json!(image);
+        }
+        Ok(value)
+    }
+
+    /// Read a `.contract` bundle or metadata `.json` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read metadata file: {}", path.display()))?;
+
+        Self::from_json(&content).with_context(|| format!("Invalid metadata in {}", path.display()))
+    }
+
+    /// Contract name, falling back to the storage struct name for bare metadata
+    pub fn name(&self) -> String {
+        if self.contract.name.is_empty() {
+            metadata::get_contract_name(&self.metadata)
+        } else {
+            self.contract.name.clone()
+        }
+    }
+
+    /// Contract code, if this is a `.contract` bundle
+    pub fn wasm(&self) -> Option<&[u8]> {
+        self.source.wasm.as_deref()
+    }
+}
+
+fn parse_source(source: Option<JsonValue>) -> Result<BundleSource> {
+    let Some(source) = source else {
+        return Ok(BundleSource::default());
+    };
+
+    let mut parsed: BundleSource =
+        serde_json::from_value(source.clone()).context("Invalid `source` section")?;
+
+    if let Some(wasm) = source.get("wasm").and_then(|w| w.as_str()) {
+        parsed.wasm = Some(
+            hex::decode(wasm.strip_prefix("0x").unwrap_or(wasm))
+                .context("Invalid wasm hex in `source` section")?,
+        );
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{erc20, flipper, v4_json, AccountId};
+    use scale::Encode;
+
+    fn bundle_json(metadata: &InkProject) -> JsonValue {
+        let mut json = serde_json::to_value(metadata).unwrap();
+        json["source"] = json!({
+            "hash": format!("0x{}", hex::encode([7u8; 32])),
+            "language": "ink! 5.0.0",
+            "compiler": "rustc 1.78.0",
+            "wasm": "0x0061736d01000000",
+        });
+        json["contract"] = json!({
+            "name": "flipper",
+            "version": "0.1.0",
+            "authors": ["GLIN <dev@glin.ai>"],
+        });
+        json
+    }
+
+    #[test]
+    fn test_parse_v5_bundle() {
+        let bundle = ContractBundle::from_value(bundle_json(&flipper())).unwrap();
+
+        assert_eq!(bundle.version, MetadataVersion::V5);
+        assert_eq!(bundle.name(), "flipper");
+        assert_eq!(bundle.contract.version, "0.1.0");
+        assert_eq!(bundle.contract.authors, vec!["GLIN <dev@glin.ai>"]);
+        assert_eq!(bundle.source.language.as_deref(), Some("ink! 5.0.0"));
+        assert_eq!(bundle.source.code_hash(), Some([7u8; 32]));
+        assert_eq!(bundle.wasm(), Some(&[0, 0x61, 0x73, 0x6d, 1, 0, 0, 0][..]));
+        assert_eq!(
+            metadata::list_messages(&bundle.metadata),
+            ["flip", "get", "set"]
+        );
+
+        // Bare metadata without the bundle sections
+        let bare = serde_json::to_string(&erc20()).unwrap();
+        let bundle = ContractBundle::from_json(&bare).unwrap();
+        assert_eq!(bundle.wasm(), None);
+        assert_eq!(bundle.name(), "Erc20");
+    }
+
+    #[test]
+    fn test_upgrade_v4() {
+        let mut json = v4_json(&flipper());
+        json["source"] = bundle_json(&flipper())["source"].take();
+
+        let bundle = ContractBundle::from_value(json).unwrap();
+        assert_eq!(bundle.version, MetadataVersion::V4);
+        assert_eq!(*bundle.metadata.version(), 4);
+
+        let reparsed = ContractBundle::from_value(bundle.to_value().unwrap()).unwrap();
+        assert_eq!(reparsed.version, MetadataVersion::V4);
+        assert_eq!(reparsed.source.code_hash(), Some([7u8; 32]));
+        assert_eq!(metadata::get_contract_version(&bundle.metadata), "4");
+        assert_eq!(bundle.metadata.spec().messages().len(), 3);
+
+        let environment = bundle.metadata.spec().environment();
+        let balance = bundle
+            .metadata
+            .registry()
+            .resolve(environment.balance().ty().id);
+        assert!(matches!(
+            balance.map(|ty| &ty.type_def),
+            Some(scale_info::TypeDef::Primitive(
+                scale_info::TypeDefPrimitive::U128
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_upgrade_v3() {
+        let mut project = serde_json::to_value(flipper()).unwrap();
+        let project_map = project.as_object_mut().unwrap();
+        project_map.remove("version");
+        project_map.insert(
+            "storage".into(),
+            json!({ "struct": { "fields": [{ "name": "value", "layout": { "cell": { "key": format!("0x{}", "00".repeat(32)), "ty": 0 } } }] } }),
+        );
+        let spec = project_map["spec"].as_object_mut().unwrap();
+        spec.remove("lang_error");
+        spec.remove("environment");
+        for constructor in spec["constructors"].as_array_mut().unwrap() {
+            constructor.as_object_mut().unwrap().remove("returnType");
+        }
+        spec["messages"][0]["returnType"] = JsonValue::Null;
+
+        let json = json!({ "source": { "hash": "0x00" }, "V3": project });
+        let bundle = ContractBundle::from_value(json).unwrap();
+        assert_eq!(bundle.version, MetadataVersion::V3);
+
+        let flip = metadata::get_message_spec(&bundle.metadata, "flip").unwrap();
+        let unit = bundle
+            .metadata
+            .registry()
+            .resolve(flip.return_type().ret_type().ty().id);
+        assert!(matches!(
+            unit.map(|ty| &ty.type_def),
+            Some(scale_info::TypeDef::Tuple(tuple)) if tuple.fields.is_empty()
+        ));
+
+        assert!(ContractBundle::from_value(json!({ "V1": {} })).is_err());
+        assert!(ContractBundle::from_value(json!({ "version": 6 })).is_err());
+    }
+
+    #[test]
+    fn test_v4_bundle_events_decode_by_index() {
+        let json = serde_json::to_string(&v4_json(&erc20())).unwrap();
+        let bundle = ContractBundle::from_json(&json).unwrap();
+        assert_eq!(bundle.version, MetadataVersion::V4);
+
+        // ink! 4 events carry no signature topic; the payload starts with the
+        // event index, here `Approval`
+        let mut data = vec![1u8];
+        data.extend((AccountId([1u8; 32]), AccountId([2u8; 32]), 7u128).encode());
+        let event = crate::events::decode_contract_event(&bundle.metadata, &data, &[]).unwrap();
+
+        assert_eq!(event.name, "Approval");
+        assert_eq!(event.signature_topic, None);
+        assert_eq!(event.field("value"), Some(&json!("7")));
+    }
+}
@@ -0,0 +1,403 @@
+//! Contract message calls and read-only queries
+//!
+//! `query_contract` dry-runs a message through the `ContractsApi_call` runtime API
+//! and decodes its return value, `call_contract` submits `Contracts::call` for
+//! messages that mutate state.
+
+use anyhow::Result;
+use ink_metadata::{InkProject, TypeSpec};
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::dynamic::{self, Value};
+use subxt_signer::sr25519::Keypair;
+
+use glin_client::GlinClient;
+use glin_types::EventData;
+
+use crate::chain_info::parse_address;
+use crate::errors::{self, ContractError};
+use crate::gas::{EstimateOptions, GasEstimate};
+use crate::runtime_api::{self, CallDryRunResult, StorageDeposit};
+use crate::tx::{self, Weight};
+use crate::{encoding, metadata};
+
+/// Options for querying or calling a contract message
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// Balance transferred with the call
+    pub value: u128,
+    /// Gas limit for the call (estimated via dry-run if `None`)
+    pub gas_limit: Option<Weight>,
+    /// Maximum storage deposit the caller is willing to pay (estimated via dry-run if `None`)
+    pub storage_deposit_limit: Option<u128>,
+    /// Safety margin applied when limits are estimated
+    pub estimate: EstimateOptions,
+}
+
+/// Result of a read-only contract query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    /// Decoded return value of the message, `null` if the contract reverted
+    pub value: JsonValue,
+    /// Whether the contract reverted
+    pub reverted: bool,
+    /// Weight consumed by the execution
+    pub gas_consumed: Weight,
+    /// Weight required to execute successfully
+    pub gas_required: Weight,
+    /// Storage deposit the call would charge or refund
+    pub storage_deposit: StorageDeposit,
+    /// Debug message emitted by the contract
+    pub debug_message: String,
+    /// Decoded error if the contract reverted
+    pub error: Option<ContractError>,
+}
+
+/// Raw output of a read-only contract query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawQueryResult {
+    /// SCALE-encoded return value of the message
+    pub data: Vec<u8>,
+    /// Whether the contract reverted
+    pub reverted: bool,
+    /// Weight consumed by the execution
+    pub gas_consumed: Weight,
+    /// Weight required to execute successfully
+    pub gas_required: Weight,
+    /// Storage deposit the call would charge or refund
+    pub storage_deposit: StorageDeposit,
+    /// Debug message emitted by the contract
+    pub debug_message: String,
+}
+
+/// Result of a submitted contract call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallResult {
+    /// Hash of the block the extrinsic was included in
+    pub block_hash: String,
+    /// Hash of the call extrinsic
+    pub extrinsic_hash: String,
+    /// Weight consumed by the pre-flight dry-run
+    pub gas_consumed: Weight,
+    /// Storage deposit charged or refunded by the pre-flight dry-run
+    pub storage_deposit: StorageDeposit,
+    /// Debug message emitted during the pre-flight dry-run
+    pub debug_message: String,
+    /// Events emitted by the extrinsic
+    pub events: Vec<EventData>,
+}
+
+/// Query a contract message without submitting a transaction
+///
+/// The call is executed as a dry-run on the latest block with `origin`
+/// as the caller (the zero account if `None`).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use glin_client::create_client;
+/// use glin_contracts::{metadata, query_contract, CallOptions};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let client = create_client("ws://localhost:9944").await?;
+///     # let metadata_json = "{}";
+///     let metadata = metadata::parse_metadata(metadata_json)?;
+///
+///     let result = query_contract(
+///         &client,
+///         "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+///         &metadata,
+///         "get",
+///         &[],
+///         None,
+///         CallOptions::default(),
+///     )
+///     .await?;
+///
+///     println!("Result: {}", result.value);
+///     Ok(())
+/// }
+/// ```
+pub async fn query_contract(
+    client: &GlinClient,
+    contract_address: &str,
+    metadata: &InkProject,
+    message: &str,
+    args: &[String],
+    origin: Option<&str>,
+    options: CallOptions,
+) -> Result<QueryResult> {
+    let spec = metadata::get_message_spec(metadata, message)?;
+    let input_data = encode_message_call(metadata, message, args)?;
+
+    let raw = query_raw(client, contract_address, input_data, origin, options).await?;
+    decode_query_result(raw, metadata::get_message_return_type(spec), metadata)
+}
+
+/// Decode the return value of a query, or its revert into a [`ContractError`]
+///
+/// Revert data need not match the return type, so it is never decoded as one.
+fn decode_query_result(
+    raw: RawQueryResult,
+    return_type: &TypeSpec<PortableForm>,
+    metadata: &InkProject,
+) -> Result<QueryResult> {
+    let (value, error) = if raw.reverted {
+        (
+            JsonValue::Null,
+            Some(errors::decode_revert(&raw.data, return_type, metadata)),
+        )
+    } else {
+        (
+            encoding::decode_result(&raw.data, Some(return_type), metadata)?,
+            None,
+        )
+    };
+
+    Ok(QueryResult {
+        value,
+        reverted: raw.reverted,
+        gas_consumed: raw.gas_consumed,
+        gas_required: raw.gas_required,
+        storage_deposit: raw.storage_deposit,
+        debug_message: raw.debug_message,
+        error,
+    })
+}
+
+/// Query a contract with pre-encoded call data, returning the raw output
+///
+/// `input_data` is the message selector followed by the SCALE-encoded arguments.
+pub async fn query_raw(
+    client: &GlinClient,
+    contract_address: &str,
+    input_data: Vec<u8>,
+    origin: Option<&str>,
+    options: CallOptions,
+) -> Result<RawQueryResult> {
+    let origin = match origin {
+        Some(origin) => to_account_bytes(origin)?,
+        None => [0u8; 32],
+    };
+
+    let dry_run = runtime_api::dry_run_call(
+        client,
+        origin,
+        to_account_bytes(contract_address)?,
+        options.value,
+        options.gas_limit,
+        options.storage_deposit_limit,
+        input_data,
+    )
+    .await?;
+
+    let output = dry_run_output(&dry_run)?;
+
+    Ok(RawQueryResult {
+        data: output.data.clone(),
+        reverted: output.did_revert(),
+        gas_consumed: dry_run.gas_consumed,
+        gas_required: dry_run.gas_required,
+        storage_deposit: dry_run.storage_deposit,
+        debug_message: dry_run.debug_message.clone(),
+    })
+}
+
+/// Submit a `Contracts::call` extrinsic for a state-mutating message
+///
+/// The call is dry-run first so that contract reverts are reported before
+/// anything is submitted; missing limits are estimated from that dry-run.
+pub async fn call_contract(
+    client: &GlinClient,
+    signer: &Keypair,
+    contract_address: &str,
+    metadata: &InkProject,
+    message: &str,
+    args: &[String],
+    options: CallOptions,
+) -> Result<CallResult> {
+    let spec = metadata::get_message_spec(metadata, message)?;
+    if !metadata::is_message_mutable(spec) {
+        anyhow::bail!(
+            "Message '{}' does not mutate state; use query_contract instead",
+            message
+        );
+    }
+
+    let input_data = encode_message_call(metadata, message, args)?;
+
+    submit_call(
+        client,
+        signer,
+        contract_address,
+        input_data,
+        options,
+        |data| errors::decode_revert(data, metadata::get_message_return_type(spec), metadata),
+    )
+    .await
+}
+
+/// Submit a `Contracts::call` extrinsic with pre-encoded call data
+///
+/// Like [`call_contract`], the call is dry-run first; a revert is reported
+/// with the raw (hex) output of the contract.
+pub async fn call_raw(
+    client: &GlinClient,
+    signer: &Keypair,
+    contract_address: &str,
+    input_data: Vec<u8>,
+    options: CallOptions,
+) -> Result<CallResult> {
+    submit_call(
+        client,
+        signer,
+        contract_address,
+        input_data,
+        options,
+        ContractError::raw_revert,
+    )
+    .await
+}
+
+/// Dry-run a call, then submit it with the caller's or estimated limits
+async fn submit_call(
+    client: &GlinClient,
+    signer: &Keypair,
+    contract_address: &str,
+    input_data: Vec<u8>,
+    options: CallOptions,
+    decode_revert: impl FnOnce(&[u8]) -> ContractError,
+) -> Result<CallResult> {
+    let dest = to_account_bytes(contract_address)?;
+
+    let dry_run = runtime_api::dry_run_call(
+        client,
+        signer.public_key().0,
+        dest,
+        options.value,
+        options.gas_limit,
+        options.storage_deposit_limit,
+        input_data.clone(),
+    )
+    .await?;
+
+    let output = dry_run_output(&dry_run)?;
+    if output.did_revert() {
+        let err = decode_revert(&output.data);
+        let message = format!("Contract reverted during dry-run: {}", err);
+        return Err(anyhow::Error::new(err).context(message));
+    }
+
+    let (gas_limit, storage_deposit_limit) =
+        match (options.gas_limit, options.storage_deposit_limit) {
+            (Some(gas_limit), Some(deposit_limit)) => (gas_limit, deposit_limit),
+            (gas_limit, deposit_limit) => {
+                let estimate = GasEstimate::from_dry_run(&dry_run, &options.estimate)?;
+                (
+                    gas_limit.unwrap_or(estimate.gas_limit),
+                    deposit_limit.unwrap_or(estimate.storage_deposit_limit),
+                )
+            }
+        };
+
+    let call = dynamic::tx(
+        "Contracts",
+        "call",
+        vec![
+            Value::unnamed_variant("Id", [Value::from_bytes(dest)]),
+            Value::u128(options.value),
+            gas_limit.to_value(),
+            tx::storage_deposit_limit_value(Some(storage_deposit_limit)),
+            Value::from_bytes(input_data),
+        ],
+    );
+
+    let submitted = tx::submit_and_watch(client, signer, &call).await?;
+
+    Ok(CallResult {
+        block_hash: submitted.block_hash,
+        extrinsic_hash: submitted.extrinsic_hash,
+        gas_consumed: dry_run.gas_consumed,
+        storage_deposit: dry_run.storage_deposit,
+        debug_message: dry_run.debug_message,
+        events: tx::collect_events(&submitted.events, submitted.block_number)?,
+    })
+}
+
+/// Encode message selector followed by its SCALE-encoded arguments
+pub fn encode_message_call(
+    metadata: &InkProject,
+    message: &str,
+    args: &[String],
+) -> Result<Vec<u8>> {
+    let spec = metadata::get_message_spec(metadata, message)?;
+
+    let mut data = metadata::get_message_selector(spec).to_bytes().to_vec();
+    data.extend(encoding::encode_args(args, spec.args(), metadata)?);
+
+    Ok(data)
+}
+
+/// Extract the contract output from a dry-run, failing on dispatch errors
+fn dry_run_output(dry_run: &CallDryRunResult) -> Result<&runtime_api::ExecReturnValue> {
+    dry_run
+        .result
+        .as_ref()
+        .map_err(|err| errors::dry_run_error(err, &dry_run.debug_message, "Contract call failed"))
+}
+
+/// Parse an address into raw account bytes
+pub(crate) fn to_account_bytes(address: &str) -> Result<[u8; 32]> {
+    let bytes = parse_address(address)?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid account length for address: {}", address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{flipper, project_with};
+
+    #[test]
+    fn test_encode_message_call() {
+        let data = encode_message_call(&flipper(), "set", &["true".to_string()]).unwrap();
+        assert_eq!(data, vec![0xe8, 0xc4, 0x5e, 0xb6, 1]);
+
+        assert!(encode_message_call(&flipper(), "missing", &[]).is_err());
+    }
+
+    #[test]
+    fn test_to_account_bytes() {
+        let account = to_account_bytes("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        assert_eq!(account[0], 0xd4);
+        assert!(to_account_bytes("not-an-address").is_err());
+    }
+
+    #[test]
+    fn test_reverted_query_skips_return_value() {
+        let (metadata, _) = project_with::<u32>();
+        let return_type = metadata.spec().messages()[0].return_type().ret_type();
+        let raw = |data: Vec<u8>, reverted| RawQueryResult {
+            data,
+            reverted,
+            gas_consumed: Weight::new(0, 0),
+            gas_required: Weight::new(0, 0),
+            storage_deposit: StorageDeposit::Charge(0),
+            debug_message: String::new(),
+        };
+
+        // Empty revert data is not a valid `Result<u32, LangError>`
+        let result = decode_query_result(raw(vec![], true), return_type, &metadata).unwrap();
+        assert!(result.reverted);
+        assert_eq!(result.value, JsonValue::Null);
+        assert_eq!(result.error, Some(ContractError::raw_revert(&[])));
+
+        let result =
+            decode_query_result(raw(vec![0, 7, 0, 0, 0], false), return_type, &metadata).unwrap();
+        assert_eq!(result.value, serde_json::json!({ "Ok": 7 }));
+        assert_eq!(result.error, None);
+    }
+}
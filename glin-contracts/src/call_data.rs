@@ -0,0 +1,226 @@
+//! Contract call data decoding
+//!
+//! The reverse of argument encoding: matches the leading 4-byte selector of
+//! call data to a message or constructor of the contract's ink! metadata and
+//! decodes the remaining bytes into named JSON arguments. Works on the `data`
+//! of `Contracts::call` / `instantiate` extrinsics as well as on the input of
+//! cross-contract calls.
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::{encoding, metadata};
+
+type MessageParamSpec = ink_metadata::MessageParamSpec<PortableForm>;
+
+/// Whether call data targets a message or a constructor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CallKind {
+    Message,
+    Constructor,
+}
+
+/// Decoded contract call data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedCall {
+    /// Whether the selector belongs to a message or a constructor
+    pub kind: CallKind,
+    /// Message or constructor label from the metadata
+    pub label: String,
+    /// Selector of the message or constructor (hex)
+    pub selector: String,
+    /// Decoded arguments in declaration order
+    pub args: Vec<DecodedArg>,
+}
+
+impl DecodedCall {
+    /// Look up a decoded argument by name
+    pub fn arg(&self, name: &str) -> Option<&JsonValue> {
+        self.args
+            .iter()
+            .find(|arg| arg.name == name)
+            .map(|arg| &arg.value)
+    }
+
+    /// Arguments as a JSON object keyed by argument name
+    pub fn args_json(&self) -> JsonValue {
+        JsonValue::Object(
+            self.args
+                .iter()
+                .map(|arg| (arg.name.clone(), arg.value.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// A single decoded argument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedArg {
+    /// Argument label
+    pub name: String,
+    /// Decoded value
+    pub value: JsonValue,
+}
+
+/// Decode call data targeting either a message or a constructor
+///
+/// Messages are matched first; use [`decode_constructor_data`] for
+/// instantiation data if a constructor shares a selector with a message.
+pub fn decode_call_data(metadata: &InkProject, data: &[u8]) -> Result<DecodedCall> {
+    let selector = split_selector(data)?.0;
+
+    let is_message = metadata
+        .spec()
+        .messages()
+        .iter()
+        .any(|m| metadata::selector_bytes(metadata::get_message_selector(m)) == selector);
+
+    if is_message {
+        decode_message_data(metadata, data)
+    } else {
+        decode_constructor_data(metadata, data)
+    }
+}
+
+/// Decode call data of a message call
+pub fn decode_message_data(metadata: &InkProject, data: &[u8]) -> Result<DecodedCall> {
+    let (selector, input) = split_selector(data)?;
+
+    let message = metadata
+        .spec()
+        .messages()
+        .iter()
+        .find(|m| metadata::selector_bytes(metadata::get_message_selector(m)) == selector)
+        .ok_or_else(|| anyhow::anyhow!("No message with selector 0x{}", hex::encode(selector)))?;
+
+    Ok(DecodedCall {
+        kind: CallKind::Message,
+        label: message.label().clone(),
+        selector: format!("0x{}", hex::encode(selector)),
+        args: decode_args(metadata, message.label(), message.args(), input)?,
+    })
+}
+
+/// Decode the data of a contract instantiation
+pub fn decode_constructor_data(metadata: &InkProject, data: &[u8]) -> Result<DecodedCall> {
+    let (selector, input) = split_selector(data)?;
+
+    let constructor = metadata
+        .spec()
+        .constructors()
+        .iter()
+        .find(|c| metadata::selector_bytes(metadata::get_constructor_selector(c)) == selector)
+        .ok_or_else(|| {
+            anyhow::anyhow!("No constructor with selector 0x{}", hex::encode(selector))
+        })?;
+
+    Ok(DecodedCall {
+        kind: CallKind::Constructor,
+        label: constructor.label().clone(),
+        selector: format!("0x{}", hex::encode(selector)),
+        args: decode_args(metadata, constructor.label(), constructor.args(), input)?,
+    })
+}
+
+/// Decode hex-encoded call data (with or without `0x` prefix)
+pub fn decode_call_data_hex(metadata: &InkProject, data: &str) -> Result<DecodedCall> {
+    let bytes =
+        hex::decode(data.strip_prefix("0x").unwrap_or(data)).context("Invalid hex call data")?;
+    decode_call_data(metadata, &bytes)
+}
+
+fn split_selector(data: &[u8]) -> Result<([u8; 4], &[u8])> {
+    if data.len() < 4 {
+        anyhow::bail!(
+            "Call data too short: {} bytes (expected at least a 4-byte selector)",
+            data.len()
+        );
+    }
+
+    let (selector, input) = data.split_at(4);
+    Ok((selector.try_into()?, input))
+}
+
+fn decode_args(
+    metadata: &InkProject,
+    label: &str,
+    specs: &[MessageParamSpec],
+    mut input: &[u8],
+) -> Result<Vec<DecodedArg>> {
+    let mut args = Vec::with_capacity(specs.len());
+
+    for spec in specs {
+        let value =
+            encoding::decode_value(&mut input, spec.ty().ty().id, metadata).with_context(|| {
+                format!(
+                    "Failed to decode argument '{}' of '{}'",
+                    spec.label(),
+                    label
+                )
+            })?;
+
+        args.push(DecodedArg {
+            name: spec.label().clone(),
+            value,
+        });
+    }
+
+    if !input.is_empty() {
+        anyhow::bail!(
+            "Call data of '{}' has {} trailing bytes",
+            label,
+            input.len()
+        );
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::erc20;
+    use subxt::utils::AccountId32;
+
+    #[test]
+    fn test_decode_round_trip() {
+        let metadata = erc20();
+        let to = AccountId32([5u8; 32]).to_string();
+
+        let spec = metadata::get_message_spec(&metadata, "transfer").unwrap();
+        let mut data = metadata::selector_bytes(spec.selector()).to_vec();
+        data.extend(
+            encoding::encode_args(&[to.clone(), "1000".to_string()], spec.args(), &metadata)
+                .unwrap(),
+        );
+
+        let call = decode_call_data(&metadata, &data).unwrap();
+        assert_eq!(call.kind, CallKind::Message);
+        assert_eq!(call.label, "transfer");
+        assert_eq!(call.selector, "0x84a15da1");
+        assert_eq!(call.arg("to"), Some(&JsonValue::String(to)));
+        assert_eq!(call.args_json()["value"], "1000");
+
+        let hex_data = format!("0x{}", hex::encode(&data));
+        assert_eq!(decode_call_data_hex(&metadata, &hex_data).unwrap(), call);
+
+        // Constructor `new(init_value: bool)`
+        let call = decode_call_data(&metadata, &[0x9b, 0xae, 0x9d, 0x5e, 1]).unwrap();
+        assert_eq!(call.kind, CallKind::Constructor);
+        assert_eq!(call.arg("init_value"), Some(&JsonValue::Bool(true)));
+    }
+
+    #[test]
+    fn test_decode_invalid_call_data() {
+        let metadata = erc20();
+
+        assert!(decode_call_data(&metadata, &[0x84, 0xa1]).is_err());
+        assert!(decode_call_data(&metadata, &[0xff, 0xff, 0xff, 0xff]).is_err());
+        // Trailing bytes after `total_supply()`
+        assert!(decode_call_data(&metadata, &[0xdb, 0x63, 0x75, 0xa8, 0]).is_err());
+        assert!(decode_constructor_data(&metadata, &[0xdb, 0x63, 0x75, 0xa8]).is_err());
+    }
+}
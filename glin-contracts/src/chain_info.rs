@@ -1,16 +1,66 @@
 // Query contract and code information from blockchain storage
 
 use anyhow::{Context, Result};
+use scale_info::PortableRegistry;
 use subxt::dynamic;
+use subxt::ext::scale_value::{self, At, Composite, Primitive, ValueDef};
+use subxt::utils::AccountId32;
 use subxt_core::storage;
 
 use glin_client::GlinClient;
 
+/// Decoded storage value, with type IDs from the runtime metadata
+type Value = scale_value::Value<u32>;
+
 /// Contract information stored on-chain
 #[derive(Debug, Clone)]
 pub struct ContractInfo {
+    /// Unique ID of the contract's child trie
+    pub trie_id: Vec<u8>,
     pub code_hash: [u8; 32],
-    pub storage_deposit: u128,
+    /// Number of bytes used by the contract's storage items
+    pub storage_bytes: u32,
+    /// Number of storage items of the contract
+    pub storage_items: u32,
+    /// Deposit held for `storage_bytes`
+    pub storage_byte_deposit: u128,
+    /// Deposit held for `storage_items`
+    pub storage_item_deposit: u128,
+    /// Deposit held for the contract itself, independent of its storage
+    pub storage_base_deposit: u128,
+}
+
+/// Information about uploaded code stored on-chain
+#[derive(Debug, Clone)]
+pub struct CodeInfo {
+    /// Account that uploaded the code (SS58)
+    pub owner: String,
+    /// Deposit held for storing the code
+    pub deposit: u128,
+    /// Number of contracts instantiated from the code
+    pub refcount: u64,
+    /// Determinism of the code (`Enforced` or `Relaxed`), if the runtime tracks it
+    pub determinism: Option<String>,
+    /// Length of the uploaded wasm in bytes
+    pub code_len: u32,
+}
+
+/// Options for listing contracts
+#[derive(Debug, Clone, Default)]
+pub struct ListContractsOptions {
+    /// Number of matching contracts to skip
+    pub skip: usize,
+    /// Maximum number of contracts to return (all if not set)
+    pub limit: Option<usize>,
+}
+
+impl ContractInfo {
+    /// Total deposit held for the contract and its storage
+    pub fn total_deposit(&self) -> u128 {
+        self.storage_byte_deposit
+            .saturating_add(self.storage_item_deposit)
+            .saturating_add(self.storage_base_deposit)
+    }
 }
 
 /// Get contract info from blockchain storage
@@ -21,69 +71,274 @@ pub async fn get_contract_info(
     // Parse contract address to bytes
     let address_bytes = parse_address(contract_address)?;
 
-    // Create dynamic storage query for ContractInfoOf
+    // Fetch raw SCALE-encoded bytes from ContractInfoOf storage
+    let raw_bytes = fetch_raw_entry(client, "ContractInfoOf", address_bytes)
+        .await
+        .context("Failed to fetch contract info")?
+        .ok_or_else(|| anyhow::anyhow!("Contract not found at address: {}", contract_address))?;
+
+    // Decode the raw SCALE bytes using the ContractInfo type of the current runtime
+    let metadata = client.metadata();
+    let type_id = entry_value_type(&metadata, "ContractInfoOf")?;
+    decode_contract_info_from_bytes(&raw_bytes, type_id, metadata.types())
+}
+
+/// Check whether code with the given hash has already been uploaded
+pub async fn code_exists(client: &GlinClient, code_hash: &[u8; 32]) -> Result<bool> {
+    let raw_bytes = fetch_raw_entry(client, "CodeInfoOf", code_hash.to_vec())
+        .await
+        .context("Failed to fetch code info")?;
+
+    Ok(raw_bytes.is_some())
+}
+
+/// Get information about uploaded code from blockchain storage
+pub async fn get_code_info(client: &GlinClient, code_hash: &[u8; 32]) -> Result<CodeInfo> {
+    let raw_bytes = fetch_raw_entry(client, "CodeInfoOf", code_hash.to_vec())
+        .await
+        .context("Failed to fetch code info")?
+        .ok_or_else(|| anyhow::anyhow!("Code not found: 0x{}", hex::encode(code_hash)))?;
+
+    let metadata = client.metadata();
+    let type_id = entry_value_type(&metadata, "CodeInfoOf")?;
+    decode_code_info_from_bytes(&raw_bytes, type_id, metadata.types())
+}
+
+/// Get the wasm code uploaded with the given hash
+pub async fn get_pristine_code(client: &GlinClient, code_hash: &[u8; 32]) -> Result<Vec<u8>> {
+    use scale::Decode;
+
+    let raw_bytes = fetch_raw_entry(client, "PristineCode", code_hash.to_vec())
+        .await
+        .context("Failed to fetch pristine code")?
+        .ok_or_else(|| anyhow::anyhow!("Code not found: 0x{}", hex::encode(code_hash)))?;
+
+    // PristineCode is a BoundedVec<u8>, which encodes like Vec<u8>
+    Vec::<u8>::decode(&mut &raw_bytes[..]).context("Failed to decode pristine code")
+}
+
+/// List the addresses (SS58) of all contracts instantiated from the given code
+///
+/// Iterates over all `ContractInfoOf` entries, which the node returns in pages.
+/// Use `options` to page through the matching contracts.
+pub async fn list_contracts_by_code_hash(
+    client: &GlinClient,
+    code_hash: &[u8; 32],
+    options: ListContractsOptions,
+) -> Result<Vec<String>> {
+    let metadata = client.metadata();
+    let type_id = entry_value_type(&metadata, "ContractInfoOf")?;
+
+    let storage_addr = dynamic::storage("Contracts", "ContractInfoOf", ());
+    let mut entries = client
+        .storage()
+        .at_latest()
+        .await?
+        .iter(storage_addr)
+        .await
+        .context("Failed to iterate contract info")?;
+
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let mut skipped = 0;
+    let mut contracts = Vec::new();
+
+    while contracts.len() < limit {
+        let Some(entry) = entries.next().await else {
+            break;
+        };
+        let entry = entry.context("Failed to fetch contract info")?;
+
+        let info =
+            decode_contract_info_from_bytes(entry.value.encoded(), type_id, metadata.types())?;
+        if info.code_hash != *code_hash {
+            continue;
+        }
+
+        if skipped < options.skip {
+            skipped += 1;
+            continue;
+        }
+
+        contracts.push(contract_address_from_key(&entry.key_bytes)?);
+    }
+
+    Ok(contracts)
+}
+
+/// Contract address (SS58) from a `ContractInfoOf` storage key
+///
+/// The map uses a concatenating hasher, so the key ends with the raw account ID.
+fn contract_address_from_key(key: &[u8]) -> Result<String> {
+    let Some(start) = key.len().checked_sub(32) else {
+        anyhow::bail!("Storage key too short: {} bytes", key.len());
+    };
+
+    let mut account = [0u8; 32];
+    account.copy_from_slice(&key[start..]);
+    Ok(AccountId32(account).to_string())
+}
+
+/// Fetch a raw storage entry of the Contracts pallet keyed by a single value
+async fn fetch_raw_entry(
+    client: &GlinClient,
+    entry_name: &str,
+    key: Vec<u8>,
+) -> Result<Option<Vec<u8>>> {
+    // Create dynamic storage query for the entry
     let storage_addr = dynamic::storage(
         "Contracts",
-        "ContractInfoOf",
-        vec![dynamic::Value::from_bytes(address_bytes)],
+        entry_name,
+        vec![dynamic::Value::from_bytes(key)],
     );
 
-    // Get the storage key bytes for the address
+    // Get the storage key bytes for the entry
     let lookup_bytes = storage::get_address_bytes(&storage_addr, &client.metadata())
         .context("Failed to encode storage address")?;
 
-    // Fetch raw SCALE-encoded bytes from storage
     let raw_bytes = client
         .storage()
         .at_latest()
         .await?
         .fetch_raw(lookup_bytes)
-        .await
-        .context("Failed to fetch contract info")?
-        .ok_or_else(|| anyhow::anyhow!("Contract not found at address: {}", contract_address))?;
+        .await?;
 
-    // Decode the raw SCALE bytes into ContractInfo
-    decode_contract_info_from_bytes(&raw_bytes)
+    Ok(raw_bytes)
 }
 
-/// Decode ContractInfo from raw SCALE-encoded bytes
-///
-/// Note: For now, we'll extract the code_hash from the raw SCALE-encoded bytes.
-/// In a future version, we can use proper SCALE decoding with type registry.
-fn decode_contract_info_from_bytes(encoded: &[u8]) -> Result<ContractInfo> {
-    use scale::Decode;
+/// Value type ID of a Contracts pallet storage entry in the runtime metadata
+fn entry_value_type(metadata: &subxt::Metadata, entry_name: &str) -> Result<u32> {
+    let entry = metadata
+        .pallet_by_name("Contracts")
+        .and_then(|pallet| pallet.storage())
+        .and_then(|storage| storage.entry_by_name(entry_name))
+        .ok_or_else(|| anyhow::anyhow!("Storage entry Contracts::{} not found", entry_name))?;
+
+    Ok(entry.entry_type().value_ty())
+}
 
-    // For ContractInfo structure, we need to decode:
-    // struct ContractInfo {
-    //     code_hash: H256,         // 32 bytes
-    //     storage_deposit: u128,   // 16 bytes (compact encoded)
-    //     ...other fields
-    // }
+/// Decode a SCALE-encoded storage value of the given type
+fn decode_value(encoded: &[u8], type_id: u32, types: &PortableRegistry) -> Result<Value> {
+    let mut cursor = encoded;
+    let value = scale_value::scale::decode_as_type(&mut cursor, type_id, types)
+        .map_err(|e| anyhow::anyhow!("Failed to decode storage value: {}", e))?;
 
-    // Simple approach: extract first 32 bytes as code_hash
-    if encoded.len() < 32 {
+    if !cursor.is_empty() {
         anyhow::bail!(
-            "Encoded ContractInfo too short: {} bytes (expected at least 32)",
-            encoded.len()
+            "Storage value has {} trailing bytes after decoding",
+            cursor.len()
         );
     }
 
-    let mut code_hash = [0u8; 32];
-    code_hash.copy_from_slice(&encoded[0..32]);
+    Ok(value)
+}
 
-    // Decode storage_deposit (u128 after code_hash)
-    let mut cursor = &encoded[32..];
-    let storage_deposit =
-        u128::decode(&mut cursor).context("Failed to decode storage_deposit from ContractInfo")?;
+/// Decode ContractInfo from raw SCALE-encoded bytes
+///
+/// Fields are looked up by name, so the layout may change between runtime
+/// versions as long as the fields themselves are still present.
+fn decode_contract_info_from_bytes(
+    encoded: &[u8],
+    type_id: u32,
+    types: &PortableRegistry,
+) -> Result<ContractInfo> {
+    let value = decode_value(encoded, type_id, types).context("Invalid ContractInfo")?;
 
     Ok(ContractInfo {
-        code_hash,
-        storage_deposit,
+        trie_id: bytes_field(&value, "trie_id")?,
+        code_hash: hash_field(&value, "code_hash")?,
+        storage_bytes: u32_field(&value, "storage_bytes")?,
+        storage_items: u32_field(&value, "storage_items")?,
+        storage_byte_deposit: u128_field(&value, "storage_byte_deposit")?,
+        storage_item_deposit: u128_field(&value, "storage_item_deposit")?,
+        storage_base_deposit: u128_field(&value, "storage_base_deposit")?,
     })
 }
 
+/// Decode CodeInfo from raw SCALE-encoded bytes
+fn decode_code_info_from_bytes(
+    encoded: &[u8],
+    type_id: u32,
+    types: &PortableRegistry,
+) -> Result<CodeInfo> {
+    let value = decode_value(encoded, type_id, types).context("Invalid CodeInfo")?;
+
+    let determinism = value.at("determinism").and_then(|d| match &d.value {
+        ValueDef::Variant(variant) => Some(variant.name.clone()),
+        _ => None,
+    });
+
+    Ok(CodeInfo {
+        owner: AccountId32(hash_field(&value, "owner")?).to_string(),
+        deposit: u128_field(&value, "deposit")?,
+        refcount: u64::try_from(u128_field(&value, "refcount")?)
+            .map_err(|_| anyhow::anyhow!("Field 'refcount' does not fit in u64"))?,
+        determinism,
+        code_len: u32_field(&value, "code_len")?,
+    })
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value> {
+    value
+        .at(name)
+        .ok_or_else(|| anyhow::anyhow!("Field '{}' not found", name))
+}
+
+fn u128_field(value: &Value, name: &str) -> Result<u128> {
+    let field = field(value, name)?;
+    field
+        .as_u128()
+        .or_else(|| single_field(field).and_then(|inner| inner.as_u128()))
+        .ok_or_else(|| anyhow::anyhow!("Field '{}' is not an unsigned integer", name))
+}
+
+fn u32_field(value: &Value, name: &str) -> Result<u32> {
+    u32::try_from(u128_field(value, name)?)
+        .map_err(|_| anyhow::anyhow!("Field '{}' does not fit in u32", name))
+}
+
+fn bytes_field(value: &Value, name: &str) -> Result<Vec<u8>> {
+    value_to_bytes(field(value, name)?)
+        .ok_or_else(|| anyhow::anyhow!("Field '{}' is not a byte sequence", name))
+}
+
+fn hash_field(value: &Value, name: &str) -> Result<[u8; 32]> {
+    let bytes = bytes_field(value, name)?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Field '{}' has {} bytes (expected 32)", name, bytes.len()))
+}
+
+/// The only field of a newtype wrapper
+fn single_field(value: &Value) -> Option<&Value> {
+    match &value.value {
+        ValueDef::Composite(composite) if composite.len() == 1 => composite.values().next(),
+        _ => None,
+    }
+}
+
+/// Bytes of a `Vec<u8>`, `[u8; N]` or a newtype around one (e.g. `H256`, `BoundedVec`)
+fn value_to_bytes(value: &Value) -> Option<Vec<u8>> {
+    let ValueDef::Composite(composite) = &value.value else {
+        return None;
+    };
+
+    let bytes: Option<Vec<u8>> = composite
+        .values()
+        .map(|v| match &v.value {
+            ValueDef::Primitive(Primitive::U128(n)) => u8::try_from(*n).ok(),
+            _ => None,
+        })
+        .collect();
+
+    match (bytes, composite) {
+        (Some(bytes), Composite::Unnamed(_)) => Some(bytes),
+        _ => single_field(value).and_then(value_to_bytes),
+    }
+}
+
 /// Parse contract address to bytes
-fn parse_address(address: &str) -> Result<Vec<u8>> {
+pub(crate) fn parse_address(address: &str) -> Result<Vec<u8>> {
     // Remove "0x" prefix if present
     let address = address.strip_prefix("0x").unwrap_or(address);
 
@@ -96,7 +351,6 @@ fn parse_address(address: &str) -> Result<Vec<u8>> {
 
     // Try SS58 decoding (Substrate addresses)
     use std::str::FromStr;
-    use subxt::utils::AccountId32;
 
     let account = AccountId32::from_str(address)
         .context("Invalid contract address format (expected hex or SS58)")?;
@@ -123,4 +377,107 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 32);
     }
+
+    #[test]
+    fn test_decode_contract_info() {
+        use scale::Encode;
+        use scale_info::{MetaType, Registry, TypeInfo};
+
+        // Mirrors pallet-contracts' ContractInfo
+        #[derive(Encode, TypeInfo)]
+        struct H256([u8; 32]);
+
+        #[derive(Encode, TypeInfo)]
+        struct BoundedVec(Vec<u8>);
+
+        #[derive(Encode, TypeInfo)]
+        struct RawContractInfo {
+            trie_id: BoundedVec,
+            code_hash: H256,
+            storage_bytes: u32,
+            storage_items: u32,
+            storage_byte_deposit: u128,
+            storage_item_deposit: u128,
+            storage_base_deposit: u128,
+            delegate_dependencies: Vec<(H256, u128)>,
+        }
+
+        let mut registry = Registry::new();
+        let type_id = registry
+            .register_type(&MetaType::new::<RawContractInfo>())
+            .id;
+        let types: PortableRegistry = registry.into();
+
+        let encoded = RawContractInfo {
+            trie_id: BoundedVec(vec![7, 8, 9]),
+            code_hash: H256([0xab; 32]),
+            storage_bytes: 120,
+            storage_items: 3,
+            storage_byte_deposit: 1_000,
+            storage_item_deposit: 200,
+            storage_base_deposit: 30,
+            delegate_dependencies: vec![(H256([1; 32]), 5)],
+        }
+        .encode();
+
+        let info = decode_contract_info_from_bytes(&encoded, type_id, &types).unwrap();
+        assert_eq!(info.trie_id, vec![7, 8, 9]);
+        assert_eq!(info.code_hash, [0xab; 32]);
+        assert_eq!(info.storage_bytes, 120);
+        assert_eq!(info.storage_items, 3);
+        assert_eq!(info.total_deposit(), 1_230);
+
+        assert!(decode_contract_info_from_bytes(&encoded[..40], type_id, &types).is_err());
+    }
+
+    #[test]
+    fn test_decode_code_info() {
+        use scale::{Compact, Encode};
+        use scale_info::{MetaType, Registry, TypeInfo};
+
+        // Mirrors pallet-contracts' CodeInfo
+        #[derive(Encode, TypeInfo)]
+        struct AccountId32([u8; 32]);
+
+        #[allow(dead_code)]
+        #[derive(Encode, TypeInfo)]
+        enum Determinism {
+            Enforced,
+            Relaxed,
+        }
+
+        #[derive(Encode, TypeInfo)]
+        struct RawCodeInfo {
+            owner: AccountId32,
+            #[codec(compact)]
+            deposit: u128,
+            refcount: Compact<u64>,
+            determinism: Determinism,
+            code_len: u32,
+        }
+
+        let mut registry = Registry::new();
+        let type_id = registry.register_type(&MetaType::new::<RawCodeInfo>()).id;
+        let types: PortableRegistry = registry.into();
+
+        let encoded = RawCodeInfo {
+            owner: AccountId32([1; 32]),
+            deposit: 5_000,
+            refcount: Compact(2),
+            determinism: Determinism::Relaxed,
+            code_len: 1_024,
+        }
+        .encode();
+
+        let info = decode_code_info_from_bytes(&encoded, type_id, &types).unwrap();
+        assert_eq!(info.owner, subxt::utils::AccountId32([1; 32]).to_string());
+        assert_eq!(info.deposit, 5_000);
+        assert_eq!(info.refcount, 2);
+        assert_eq!(info.determinism.as_deref(), Some("Relaxed"));
+        assert_eq!(info.code_len, 1_024);
+
+        let mut key = vec![0u8; 40];
+        key.extend_from_slice(&[1; 32]);
+        assert_eq!(contract_address_from_key(&key).unwrap(), info.owner);
+    }
 }
@@ -0,0 +1,415 @@
+//! Contract deployment
+//!
+//! Deploys ink! contracts from a `.contract` bundle or from WASM + metadata.
+//! Code that is not yet on-chain is uploaded with `Contracts::instantiate_with_code`,
+//! otherwise the existing code is reused via `Contracts::instantiate`.
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use scale::Decode;
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sp_core_hashing::blake2_256;
+use std::sync::atomic::{AtomicU64, Ordering};
+use subxt::dynamic::{self, Value};
+use subxt::utils::AccountId32;
+use subxt_signer::sr25519::Keypair;
+
+use glin_client::GlinClient;
+use glin_types::EventData;
+
+use crate::bundle::ContractBundle;
+use crate::gas::{self, EstimateOptions};
+use crate::runtime_api::Code;
+use crate::tx::{self, Weight};
+use crate::{encoding, metadata};
+
+type ConstructorSpec = ink_metadata::ConstructorSpec<PortableForm>;
+
+/// Options for deploying a contract
+#[derive(Debug, Clone, Default)]
+pub struct DeployOptions {
+    /// Constructor to call (defaults to `new`, or the first constructor)
+    pub constructor: Option<String>,
+    /// Balance transferred to the new contract
+    pub value: u128,
+    /// Salt used to derive the contract address (defaults to the current
+    /// timestamp combined with a process-wide counter)
+    pub salt: Option<Vec<u8>>,
+    /// Gas limit for the instantiation (estimated via dry-run if `None`)
+    pub gas_limit: Option<Weight>,
+    /// Maximum storage deposit the caller is willing to pay (estimated via dry-run if `None`)
+    pub storage_deposit_limit: Option<u128>,
+    /// Safety margin applied when limits are estimated
+    pub estimate: EstimateOptions,
+}
+
+/// Result of a successful deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployResult {
+    /// Address of the new contract (SS58)
+    pub contract_address: String,
+    /// Code hash of the instantiated code (hex)
+    pub code_hash: String,
+    /// Hash of the block the extrinsic was included in
+    pub block_hash: String,
+    /// Hash of the instantiation extrinsic
+    pub extrinsic_hash: String,
+    /// Events emitted by the extrinsic
+    pub events: Vec<EventData>,
+}
+
+/// Deploy a contract from a `.contract` bundle file
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use glin_client::{create_client, get_dev_account};
+/// use glin_contracts::{deploy_contract, DeployOptions};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let client = create_client("ws://localhost:9944").await?;
+///     let signer = get_dev_account("alice")?;
+///
+///     let result = deploy_contract(
+///         &client,
+///         &signer,
+///         "target/ink/flipper.contract",
+///         &["true".to_string()],
+///         DeployOptions::default(),
+///     )
+///     .await?;
+///
+///     println!("Contract deployed at: {}", result.contract_address);
+///     Ok(())
+/// }
+/// ```
+pub async fn deploy_contract(
+    client: &GlinClient,
+    signer: &Keypair,
+    bundle_path: &str,
+    args: &[String],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let (wasm, metadata) = read_bundle(bundle_path)?;
+
+    deploy_wasm(client, signer, &wasm, &metadata, args, options).await
+}
+
+/// Deploy a contract from a `.contract` bundle file with JSON constructor arguments
+///
+/// Like [`deploy_contract`], with arguments encoded by [`encoding::encode_args_json`].
+pub async fn deploy_contract_json(
+    client: &GlinClient,
+    signer: &Keypair,
+    bundle_path: &str,
+    args: &[JsonValue],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let (wasm, metadata) = read_bundle(bundle_path)?;
+
+    deploy_wasm_json(client, signer, &wasm, &metadata, args, options).await
+}
+
+/// Deploy a contract from WASM code and its metadata
+///
+/// Uploads the code with `instantiate_with_code` unless code with the same
+/// hash is already stored on-chain, in which case `instantiate` is used.
+pub async fn deploy_wasm(
+    client: &GlinClient,
+    signer: &Keypair,
+    wasm: &[u8],
+    metadata: &InkProject,
+    args: &[String],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let data = encode_constructor_call(metadata, options.constructor.as_deref(), args)?;
+
+    deploy_wasm_with_data(client, signer, wasm, data, options).await
+}
+
+/// Deploy a contract from WASM code and its metadata with JSON constructor arguments
+pub async fn deploy_wasm_json(
+    client: &GlinClient,
+    signer: &Keypair,
+    wasm: &[u8],
+    metadata: &InkProject,
+    args: &[JsonValue],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let data = encode_constructor_call_json(metadata, options.constructor.as_deref(), args)?;
+
+    deploy_wasm_with_data(client, signer, wasm, data, options).await
+}
+
+/// Deploy WASM code with pre-encoded constructor call data
+///
+/// `data` is the constructor selector followed by the SCALE-encoded arguments;
+/// `options.constructor` is ignored.
+pub async fn deploy_wasm_with_data(
+    client: &GlinClient,
+    signer: &Keypair,
+    wasm: &[u8],
+    data: Vec<u8>,
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let code_hash = blake2_256(wasm);
+
+    if crate::chain_info::code_exists(client, &code_hash).await? {
+        return instantiate_with_data(client, signer, &code_hash, data, options).await;
+    }
+
+    let salt = options.salt.clone().unwrap_or_else(default_salt);
+    let (gas_limit, storage_deposit_limit) = resolve_limits(
+        client,
+        signer,
+        &options,
+        Code::Upload(wasm.to_vec()),
+        &data,
+        &salt,
+    )
+    .await?;
+
+    let call = dynamic::tx(
+        "Contracts",
+        "instantiate_with_code",
+        vec![
+            Value::u128(options.value),
+            gas_limit.to_value(),
+            tx::storage_deposit_limit_value(storage_deposit_limit),
+            Value::from_bytes(wasm),
+            Value::from_bytes(data),
+            Value::from_bytes(salt),
+        ],
+    );
+
+    submit_instantiation(client, signer, &call, code_hash).await
+}
+
+/// Instantiate a contract from code that is already stored on-chain
+pub async fn instantiate(
+    client: &GlinClient,
+    signer: &Keypair,
+    code_hash: &[u8; 32],
+    metadata: &InkProject,
+    args: &[String],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let data = encode_constructor_call(metadata, options.constructor.as_deref(), args)?;
+
+    instantiate_with_data(client, signer, code_hash, data, options).await
+}
+
+/// Instantiate on-chain code with JSON constructor arguments
+pub async fn instantiate_json(
+    client: &GlinClient,
+    signer: &Keypair,
+    code_hash: &[u8; 32],
+    metadata: &InkProject,
+    args: &[JsonValue],
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let data = encode_constructor_call_json(metadata, options.constructor.as_deref(), args)?;
+
+    instantiate_with_data(client, signer, code_hash, data, options).await
+}
+
+/// Instantiate on-chain code with pre-encoded constructor call data
+///
+/// `options.constructor` is ignored.
+pub async fn instantiate_with_data(
+    client: &GlinClient,
+    signer: &Keypair,
+    code_hash: &[u8; 32],
+    data: Vec<u8>,
+    options: DeployOptions,
+) -> Result<DeployResult> {
+    let salt = options.salt.clone().unwrap_or_else(default_salt);
+    let (gas_limit, storage_deposit_limit) = resolve_limits(
+        client,
+        signer,
+        &options,
+        Code::Existing(*code_hash),
+        &data,
+        &salt,
+    )
+    .await?;
+
+    let call = dynamic::tx(
+        "Contracts",
+        "instantiate",
+        vec![
+            Value::u128(options.value),
+            gas_limit.to_value(),
+            tx::storage_deposit_limit_value(storage_deposit_limit),
+            Value::from_bytes(code_hash),
+            Value::from_bytes(data),
+            Value::from_bytes(salt),
+        ],
+    );
+
+    submit_instantiation(client, signer, &call, *code_hash).await
+}
+
+/// Use the caller's limits, estimating any that are missing with a dry-run
+async fn resolve_limits(
+    client: &GlinClient,
+    signer: &Keypair,
+    options: &DeployOptions,
+    code: Code,
+    data: &[u8],
+    salt: &[u8],
+) -> Result<(Weight, Option<u128>)> {
+    if let (Some(gas_limit), Some(deposit_limit)) =
+        (options.gas_limit, options.storage_deposit_limit)
+    {
+        return Ok((gas_limit, Some(deposit_limit)));
+    }
+
+    let estimate = gas::estimate_instantiate(
+        client,
+        signer.public_key().0,
+        options.value,
+        code,
+        data.to_vec(),
+        salt.to_vec(),
+        &options.estimate,
+    )
+    .await?;
+
+    Ok((
+        options.gas_limit.unwrap_or(estimate.gas_limit),
+        Some(
+            options
+                .storage_deposit_limit
+                .unwrap_or(estimate.storage_deposit_limit),
+        ),
+    ))
+}
+
+/// Submit an instantiation extrinsic and extract the new contract address
+async fn submit_instantiation(
+    client: &GlinClient,
+    signer: &Keypair,
+    call: &subxt::tx::DynamicPayload,
+    code_hash: [u8; 32],
+) -> Result<DeployResult> {
+    let submitted = tx::submit_and_watch(client, signer, call).await?;
+
+    let mut contract_address = None;
+    for event in submitted.events.iter() {
+        let event = event?;
+        if event.pallet_name() == "Contracts" && event.variant_name() == "Instantiated" {
+            let (_deployer, contract) = <([u8; 32], [u8; 32])>::decode(&mut event.field_bytes())
+                .context("Failed to decode Instantiated event")?;
+            contract_address = Some(AccountId32(contract).to_string());
+        }
+    }
+
+    let contract_address = contract_address
+        .ok_or_else(|| anyhow::anyhow!("No Contracts::Instantiated event found"))?;
+
+    Ok(DeployResult {
+        contract_address,
+        code_hash: format!("0x{}", hex::encode(code_hash)),
+        block_hash: submitted.block_hash,
+        extrinsic_hash: submitted.extrinsic_hash,
+        events: tx::collect_events(&submitted.events, submitted.block_number)?,
+    })
+}
+
+/// Encode constructor selector followed by its SCALE-encoded arguments
+pub fn encode_constructor_call(
+    metadata: &InkProject,
+    constructor: Option<&str>,
+    args: &[String],
+) -> Result<Vec<u8>> {
+    let spec = constructor_spec(metadata, constructor)?;
+
+    let mut data = metadata::get_constructor_selector(spec).to_bytes().to_vec();
+    data.extend(encoding::encode_args(args, spec.args(), metadata)?);
+
+    Ok(data)
+}
+
+/// Encode constructor selector followed by JSON arguments
+pub fn encode_constructor_call_json(
+    metadata: &InkProject,
+    constructor: Option<&str>,
+    args: &[JsonValue],
+) -> Result<Vec<u8>> {
+    let spec = constructor_spec(metadata, constructor)?;
+
+    let mut data = metadata::get_constructor_selector(spec).to_bytes().to_vec();
+    data.extend(encoding::encode_args_json(args, spec.args(), metadata)?);
+
+    Ok(data)
+}
+
+/// The named constructor, or the default one
+fn constructor_spec<'a>(
+    metadata: &'a InkProject,
+    constructor: Option<&str>,
+) -> Result<&'a ConstructorSpec> {
+    match constructor {
+        Some(name) => metadata::get_constructor_spec(metadata, name),
+        None => metadata::get_default_constructor(metadata),
+    }
+}
+
+/// Read WASM code and metadata from a `.contract` bundle
+fn read_bundle(path: &str) -> Result<(Vec<u8>, InkProject)> {
+    let bundle = ContractBundle::from_file(path)?;
+    let wasm = bundle
+        .source
+        .wasm
+        .ok_or_else(|| anyhow::anyhow!("No WASM in bundle: {}", path))?;
+
+    Ok((wasm, bundle.metadata))
+}
+
+/// Salt derived from the current time and a process-wide counter so repeated
+/// deployments get new addresses, even within one clock tick
+fn default_salt() -> Vec<u8> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    let mut salt = nanos.to_le_bytes().to_vec();
+    salt.extend(count.to_le_bytes());
+    salt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::erc20;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_bundle_missing_file() {
+        assert!(read_bundle("/nonexistent/contract.contract").is_err());
+    }
+
+    #[test]
+    fn test_encode_constructor_call_json() {
+        let metadata = erc20();
+        let json = encode_constructor_call_json(&metadata, None, &[json!(true)]).unwrap();
+        let text = encode_constructor_call(&metadata, None, &["true".to_string()]).unwrap();
+        assert_eq!(json, text);
+
+        assert!(encode_constructor_call_json(&metadata, Some("missing"), &[]).is_err());
+        assert!(encode_constructor_call_json(&metadata, None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_default_salt_is_unique() {
+        assert_ne!(default_salt(), default_salt());
+    }
+}
@@ -2,180 +2,505 @@
 
 use anyhow::{Context, Result};
 use ink_metadata::InkProject;
+use primitive_types::U256;
 use scale::{Decode, Encode};
-use scale_info::{form::PortableForm, TypeDef, TypeDefPrimitive};
+use scale_info::{form::PortableForm, Field, TypeDef, TypeDefBitSequence, TypeDefPrimitive};
 use serde_json::Value as JsonValue;
+use subxt::ext::scale_bits::scale::{
+    decode_using_format_from, encode_using_format,
+    format::{Format, OrderFormat, StoreFormat},
+};
 use subxt::utils::AccountId32;
 
 // Type aliases for PortableForm specs
 type MessageParamSpec = ink_metadata::MessageParamSpec<PortableForm>;
 type TypeSpec = ink_metadata::TypeSpec<PortableForm>;
 
+/// Error raised while encoding a JSON argument
+///
+/// `path` points at the offending value, e.g. `args[1].owner.id`. Callers can
+/// recover it with `anyhow::Error::downcast_ref::<EncodeError>()`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Failed to encode {path}: {message}")]
+pub struct EncodeError {
+    /// JSON path of the value that failed to encode
+    pub path: String,
+    /// What went wrong
+    pub message: String,
+}
+
+/// Build an [`EncodeError`] for the value at `path`
+fn error_at(path: &str, message: impl std::fmt::Display) -> anyhow::Error {
+    EncodeError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+    .into()
+}
+
 /// Encode contract arguments based on their types from metadata
+///
+/// Arguments for `String` parameters are always taken literally. Other
+/// arguments are interpreted as JSON when they look like JSON (an object,
+/// array, quoted string or `null`), and as a plain string otherwise, so that
+/// e.g. `42`, `true` and SS58 addresses can be passed unquoted. Use
+/// [`encode_args_json`] to pass JSON values directly.
 pub fn encode_args(
     args: &[String],
     param_specs: &[MessageParamSpec],
     metadata: &InkProject,
 ) -> Result<Vec<u8>> {
-    if args.len() != param_specs.len() {
+    check_arg_count(args.len(), param_specs)?;
+
+    let values: Vec<JsonValue> = args
+        .iter()
+        .zip(param_specs)
+        .map(|(arg, param)| arg_to_json(arg, param.ty().ty().id, metadata))
+        .collect();
+    encode_args_json(&values, param_specs, metadata)
+}
+
+/// Encode contract arguments given as JSON values
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use glin_contracts::{encoding, metadata};
+/// use serde_json::json;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// # let metadata_json = "{}";
+/// let metadata = metadata::parse_metadata(metadata_json)?;
+/// let spec = metadata::get_message_spec(&metadata, "transfer")?;
+///
+/// let encoded = encoding::encode_args_json(
+///     &[json!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"), json!("1000")],
+///     spec.args(),
+///     &metadata,
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_args_json(
+    args: &[JsonValue],
+    param_specs: &[MessageParamSpec],
+    metadata: &InkProject,
+) -> Result<Vec<u8>> {
+    check_arg_count(args.len(), param_specs)?;
+
+    let mut encoded = Vec::new();
+
+    for (i, (arg, param)) in args.iter().zip(param_specs.iter()).enumerate() {
+        let path = format!("args[{}]", i);
+        encoded.extend(encode_at(arg, param.ty().ty().id, metadata, &path)?);
+    }
+
+    Ok(encoded)
+}
+
+fn check_arg_count(count: usize, param_specs: &[MessageParamSpec]) -> Result<()> {
+    if count != param_specs.len() {
         anyhow::bail!(
             "Argument count mismatch: expected {}, got {}",
             param_specs.len(),
-            args.len()
+            count
         );
     }
+    Ok(())
+}
 
-    let mut encoded = Vec::new();
+/// Encode a single JSON value as the type with the given registry ID
+pub fn encode_value(value: &JsonValue, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
+    encode_at(value, type_id, metadata, "value")
+}
 
-    for (arg_str, param) in args.iter().zip(param_specs.iter()) {
-        // Get type ID from param
-        let type_id = param.ty().ty().id;
-        let arg_bytes = encode_value_by_id(arg_str, type_id, metadata)?;
-        encoded.extend_from_slice(&arg_bytes);
+/// Interpret a string argument for the type with the given registry ID
+///
+/// Strings (and newtypes around them) are taken literally; other types are
+/// parsed as JSON if the argument looks like JSON, otherwise taken as a string.
+fn arg_to_json(arg: &str, type_id: u32, metadata: &InkProject) -> JsonValue {
+    if is_string_type(type_id, metadata) {
+        return JsonValue::String(arg.to_string());
     }
 
-    Ok(encoded)
+    let trimmed = arg.trim();
+    let looks_like_json = trimmed == "null"
+        || trimmed.starts_with('{')
+        || trimmed.starts_with('[')
+        || trimmed.starts_with('"');
+
+    if looks_like_json {
+        if let Ok(json) = serde_json::from_str(trimmed) {
+            return json;
+        }
+    }
+
+    JsonValue::String(arg.to_string())
+}
+
+/// Whether a type is `str`, or a newtype struct wrapping one
+///
+/// Structs with a named field are encoded from a JSON object, so only
+/// unnamed newtypes count, as in [`encode_at`].
+fn is_string_type(type_id: u32, metadata: &InkProject) -> bool {
+    match metadata.registry().resolve(type_id).map(|ty| &ty.type_def) {
+        Some(TypeDef::Primitive(TypeDefPrimitive::Str)) => true,
+        Some(TypeDef::Composite(composite))
+            if composite.fields.len() == 1 && composite.fields[0].name.is_none() =>
+        {
+            is_string_type(composite.fields[0].ty.id, metadata)
+        }
+        _ => false,
+    }
 }
 
-/// Encode a single value based on its type ID
-fn encode_value_by_id(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
+/// Encode a value at `path` based on its type ID
+fn encode_at(
+    value: &JsonValue,
+    type_id: u32,
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
     let registry = metadata.registry();
 
     let ty = registry
         .resolve(type_id)
-        .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", type_id))?;
+        .ok_or_else(|| error_at(path, format!("type {} not found in registry", type_id)))?;
 
-    // Access type_def field directly (not deprecated)
     match &ty.type_def {
-        TypeDef::Primitive(prim) => encode_primitive(value_str, prim),
-        TypeDef::Composite(_) => encode_composite(value_str, type_id, metadata),
-        TypeDef::Variant(_) => encode_variant(value_str, type_id, metadata),
-        TypeDef::Sequence(_) => encode_sequence(value_str, type_id, metadata),
-        TypeDef::Array(_) => encode_array(value_str, type_id, metadata),
-        TypeDef::Tuple(_) => encode_tuple(value_str, type_id, metadata),
+        TypeDef::Primitive(prim) => encode_primitive(value, prim).map_err(|e| error_at(path, e)),
+        TypeDef::Composite(_) => encode_composite(value, type_id, metadata, path),
+        TypeDef::Variant(_) => encode_variant(value, type_id, metadata, path),
+        TypeDef::Sequence(seq) => {
+            let array = expect_array(value, path)?;
+
+            // Length prefix followed by each element
+            let mut encoded = scale::Compact(array.len() as u32).encode();
+            for (i, element) in array.iter().enumerate() {
+                let element_path = format!("{}[{}]", path, i);
+                encoded.extend(encode_at(
+                    element,
+                    seq.type_param.id,
+                    metadata,
+                    &element_path,
+                )?);
+            }
+
+            Ok(encoded)
+        }
+        TypeDef::Array(arr) => {
+            let array = expect_array(value, path)?;
+            if array.len() != arr.len as usize {
+                return Err(error_at(
+                    path,
+                    format!(
+                        "array length mismatch: expected {}, got {}",
+                        arr.len,
+                        array.len()
+                    ),
+                ));
+            }
+
+            let mut encoded = Vec::new();
+            for (i, element) in array.iter().enumerate() {
+                let element_path = format!("{}[{}]", path, i);
+                encoded.extend(encode_at(
+                    element,
+                    arr.type_param.id,
+                    metadata,
+                    &element_path,
+                )?);
+            }
+
+            Ok(encoded)
+        }
+        TypeDef::Tuple(tuple) => {
+            // Unit type `()` has no data
+            if tuple.fields.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let array = expect_array(value, path)?;
+            if array.len() != tuple.fields.len() {
+                return Err(error_at(
+                    path,
+                    format!(
+                        "tuple length mismatch: expected {}, got {}",
+                        tuple.fields.len(),
+                        array.len()
+                    ),
+                ));
+            }
+
+            let mut encoded = Vec::new();
+            for (i, (element, field_ty)) in array.iter().zip(&tuple.fields).enumerate() {
+                let element_path = format!("{}[{}]", path, i);
+                encoded.extend(encode_at(element, field_ty.id, metadata, &element_path)?);
+            }
+
+            Ok(encoded)
+        }
         TypeDef::Compact(_) => {
-            // Compact encoding - parse as number and use compact encoding
-            let num: u128 = value_str
-                .parse()
-                .context("Failed to parse compact value as number")?;
+            let num: u128 = integer_string(value)
+                .and_then(|s| s.parse().context("Failed to parse compact value as number"))
+                .map_err(|e| error_at(path, format!("{:#}", e)))?;
             Ok(scale::Compact(num).encode())
         }
-        TypeDef::BitSequence(_) => {
-            anyhow::bail!("BitSequence encoding not yet supported")
-        }
+        TypeDef::BitSequence(bits) => encode_bit_sequence(value, bits, metadata)
+            .map_err(|e| error_at(path, format!("{:#}", e))),
+    }
+}
+
+/// Expect a JSON array at `path`
+fn expect_array<'a>(value: &'a JsonValue, path: &str) -> Result<&'a Vec<JsonValue>> {
+    value
+        .as_array()
+        .ok_or_else(|| error_at(path, format!("expected JSON array, got {}", value)))
+}
+
+/// Text of an integer given as a JSON number or string
+fn integer_string(value: &JsonValue) -> Result<String> {
+    match value {
+        JsonValue::Number(n) => Ok(n.to_string()),
+        JsonValue::String(s) => Ok(s.trim().to_string()),
+        other => anyhow::bail!("expected integer, got {}", other),
     }
 }
 
 /// Encode primitive types
-fn encode_primitive(value_str: &str, prim: &TypeDefPrimitive) -> Result<Vec<u8>> {
+fn encode_primitive(value: &JsonValue, prim: &TypeDefPrimitive) -> Result<Vec<u8>> {
     match prim {
         TypeDefPrimitive::Bool => {
-            let val: bool = value_str.parse().context("Failed to parse boolean")?;
+            let val = match value {
+                JsonValue::Bool(b) => *b,
+                JsonValue::String(s) => s.trim().parse().context("Failed to parse boolean")?,
+                other => anyhow::bail!("expected boolean, got {}", other),
+            };
             Ok(val.encode())
         }
         TypeDefPrimitive::Char => {
-            let val: char = value_str
-                .chars()
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("Empty char value"))?;
+            let val: char = value
+                .as_str()
+                .and_then(|s| s.chars().next())
+                .ok_or_else(|| anyhow::anyhow!("expected a non-empty string for char"))?;
             Ok((val as u32).encode())
         }
-        TypeDefPrimitive::Str => Ok(value_str.to_string().encode()),
-        TypeDefPrimitive::U8 => {
-            let val: u8 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::U16 => {
-            let val: u16 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::U32 => {
-            let val: u32 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::U64 => {
-            let val: u64 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::U128 => {
-            let val: u128 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::U256 => {
-            anyhow::bail!("U256 encoding not yet supported")
-        }
-        TypeDefPrimitive::I8 => {
-            let val: i8 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::I16 => {
-            let val: i16 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::I32 => {
-            let val: i32 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::I64 => {
-            let val: i64 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::I128 => {
-            let val: i128 = value_str.parse()?;
-            Ok(val.encode())
-        }
-        TypeDefPrimitive::I256 => {
-            anyhow::bail!("I256 encoding not yet supported")
+        TypeDefPrimitive::Str => match value {
+            JsonValue::String(s) => Ok(s.encode()),
+            JsonValue::Number(_) | JsonValue::Bool(_) => Ok(value.to_string().encode()),
+            other => anyhow::bail!("expected string, got {}", other),
+        },
+        TypeDefPrimitive::U8 => Ok(integer_string(value)?.parse::<u8>()?.encode()),
+        TypeDefPrimitive::U16 => Ok(integer_string(value)?.parse::<u16>()?.encode()),
+        TypeDefPrimitive::U32 => Ok(integer_string(value)?.parse::<u32>()?.encode()),
+        TypeDefPrimitive::U64 => Ok(integer_string(value)?.parse::<u64>()?.encode()),
+        TypeDefPrimitive::U128 => Ok(integer_string(value)?.parse::<u128>()?.encode()),
+        TypeDefPrimitive::U256 => Ok(parse_u256(&integer_string(value)?)?
+            .to_little_endian()
+            .to_vec()),
+        TypeDefPrimitive::I8 => Ok(integer_string(value)?.parse::<i8>()?.encode()),
+        TypeDefPrimitive::I16 => Ok(integer_string(value)?.parse::<i16>()?.encode()),
+        TypeDefPrimitive::I32 => Ok(integer_string(value)?.parse::<i32>()?.encode()),
+        TypeDefPrimitive::I64 => Ok(integer_string(value)?.parse::<i64>()?.encode()),
+        TypeDefPrimitive::I128 => Ok(integer_string(value)?.parse::<i128>()?.encode()),
+        TypeDefPrimitive::I256 => Ok(parse_i256(&integer_string(value)?)?
+            .to_little_endian()
+            .to_vec()),
+    }
+}
+
+/// Parse a 256-bit unsigned integer from a decimal or `0x`-prefixed hex string
+fn parse_u256(value_str: &str) -> Result<U256> {
+    let parsed = match value_str
+        .strip_prefix("0x")
+        .or_else(|| value_str.strip_prefix("0X"))
+    {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|e| anyhow::anyhow!("{:?}", e)),
+        None => U256::from_dec_str(value_str).map_err(|e| anyhow::anyhow!("{:?}", e)),
+    };
+
+    parsed.with_context(|| format!("Invalid U256 value: {}", value_str))
+}
+
+/// Parse a 256-bit signed integer into its two's complement representation
+///
+/// Decimal input may carry a leading `-`; hex input is taken as the raw
+/// two's complement bits.
+fn parse_i256(value_str: &str) -> Result<U256> {
+    let min_magnitude = U256::one() << 255;
+
+    if let Some(magnitude) = value_str.strip_prefix('-') {
+        let magnitude = parse_u256(magnitude)?;
+        if magnitude > min_magnitude {
+            anyhow::bail!("I256 value out of range: {}", value_str);
         }
+        return Ok((!magnitude).overflowing_add(U256::one()).0);
     }
+
+    let value = parse_u256(value_str)?;
+    if !value_str.starts_with("0x") && !value_str.starts_with("0X") && value >= min_magnitude {
+        anyhow::bail!("I256 value out of range: {}", value_str);
+    }
+
+    Ok(value)
+}
+
+/// Encode a bit sequence from a JSON bool array or a binary string like `"1011"`
+///
+/// The first element or character is bit 0 of the sequence.
+fn encode_bit_sequence(
+    value: &JsonValue,
+    bits: &TypeDefBitSequence<PortableForm>,
+    metadata: &InkProject,
+) -> Result<Vec<u8>> {
+    let format = bit_sequence_format(bits, metadata)?;
+
+    let values: Vec<bool> = match value {
+        JsonValue::String(s) => s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                other => anyhow::bail!("Invalid character in binary string: {:?}", other),
+            })
+            .collect::<Result<_>>()?,
+        JsonValue::Array(array) => array
+            .iter()
+            .map(|bit| {
+                bit.as_bool()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid bit value: {}", bit))
+            })
+            .collect::<Result<_>>()?,
+        other => anyhow::bail!("expected JSON bool array or binary string, got {}", other),
+    };
+
+    Ok(encode_using_format(values.into_iter(), format))
+}
+
+/// Resolve the store and order types of a bit sequence into a `scale_bits` format
+fn bit_sequence_format(
+    bits: &TypeDefBitSequence<PortableForm>,
+    metadata: &InkProject,
+) -> Result<Format> {
+    let registry = metadata.registry();
+
+    let store_ty = registry
+        .resolve(bits.bit_store_type.id)
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found", bits.bit_store_type.id))?;
+    let store = match &store_ty.type_def {
+        TypeDef::Primitive(TypeDefPrimitive::U8) => StoreFormat::U8,
+        TypeDef::Primitive(TypeDefPrimitive::U16) => StoreFormat::U16,
+        TypeDef::Primitive(TypeDefPrimitive::U32) => StoreFormat::U32,
+        TypeDef::Primitive(TypeDefPrimitive::U64) => StoreFormat::U64,
+        other => anyhow::bail!("Unsupported bit sequence store type: {:?}", other),
+    };
+
+    let order_ty = registry
+        .resolve(bits.bit_order_type.id)
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found", bits.bit_order_type.id))?;
+    let order = match order_ty.path.segments.last().map(|s| s.as_str()) {
+        Some("Lsb0") => OrderFormat::Lsb0,
+        Some("Msb0") => OrderFormat::Msb0,
+        other => anyhow::bail!("Unsupported bit sequence order type: {:?}", other),
+    };
+
+    Ok(Format::new(store, order))
 }
 
 /// Encode composite types (structs)
-fn encode_composite(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
+///
+/// Named fields are read from a JSON object, tuple struct fields from a JSON
+/// array, and newtypes take the inner value directly.
+fn encode_composite(
+    value: &JsonValue,
+    type_id: u32,
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
     let registry = metadata.registry();
     let ty = registry
         .resolve(type_id)
-        .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
+        .ok_or_else(|| error_at(path, format!("type {} not found", type_id)))?;
 
     // Check if this is an AccountId or AccountId32 (special case)
     let last_segment = ty.path.segments.last().map(|s| s.as_str());
     if last_segment == Some("AccountId32") || last_segment == Some("AccountId") {
-        return encode_account_id(value_str);
+        return encode_account_id(value).map_err(|e| error_at(path, e));
     }
 
-    // Try to parse as JSON for complex types
-    let json: JsonValue =
-        serde_json::from_str(value_str).context("Failed to parse composite value as JSON")?;
+    let TypeDef::Composite(composite) = &ty.type_def else {
+        return Err(error_at(path, "expected composite type"));
+    };
 
-    if let TypeDef::Composite(composite) = &ty.type_def {
-        let mut encoded = Vec::new();
+    // Unit structs have no data
+    if composite.fields.is_empty() {
+        return Ok(Vec::new());
+    }
 
-        for field in &composite.fields {
-            let field_name = field
-                .name
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("Unnamed field in composite"))?;
+    // Newtype structs take the inner value directly
+    if composite.fields.len() == 1 && composite.fields[0].name.is_none() {
+        return encode_at(value, composite.fields[0].ty.id, metadata, path);
+    }
 
-            let field_value = json
-                .get(field_name)
-                .ok_or_else(|| anyhow::anyhow!("Missing field: {}", field_name))?
-                .to_string();
+    encode_fields(value, &composite.fields, metadata, path)
+}
 
-            let field_type_id = field.ty.id;
-            let field_bytes = encode_value_by_id(&field_value, field_type_id, metadata)?;
-            encoded.extend_from_slice(&field_bytes);
-        }
+/// Encode struct or variant fields from a JSON array in declaration order or,
+/// for named fields, from a JSON object keyed by field name
+fn encode_fields(
+    value: &JsonValue,
+    fields: &[Field<PortableForm>],
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
+    let named = fields.iter().all(|field| field.name.is_some());
+    let mut encoded = Vec::new();
 
-        Ok(encoded)
-    } else {
-        anyhow::bail!("Expected composite type")
+    for (i, field) in fields.iter().enumerate() {
+        let (field_value, field_path) = match (&field.name, value) {
+            (Some(field_name), JsonValue::Object(object)) => {
+                let field_path = format!("{}.{}", path, field_name);
+                let field_value = object
+                    .get(field_name)
+                    .ok_or_else(|| error_at(&field_path, "missing field"))?;
+                (field_value, field_path)
+            }
+            (_, JsonValue::Array(array)) => {
+                let field_path = format!("{}[{}]", path, i);
+                let field_value = array
+                    .get(i)
+                    .ok_or_else(|| error_at(&field_path, "missing field"))?;
+                (field_value, field_path)
+            }
+            _ if named => {
+                return Err(error_at(
+                    path,
+                    format!("expected JSON object or array, got {}", value),
+                ))
+            }
+            _ => {
+                return Err(error_at(
+                    path,
+                    format!("expected JSON array, got {}", value),
+                ))
+            }
+        };
+
+        encoded.extend(encode_at(field_value, field.ty.id, metadata, &field_path)?);
     }
+
+    Ok(encoded)
 }
 
-/// Encode AccountId32
-fn encode_account_id(value_str: &str) -> Result<Vec<u8>> {
+/// Encode AccountId32 from an SS58 address or a 32-byte hex string
+fn encode_account_id(value: &JsonValue) -> Result<Vec<u8>> {
     use std::str::FromStr;
 
+    let value_str = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected address string, got {}", value))?;
+
     // Try parsing as SS58 address
     if let Ok(account_id) = AccountId32::from_str(value_str) {
         return Ok(account_id.0.encode());
@@ -194,285 +519,863 @@ fn encode_account_id(value_str: &str) -> Result<Vec<u8>> {
 }
 
 /// Encode variant types (enums, Option, Result)
-fn encode_variant(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
+fn encode_variant(
+    value: &JsonValue,
+    type_id: u32,
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
     let registry = metadata.registry();
     let ty = registry
         .resolve(type_id)
-        .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
+        .ok_or_else(|| error_at(path, format!("type {} not found", type_id)))?;
 
     // Check for common types: Option, Result
     let type_name = ty.path.segments.last().map(|s| s.as_str());
 
     match type_name {
-        Some("Option") => encode_option(value_str, type_id, metadata),
-        Some("Result") => encode_result(value_str, type_id, metadata),
+        Some("Option") => encode_option(value, type_id, metadata, path),
+        Some("Result") => encode_result(value, type_id, metadata, path),
         _ => {
-            // Generic enum
-            let json: JsonValue =
-                serde_json::from_str(value_str).context("Failed to parse variant as JSON")?;
-
-            if let Some(variant_name) = json.get("variant").and_then(|v| v.as_str()) {
-                if let TypeDef::Variant(variant_def) = &ty.type_def {
-                    let variant = variant_def
-                        .variants
-                        .iter()
-                        .find(|v| v.name == variant_name)
-                        .ok_or_else(|| anyhow::anyhow!("Variant {} not found", variant_name))?;
-
-                    let mut encoded = Vec::new();
-                    encoded.push(variant.index);
-
-                    // Encode variant fields if any
-                    if let Some(fields_json) = json.get("fields") {
-                        for (i, field) in variant.fields.iter().enumerate() {
-                            let field_value = fields_json
-                                .get(i)
-                                .ok_or_else(|| anyhow::anyhow!("Missing variant field {}", i))?
-                                .to_string();
-
-                            let field_type_id = field.ty.id;
-                            let field_bytes =
-                                encode_value_by_id(&field_value, field_type_id, metadata)?;
-                            encoded.extend_from_slice(&field_bytes);
-                        }
-                    }
-
-                    return Ok(encoded);
-                }
+            // Generic enum: {"variant": name, "fields": [..]}
+            let variant_name = value
+                .get("variant")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    error_at(
+                        path,
+                        format!(
+                            "expected {{\"variant\": .., \"fields\": [..]}}, got {}",
+                            value
+                        ),
+                    )
+                })?;
+
+            let TypeDef::Variant(variant_def) = &ty.type_def else {
+                return Err(error_at(path, "expected variant type"));
+            };
+
+            let variant = variant_def
+                .variants
+                .iter()
+                .find(|v| v.name == variant_name)
+                .ok_or_else(|| error_at(path, format!("variant {} not found", variant_name)))?;
+
+            let mut encoded = vec![variant.index];
+
+            if !variant.fields.is_empty() {
+                let fields_path = format!("{}.fields", path);
+                let fields_value = value
+                    .get("fields")
+                    .ok_or_else(|| error_at(&fields_path, "missing field"))?;
+                encoded.extend(encode_fields(
+                    fields_value,
+                    &variant.fields,
+                    metadata,
+                    &fields_path,
+                )?);
             }
 
-            anyhow::bail!("Invalid variant encoding")
+            Ok(encoded)
         }
     }
 }
 
 /// Encode Option type
-fn encode_option(value_str: &str, _type_id: u32, _metadata: &InkProject) -> Result<Vec<u8>> {
-    if value_str == "null" || value_str.is_empty() {
-        // None variant (index 0)
-        Ok(vec![0u8])
-    } else {
-        // Some variant (index 1) + encoded value
-        let mut encoded = vec![1u8];
-        encoded.extend_from_slice(&value_str.to_string().encode());
-        Ok(encoded)
-    }
-}
-
-/// Encode Result type
-fn encode_result(value_str: &str, _type_id: u32, _metadata: &InkProject) -> Result<Vec<u8>> {
-    let json: JsonValue =
-        serde_json::from_str(value_str).context("Failed to parse Result as JSON")?;
-
-    if json.get("Ok").is_some() {
-        // Ok variant (index 0)
-        let mut encoded = vec![0u8];
-        let ok_value = json.get("Ok").unwrap().to_string();
-        encoded.extend_from_slice(&ok_value.encode());
-        Ok(encoded)
-    } else if json.get("Err").is_some() {
-        // Err variant (index 1)
-        let mut encoded = vec![1u8];
-        let err_value = json.get("Err").unwrap().to_string();
-        encoded.extend_from_slice(&err_value.encode());
-        Ok(encoded)
-    } else {
-        anyhow::bail!("Invalid Result format")
+///
+/// Accepts `null` or `{"None": null}` for `None`, and either `{"Some": value}`
+/// or a bare value for `Some`, so `""` is `Some("")` for `Option<String>`.
+/// The inner value is encoded using the `T` type parameter, so nested options
+/// work as well.
+fn encode_option(
+    value: &JsonValue,
+    type_id: u32,
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
+    let (some_index, inner_type_id) =
+        variant_field(type_id, "Some", metadata).map_err(|e| error_at(path, e))?;
+    let (none_index, _) =
+        variant_field(type_id, "None", metadata).map_err(|e| error_at(path, e))?;
+
+    let (inner, inner_path) = match value {
+        JsonValue::Null => return Ok(vec![none_index]),
+        JsonValue::Object(object) if object.len() == 1 && object.contains_key("None") => {
+            return Ok(vec![none_index]);
+        }
+        JsonValue::Object(object) if object.len() == 1 && object.contains_key("Some") => {
+            (&object["Some"], format!("{}.Some", path))
+        }
+        other => (other, path.to_string()),
+    };
+
+    // Some variant + encoded inner value
+    let mut encoded = vec![some_index];
+    if let Some(inner_type_id) = inner_type_id {
+        encoded.extend(encode_at(inner, inner_type_id, metadata, &inner_path)?);
     }
+    Ok(encoded)
 }
 
-/// Encode sequence (Vec)
-fn encode_sequence(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
-    let json: JsonValue =
-        serde_json::from_str(value_str).context("Failed to parse sequence as JSON array")?;
+/// Encode Result type from `{"Ok": value}` or `{"Err": value}`
+fn encode_result(
+    value: &JsonValue,
+    type_id: u32,
+    metadata: &InkProject,
+    path: &str,
+) -> Result<Vec<u8>> {
+    let (variant_name, inner) = if let Some(ok_value) = value.get("Ok") {
+        ("Ok", ok_value)
+    } else if let Some(err_value) = value.get("Err") {
+        ("Err", err_value)
+    } else {
+        return Err(error_at(
+            path,
+            format!("expected {{\"Ok\": ..}} or {{\"Err\": ..}}, got {}", value),
+        ));
+    };
 
-    let array = json
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Expected JSON array"))?;
+    let (index, inner_type_id) =
+        variant_field(type_id, variant_name, metadata).map_err(|e| error_at(path, e))?;
 
-    let registry = metadata.registry();
-    let ty = registry
+    let mut encoded = vec![index];
+    if let Some(inner_type_id) = inner_type_id {
+        let inner_path = format!("{}.{}", path, variant_name);
+        encoded.extend(encode_at(inner, inner_type_id, metadata, &inner_path)?);
+    }
+    Ok(encoded)
+}
+
+/// Look up a variant by name, returning its index and the type of its single field
+fn variant_field(
+    type_id: u32,
+    variant_name: &str,
+    metadata: &InkProject,
+) -> Result<(u8, Option<u32>)> {
+    let ty = metadata
+        .registry()
         .resolve(type_id)
         .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
 
-    if let TypeDef::Sequence(seq) = &ty.type_def {
-        let element_type_id = seq.type_param.id;
+    let TypeDef::Variant(variant_def) = &ty.type_def else {
+        anyhow::bail!("Expected variant type");
+    };
 
-        // Encode length as compact
-        let mut encoded = scale::Compact(array.len() as u32).encode();
+    let variant = variant_def
+        .variants
+        .iter()
+        .find(|v| v.name == variant_name)
+        .ok_or_else(|| anyhow::anyhow!("Variant {} not found", variant_name))?;
 
-        // Encode each element
-        for element in array {
-            let element_str = element.to_string();
-            let element_bytes = encode_value_by_id(&element_str, element_type_id, metadata)?;
-            encoded.extend_from_slice(&element_bytes);
-        }
+    Ok((variant.index, variant.fields.first().map(|f| f.ty.id)))
+}
 
-        Ok(encoded)
+/// Decode query result based on return type
+pub fn decode_result(
+    bytes: &[u8],
+    type_spec: Option<&TypeSpec>,
+    metadata: &InkProject,
+) -> Result<JsonValue> {
+    if let Some(spec) = type_spec {
+        let type_id = spec.ty().id;
+        decode_value_by_id(bytes, type_id, metadata)
     } else {
-        anyhow::bail!("Expected sequence type")
+        // No return type (void)
+        Ok(JsonValue::Null)
     }
 }
 
-/// Encode array
-fn encode_array(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
-    let json: JsonValue =
-        serde_json::from_str(value_str).context("Failed to parse array as JSON")?;
-
-    let array = json
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Expected JSON array"))?;
+/// Decode a value based on its type ID
+fn decode_value_by_id(bytes: &[u8], type_id: u32, metadata: &InkProject) -> Result<JsonValue> {
+    let mut input = bytes;
+    decode_value(&mut input, type_id, metadata)
+}
 
+/// Decode a value from the front of `input`, advancing it past the consumed bytes
+///
+/// The JSON shapes mirror what `encode_value` accepts.
+pub(crate) fn decode_value(
+    input: &mut &[u8],
+    type_id: u32,
+    metadata: &InkProject,
+) -> Result<JsonValue> {
     let registry = metadata.registry();
+
     let ty = registry
         .resolve(type_id)
-        .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", type_id))?;
 
-    if let TypeDef::Array(arr_def) = &ty.type_def {
-        let element_type_id = arr_def.type_param.id;
+    match &ty.type_def {
+        TypeDef::Primitive(prim) => decode_primitive(input, prim),
+        TypeDef::Composite(_) => decode_composite(input, type_id, metadata),
+        TypeDef::Variant(_) => decode_variant(input, type_id, metadata),
+        TypeDef::Sequence(seq) => {
+            let len = scale::Compact::<u32>::decode(input)
+                .context("Failed to decode sequence length")?
+                .0 as usize;
 
-        if array.len() != arr_def.len as usize {
-            anyhow::bail!(
-                "Array length mismatch: expected {}, got {}",
-                arr_def.len,
-                array.len()
-            );
+            // The length is untrusted: a sequence whose elements cannot fit in
+            // the remaining input is invalid and must not be allocated.
+            // Zero-sized elements (e.g. `Vec<()>`) take no input at all.
+            let element_size = min_encoded_size(seq.type_param.id, metadata);
+            if element_size > 0 && len > input.len() / element_size {
+                anyhow::bail!(
+                    "Sequence length {} exceeds the {} remaining input bytes",
+                    len,
+                    input.len()
+                );
+            }
+
+            let mut elements = Vec::with_capacity(len.min(input.len()));
+            for _ in 0..len {
+                elements.push(decode_value(input, seq.type_param.id, metadata)?);
+            }
+
+            Ok(JsonValue::Array(elements))
         }
+        TypeDef::Array(arr) => {
+            let mut elements = Vec::with_capacity(arr.len as usize);
+            for _ in 0..arr.len {
+                elements.push(decode_value(input, arr.type_param.id, metadata)?);
+            }
 
-        let mut encoded = Vec::new();
+            Ok(JsonValue::Array(elements))
+        }
+        TypeDef::Tuple(tuple) => {
+            if tuple.fields.is_empty() {
+                return Ok(JsonValue::Null);
+            }
+
+            let mut elements = Vec::with_capacity(tuple.fields.len());
+            for field_ty in &tuple.fields {
+                elements.push(decode_value(input, field_ty.id, metadata)?);
+            }
 
-        for element in array {
-            let element_str = element.to_string();
-            let element_bytes = encode_value_by_id(&element_str, element_type_id, metadata)?;
-            encoded.extend_from_slice(&element_bytes);
+            Ok(JsonValue::Array(elements))
         }
+        TypeDef::Compact(compact) => decode_compact(input, compact.type_param.id, metadata),
+        TypeDef::BitSequence(bits) => decode_bit_sequence(input, bits, metadata),
+    }
+}
 
-        Ok(encoded)
-    } else {
-        anyhow::bail!("Expected array type")
+/// Smallest number of bytes any value of a type encodes to
+fn min_encoded_size(type_id: u32, metadata: &InkProject) -> usize {
+    let Some(ty) = metadata.registry().resolve(type_id) else {
+        return 0;
+    };
+
+    match &ty.type_def {
+        TypeDef::Primitive(prim) => match prim {
+            TypeDefPrimitive::Bool
+            | TypeDefPrimitive::Str
+            | TypeDefPrimitive::U8
+            | TypeDefPrimitive::I8 => 1,
+            TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+            TypeDefPrimitive::Char | TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 4,
+            TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+            TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+            TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+        },
+        TypeDef::Composite(composite) => composite
+            .fields
+            .iter()
+            .map(|field| min_encoded_size(field.ty.id, metadata))
+            .sum(),
+        TypeDef::Tuple(tuple) => tuple
+            .fields
+            .iter()
+            .map(|field| min_encoded_size(field.id, metadata))
+            .sum(),
+        TypeDef::Array(arr) => {
+            (arr.len as usize).saturating_mul(min_encoded_size(arr.type_param.id, metadata))
+        }
+        // Variant index, compact length or compact value
+        TypeDef::Variant(_)
+        | TypeDef::Sequence(_)
+        | TypeDef::Compact(_)
+        | TypeDef::BitSequence(_) => 1,
     }
 }
 
-/// Encode tuple
-fn encode_tuple(value_str: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
-    let json: JsonValue =
-        serde_json::from_str(value_str).context("Failed to parse tuple as JSON array")?;
+/// Decode a bit sequence into a JSON bool array
+fn decode_bit_sequence(
+    input: &mut &[u8],
+    bits: &TypeDefBitSequence<PortableForm>,
+    metadata: &InkProject,
+) -> Result<JsonValue> {
+    let format = bit_sequence_format(bits, metadata)?;
+    let decoder =
+        decode_using_format_from(input, format).context("Failed to decode bit sequence")?;
+    let encoded_size = decoder.encoded_size();
+
+    let values = decoder
+        .map(|bit| bit.map(JsonValue::Bool))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to decode bit sequence")?;
 
-    let array = json
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Expected JSON array for tuple"))?;
+    *input = &input[encoded_size..];
+    Ok(JsonValue::Array(values))
+}
 
+/// Decode composite types (structs)
+fn decode_composite(input: &mut &[u8], type_id: u32, metadata: &InkProject) -> Result<JsonValue> {
     let registry = metadata.registry();
     let ty = registry
         .resolve(type_id)
         .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
 
-    if let TypeDef::Tuple(tuple_def) = &ty.type_def {
-        if array.len() != tuple_def.fields.len() {
-            anyhow::bail!("Tuple length mismatch");
-        }
-
-        let mut encoded = Vec::new();
-
-        for (element, field_ty) in array.iter().zip(&tuple_def.fields) {
-            let element_str = element.to_string();
-            let element_bytes = encode_value_by_id(&element_str, field_ty.id, metadata)?;
-            encoded.extend_from_slice(&element_bytes);
-        }
+    // AccountId is rendered as an SS58 address
+    let last_segment = ty.path.segments.last().map(|s| s.as_str());
+    if last_segment == Some("AccountId32") || last_segment == Some("AccountId") {
+        let bytes = <[u8; 32]>::decode(input).context("Failed to decode AccountId")?;
+        return Ok(JsonValue::String(AccountId32(bytes).to_string()));
+    }
 
-        Ok(encoded)
+    if let TypeDef::Composite(composite) = &ty.type_def {
+        decode_fields(input, &composite.fields, metadata)
     } else {
-        anyhow::bail!("Expected tuple type")
+        anyhow::bail!("Expected composite type")
     }
 }
 
-/// Decode query result based on return type
-pub fn decode_result(
-    bytes: &[u8],
-    type_spec: Option<&TypeSpec>,
+/// Decode struct fields: named fields become an object, a single unnamed
+/// field is unwrapped, and multiple unnamed fields become an array
+fn decode_fields(
+    input: &mut &[u8],
+    fields: &[Field<PortableForm>],
     metadata: &InkProject,
 ) -> Result<JsonValue> {
-    if let Some(spec) = type_spec {
-        let type_id = spec.ty().id;
-        decode_value_by_id(bytes, type_id, metadata)
-    } else {
-        // No return type (void)
-        Ok(JsonValue::Null)
+    if fields.is_empty() {
+        return Ok(JsonValue::Null);
     }
+
+    if fields.iter().all(|f| f.name.is_some()) {
+        let mut object = serde_json::Map::new();
+        for field in fields {
+            let name = field.name.clone().unwrap_or_default();
+            object.insert(name, decode_value(input, field.ty.id, metadata)?);
+        }
+        return Ok(JsonValue::Object(object));
+    }
+
+    if fields.len() == 1 {
+        return decode_value(input, fields[0].ty.id, metadata);
+    }
+
+    let mut elements = Vec::with_capacity(fields.len());
+    for field in fields {
+        elements.push(decode_value(input, field.ty.id, metadata)?);
+    }
+
+    Ok(JsonValue::Array(elements))
 }
 
-/// Decode a value based on its type ID
-fn decode_value_by_id(bytes: &[u8], type_id: u32, metadata: &InkProject) -> Result<JsonValue> {
+/// Decode variant types (enums, Option, Result)
+fn decode_variant(input: &mut &[u8], type_id: u32, metadata: &InkProject) -> Result<JsonValue> {
     let registry = metadata.registry();
-
     let ty = registry
         .resolve(type_id)
-        .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", type_id))?;
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found", type_id))?;
 
-    match &ty.type_def {
-        TypeDef::Primitive(prim) => decode_primitive(bytes, prim),
-        TypeDef::Composite(_) => {
-            // For simplicity, return hex for complex types
-            Ok(JsonValue::String(format!("0x{}", hex::encode(bytes))))
+    let TypeDef::Variant(variant_def) = &ty.type_def else {
+        anyhow::bail!("Expected variant type");
+    };
+
+    let index = u8::decode(input).context("Failed to decode variant index")?;
+    let variant = variant_def
+        .variants
+        .iter()
+        .find(|v| v.index == index)
+        .ok_or_else(|| anyhow::anyhow!("Variant index {} not found in type {}", index, type_id))?;
+
+    let type_name = ty.path.segments.last().map(|s| s.as_str());
+
+    match (type_name, variant.name.as_str()) {
+        // Option is unwrapped: None becomes null, Some(x) becomes x, unless x
+        // is an option itself; then Some(x) is {"Some": x} to keep Some(None)
+        (Some("Option"), "None") => Ok(JsonValue::Null),
+        (Some("Option"), "Some") => {
+            let value = decode_fields(input, &variant.fields, metadata)?;
+            match variant.fields.first() {
+                Some(field) if is_option_type(field.ty.id, metadata) => {
+                    Ok(serde_json::json!({ "Some": value }))
+                }
+                _ => Ok(value),
+            }
         }
-        _ => {
-            // For other types, return as hex
-            Ok(JsonValue::String(format!("0x{}", hex::encode(bytes))))
+        // Result keeps its variant as a single key
+        (Some("Result"), name @ ("Ok" | "Err")) => {
+            let value = decode_fields(input, &variant.fields, metadata)?;
+            Ok(serde_json::json!({ name: value }))
         }
+        // Generic enum: named fields as an object, unnamed fields as an
+        // array, as accepted by the encoder
+        (_, name) => {
+            let fields = if variant.fields.iter().any(|f| f.name.is_some()) {
+                let mut object = serde_json::Map::new();
+                for field in &variant.fields {
+                    let field_name = field.name.clone().unwrap_or_default();
+                    object.insert(field_name, decode_value(input, field.ty.id, metadata)?);
+                }
+                JsonValue::Object(object)
+            } else {
+                let mut elements = Vec::with_capacity(variant.fields.len());
+                for field in &variant.fields {
+                    elements.push(decode_value(input, field.ty.id, metadata)?);
+                }
+                JsonValue::Array(elements)
+            };
+
+            Ok(serde_json::json!({
+                "variant": name,
+                "fields": fields,
+            }))
+        }
+    }
+}
+
+/// Whether a type is an `Option`
+fn is_option_type(type_id: u32, metadata: &InkProject) -> bool {
+    metadata.registry().resolve(type_id).is_some_and(|ty| {
+        matches!(ty.type_def, TypeDef::Variant(_))
+            && ty.path.segments.last().map(|s| s.as_str()) == Some("Option")
+    })
+}
+
+/// Decode compact-encoded values
+fn decode_compact(
+    input: &mut &[u8],
+    inner_type_id: u32,
+    metadata: &InkProject,
+) -> Result<JsonValue> {
+    let registry = metadata.registry();
+    let inner = registry
+        .resolve(inner_type_id)
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found", inner_type_id))?;
+
+    let value = scale::Compact::<u128>::decode(input)
+        .context("Failed to decode compact value")?
+        .0;
+
+    // Values wider than 64 bits are rendered as strings to avoid precision loss
+    match &inner.type_def {
+        TypeDef::Primitive(TypeDefPrimitive::U128) => Ok(JsonValue::String(value.to_string())),
+        _ => match u64::try_from(value) {
+            Ok(v) => Ok(JsonValue::Number(v.into())),
+            Err(_) => Ok(JsonValue::String(value.to_string())),
+        },
     }
 }
 
 /// Decode primitive types
-fn decode_primitive(bytes: &[u8], prim: &TypeDefPrimitive) -> Result<JsonValue> {
+fn decode_primitive(input: &mut &[u8], prim: &TypeDefPrimitive) -> Result<JsonValue> {
     match prim {
         TypeDefPrimitive::Bool => {
-            let val = bool::decode(&mut &bytes[..])?;
+            let val = bool::decode(input)?;
             Ok(JsonValue::Bool(val))
         }
+        TypeDefPrimitive::Char => {
+            let val = u32::decode(input)?;
+            let c = char::from_u32(val).ok_or_else(|| anyhow::anyhow!("Invalid char: {}", val))?;
+            Ok(JsonValue::String(c.to_string()))
+        }
         TypeDefPrimitive::U8 => {
-            let val = u8::decode(&mut &bytes[..])?;
+            let val = u8::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::U16 => {
-            let val = u16::decode(&mut &bytes[..])?;
+            let val = u16::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::U32 => {
-            let val = u32::decode(&mut &bytes[..])?;
+            let val = u32::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::U64 => {
-            let val = u64::decode(&mut &bytes[..])?;
+            let val = u64::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::U128 => {
-            let val = u128::decode(&mut &bytes[..])?;
+            let val = u128::decode(input)?;
             Ok(JsonValue::String(val.to_string()))
         }
+        TypeDefPrimitive::U256 => {
+            let bytes = <[u8; 32]>::decode(input)?;
+            Ok(JsonValue::String(
+                U256::from_little_endian(&bytes).to_string(),
+            ))
+        }
         TypeDefPrimitive::I8 => {
-            let val = i8::decode(&mut &bytes[..])?;
+            let val = i8::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::I16 => {
-            let val = i16::decode(&mut &bytes[..])?;
+            let val = i16::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::I32 => {
-            let val = i32::decode(&mut &bytes[..])?;
+            let val = i32::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::I64 => {
-            let val = i64::decode(&mut &bytes[..])?;
+            let val = i64::decode(input)?;
             Ok(JsonValue::Number(val.into()))
         }
         TypeDefPrimitive::I128 => {
-            let val = i128::decode(&mut &bytes[..])?;
+            let val = i128::decode(input)?;
             Ok(JsonValue::String(val.to_string()))
         }
+        TypeDefPrimitive::I256 => {
+            let bytes = <[u8; 32]>::decode(input)?;
+            let val = U256::from_little_endian(&bytes);
+            // Two's complement: the sign is the most significant bit
+            if val.bit(255) {
+                let magnitude = (!val).overflowing_add(U256::one()).0;
+                Ok(JsonValue::String(format!("-{}", magnitude)))
+            } else {
+                Ok(JsonValue::String(val.to_string()))
+            }
+        }
         TypeDefPrimitive::Str => {
-            let val = String::decode(&mut &bytes[..])?;
+            let val = String::decode(input)?;
             Ok(JsonValue::String(val))
         }
-        _ => Ok(JsonValue::String(format!("0x{}", hex::encode(bytes)))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{project_with, AccountId};
+    use scale_info::TypeInfo;
+    use serde_json::json;
+
+    #[derive(TypeInfo, Encode)]
+    struct Point {
+        x: i32,
+        y: u128,
+    }
+
+    #[derive(TypeInfo, Encode)]
+    struct Wrapper(u64);
+
+    #[derive(TypeInfo, Encode)]
+    enum Status {
+        Active,
+        Paused(u32, bool),
+    }
+
+    /// Encode a string argument the way `encode_args` does
+    fn encode_arg(value: &str, type_id: u32, metadata: &InkProject) -> Result<Vec<u8>> {
+        encode_value(&arg_to_json(value, type_id, metadata), type_id, metadata)
+    }
+
+    fn decode<T: TypeInfo + Encode + 'static>(value: T) -> JsonValue {
+        let (metadata, type_id) = project_with::<T>();
+        decode_value_by_id(&value.encode(), type_id, &metadata).unwrap()
+    }
+
+    #[test]
+    fn test_decode_struct_and_newtype() {
+        assert_eq!(decode(Point { x: -3, y: 7 }), json!({ "x": -3, "y": "7" }));
+        assert_eq!(decode(Wrapper(42)), json!(42));
+    }
+
+    #[test]
+    fn test_decode_enums() {
+        assert_eq!(
+            decode(Status::Paused(5, true)),
+            json!({ "variant": "Paused", "fields": [5, true] })
+        );
+        assert_eq!(
+            decode(Status::Active),
+            json!({ "variant": "Active", "fields": [] })
+        );
+
+        // Named variant fields decode to an object the encoder accepts back
+        #[derive(TypeInfo, Encode)]
+        enum Shape {
+            Rect { width: u32, height: u32 },
+        }
+        let (metadata, type_id) = project_with::<Shape>();
+        let shape = Shape::Rect {
+            width: 2,
+            height: 3,
+        };
+        let decoded = decode_value_by_id(&shape.encode(), type_id, &metadata).unwrap();
+        assert_eq!(
+            decoded,
+            json!({ "variant": "Rect", "fields": { "width": 2, "height": 3 } })
+        );
+        assert_eq!(
+            encode_value(&decoded, type_id, &metadata).unwrap(),
+            shape.encode()
+        );
+
+        // Named fields may also be given positionally
+        let positional = json!({ "variant": "Rect", "fields": [2, 3] });
+        assert_eq!(
+            encode_value(&positional, type_id, &metadata).unwrap(),
+            shape.encode()
+        );
+        assert_eq!(decode(Some(9u8)), json!(9));
+        assert_eq!(decode(None::<u8>), JsonValue::Null);
+        assert_eq!(decode(Ok::<u32, bool>(1)), json!({ "Ok": 1 }));
+        assert_eq!(decode(Err::<u32, bool>(false)), json!({ "Err": false }));
+    }
+
+    #[test]
+    fn test_decode_collections() {
+        assert_eq!(decode(vec![1u16, 2, 3]), json!([1, 2, 3]));
+        assert_eq!(decode([4u8; 2]), json!([4, 4]));
+        assert_eq!(decode((1u8, "a".to_string())), json!([1, "a"]));
+        assert_eq!(decode(scale::Compact(300u32)), json!(300));
+    }
+
+    #[test]
+    fn test_decode_oversized_sequence_length() {
+        let (metadata, type_id) = project_with::<Vec<u64>>();
+        // Compact length of 2^30 - 1 followed by a few bytes
+        let mut bytes = vec![0xfe, 0xff, 0xff, 0xff];
+        bytes.extend([0u8; 34]);
+
+        assert!(decode_value_by_id(&bytes, type_id, &metadata).is_err());
+
+        // Zero-sized elements take no input
+        assert_eq!(decode(vec![(); 3]), json!([null, null, null]));
+    }
+
+    #[test]
+    fn test_decode_account_id_as_ss58() {
+        let decoded = decode(AccountId([0u8; 32]));
+        assert_eq!(
+            decoded,
+            json!("5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM")
+        );
+    }
+
+    #[test]
+    fn test_decode_wide_integers() {
+        let mut bytes = [0xffu8; 32];
+        let decoded = decode_primitive(&mut &bytes[..], &TypeDefPrimitive::I256).unwrap();
+        assert_eq!(decoded, json!("-1"));
+
+        bytes[31] = 0x7f;
+        let decoded = decode_primitive(&mut &bytes[..], &TypeDefPrimitive::I256).unwrap();
+        assert_eq!(decoded, json!((U256::MAX >> 1).to_string()));
+
+        let decoded = decode_primitive(&mut &[0xffu8; 32][..], &TypeDefPrimitive::U256).unwrap();
+        assert_eq!(decoded, json!(U256::MAX.to_string()));
+    }
+
+    #[test]
+    fn test_encode_option_uses_inner_type() {
+        let (metadata, type_id) = project_with::<Option<u128>>();
+        let encode = |value: &str| encode_arg(value, type_id, &metadata).unwrap();
+
+        assert_eq!(encode("null"), None::<u128>.encode());
+        assert_eq!(encode("5"), Some(5u128).encode());
+        assert_eq!(encode(r#"{"Some": "5"}"#), Some(5u128).encode());
+        assert_eq!(encode(r#"{"None": null}"#), None::<u128>.encode());
+        assert!(encode_arg("", type_id, &metadata).is_err());
+
+        // An empty string is a value, not `None`
+        let (metadata, type_id) = project_with::<Option<String>>();
+        assert_eq!(
+            encode_arg("", type_id, &metadata).unwrap(),
+            Some(String::new()).encode()
+        );
+    }
+
+    #[test]
+    fn test_encode_nested_option() {
+        let (metadata, type_id) = project_with::<Option<Option<u8>>>();
+        let encode = |value: &str| encode_arg(value, type_id, &metadata).unwrap();
+
+        assert_eq!(encode("null"), None::<Option<u8>>.encode());
+        assert_eq!(encode(r#"{"Some": null}"#), Some(None::<u8>).encode());
+        assert_eq!(encode("7"), Some(Some(7u8)).encode());
+
+        // Decoding keeps Some(None) apart from None and encodes back
+        for value in [None, Some(None), Some(Some(7u8))] {
+            let decoded = decode_value_by_id(&value.encode(), type_id, &metadata).unwrap();
+            assert_eq!(
+                encode_value(&decoded, type_id, &metadata).unwrap(),
+                value.encode()
+            );
+        }
+        assert_eq!(decode(Some(None::<u8>)), json!({ "Some": null }));
+        assert_eq!(decode(Some(Some(7u8))), json!({ "Some": 7 }));
+    }
+
+    #[test]
+    fn test_encode_result_uses_inner_types() {
+        #[derive(TypeInfo, Encode)]
+        enum Error {
+            InsufficientBalance,
+        }
+
+        let (metadata, type_id) = project_with::<Result<AccountId, Error>>();
+        let encode = |value: &str| encode_arg(value, type_id, &metadata).unwrap();
+
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let alice_bytes = <AccountId32 as std::str::FromStr>::from_str(alice)
+            .unwrap()
+            .0;
+        assert_eq!(
+            encode(&format!(r#"{{"Ok": "{}"}}"#, alice)),
+            Ok::<_, Error>(AccountId(alice_bytes)).encode()
+        );
+        assert_eq!(
+            encode(r#"{"Err": {"variant": "InsufficientBalance"}}"#),
+            Err::<AccountId, _>(Error::InsufficientBalance).encode()
+        );
+
+        let (metadata, type_id) = project_with::<Result<(), u8>>();
+        assert_eq!(
+            encode_arg(r#"{"Ok": null}"#, type_id, &metadata).unwrap(),
+            Ok::<(), u8>(()).encode()
+        );
+    }
+
+    #[derive(TypeInfo)]
+    struct Msb0;
+
+    /// `BitVec<u16, Msb0>` lookalike
+    struct Bits;
+
+    impl TypeInfo for Bits {
+        type Identity = Self;
+
+        fn type_info() -> scale_info::Type {
+            TypeDefBitSequence::new::<u16, Msb0>().into()
+        }
+    }
+
+    #[test]
+    fn test_encode_wide_integers() {
+        let encode = |value: &str, prim| encode_primitive(&json!(value), &prim).unwrap();
+
+        let max = U256::MAX.to_string();
+        assert_eq!(encode(&max, TypeDefPrimitive::U256), vec![0xff; 32]);
+        assert_eq!(
+            encode("0x0102", TypeDefPrimitive::U256),
+            U256::from(0x0102).to_little_endian().to_vec()
+        );
+        assert_eq!(encode("-1", TypeDefPrimitive::I256), vec![0xff; 32]);
+
+        let min = format!("-{}", U256::one() << 255);
+        let decoded = decode_primitive(
+            &mut &encode(&min, TypeDefPrimitive::I256)[..],
+            &TypeDefPrimitive::I256,
+        )
+        .unwrap();
+        assert_eq!(decoded, json!(min));
+
+        assert!(encode_primitive(
+            &json!((U256::one() << 255).to_string()),
+            &TypeDefPrimitive::I256
+        )
+        .is_err());
+        assert!(encode_primitive(&json!("12abc"), &TypeDefPrimitive::U256).is_err());
+    }
+
+    #[test]
+    fn test_bit_sequence_roundtrip() {
+        let (metadata, type_id) = project_with::<Bits>();
+        let bits = [
+            true, false, true, true, false, false, false, false, false, true,
+        ];
+        let expected = encode_using_format(
+            bits.into_iter(),
+            Format::new(StoreFormat::U16, OrderFormat::Msb0),
+        );
+
+        let from_array = encode_arg(&json!(bits).to_string(), type_id, &metadata).unwrap();
+        let from_string = encode_arg("1011000001", type_id, &metadata).unwrap();
+        assert_eq!(from_array, expected);
+        assert_eq!(from_string, expected);
+
+        assert_eq!(
+            decode_value_by_id(&expected, type_id, &metadata).unwrap(),
+            json!(bits)
+        );
+        assert!(encode_arg("[1, 0]", type_id, &metadata).is_err());
+    }
+
+    #[test]
+    fn test_encode_mirrors_decode() {
+        let (metadata, type_id) = project_with::<Point>();
+        let encoded = encode_arg(r#"{"x": -3, "y": 7}"#, type_id, &metadata).unwrap();
+        assert_eq!(encoded, Point { x: -3, y: 7 }.encode());
+
+        let (metadata, type_id) = project_with::<Wrapper>();
+        let encoded = encode_arg("42", type_id, &metadata).unwrap();
+        assert_eq!(encoded, Wrapper(42).encode());
+
+        // String arguments are never parsed as JSON
+        let (metadata, type_id) = project_with::<String>();
+        for literal in ["null", "\"hi\"", "{\"a\": 1}", "[1]"] {
+            let encoded = encode_arg(literal, type_id, &metadata).unwrap();
+            assert_eq!(encoded, literal.to_string().encode());
+        }
+        let (metadata, type_id) = project_with::<Option<String>>();
+        assert_eq!(
+            encode_arg("null", type_id, &metadata).unwrap(),
+            None::<String>.encode()
+        );
+
+        // Named single-field structs are still given as objects
+        #[derive(TypeInfo, Encode)]
+        struct Name {
+            name: String,
+        }
+        let (metadata, type_id) = project_with::<Name>();
+        let encoded = encode_arg(r#"{"name": "glin"}"#, type_id, &metadata).unwrap();
+        assert_eq!(
+            encoded,
+            Name {
+                name: "glin".into()
+            }
+            .encode()
+        );
+    }
+
+    #[test]
+    fn test_encode_json_args() {
+        #[derive(TypeInfo, Encode)]
+        struct Owner {
+            id: u32,
+            tags: Vec<String>,
+        }
+
+        let (metadata, type_id) = project_with::<Owner>();
+        let encoded = encode_value(&json!({ "id": 7, "tags": ["a", "b"] }), type_id, &metadata);
+        assert_eq!(
+            encoded.unwrap(),
+            Owner {
+                id: 7,
+                tags: vec!["a".into(), "b".into()]
+            }
+            .encode()
+        );
+
+        let err = encode_value(&json!({ "id": "x", "tags": [] }), type_id, &metadata).unwrap_err();
+        let err = err.downcast_ref::<EncodeError>().unwrap();
+        assert_eq!(err.path, "value.id");
+
+        let err = encode_value(
+            &json!({ "id": 1, "tags": ["a", 5, {}] }),
+            type_id,
+            &metadata,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<EncodeError>().unwrap().path,
+            "value.tags[2]"
+        );
+
+        let err = encode_value(&json!({ "tags": [] }), type_id, &metadata).unwrap_err();
+        assert_eq!(err.downcast_ref::<EncodeError>().unwrap().path, "value.id");
     }
 }
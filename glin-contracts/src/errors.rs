@@ -0,0 +1,262 @@
+//! Contract error decoding
+//!
+//! Turns the opaque failures of contract calls into named errors: dispatch
+//! errors are resolved against the runtime metadata (`Contracts::OutOfGas`),
+//! reverted output is decoded against the message's return type
+//! (`InsufficientBalance`), and ink!'s `LangError` is recognized.
+//!
+//! Functions in this crate attach a [`ContractError`] to the `anyhow::Error`
+//! they return, so callers can inspect it with `err.downcast_ref::<ContractError>()`.
+
+use ink_metadata::{InkProject, TypeSpec};
+use scale::Decode;
+use scale_info::form::PortableForm;
+use scale_info::TypeDef;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::error::DispatchError;
+use subxt::Metadata;
+
+use crate::encoding;
+
+/// A decoded contract error
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize, Deserialize)]
+pub enum ContractError {
+    /// A pallet error, e.g. `Contracts::ContractTrapped`
+    #[error("{pallet}::{name}{}", docs_suffix(docs))]
+    Module {
+        /// Pallet name
+        pallet: String,
+        /// Error variant name
+        name: String,
+        /// Documentation of the error variant
+        docs: String,
+    },
+    /// Any other dispatch error (bad origin, token or arithmetic errors, ...)
+    #[error("{0}")]
+    Dispatch(String),
+    /// The contract reverted, returning an error value
+    #[error("Contract reverted with {name}")]
+    Revert {
+        /// Variant name of the error value, or its hex encoding if undecodable
+        name: String,
+        /// Decoded error value
+        value: JsonValue,
+    },
+    /// The contract could not dispatch the call (ink! `LangError`)
+    #[error("ink! LangError::{0}")]
+    Lang(String),
+}
+
+impl ContractError {
+    /// Whether this is the ink! `LangError::CouldNotReadInput` error
+    ///
+    /// Usually caused by a wrong selector or wrongly encoded arguments.
+    pub fn is_could_not_read_input(&self) -> bool {
+        matches!(self, ContractError::Lang(name) if name == "CouldNotReadInput")
+    }
+
+    /// A revert whose output could not be decoded
+    pub fn raw_revert(data: &[u8]) -> Self {
+        ContractError::Revert {
+            name: format!("0x{}", hex::encode(data)),
+            value: JsonValue::Null,
+        }
+    }
+}
+
+impl From<&DispatchError> for ContractError {
+    fn from(err: &DispatchError) -> Self {
+        match err {
+            DispatchError::Module(module) => match module.details() {
+                Ok(details) => ContractError::Module {
+                    pallet: details.pallet.name().to_string(),
+                    name: details.variant.name.clone(),
+                    docs: details.variant.docs.join(" "),
+                },
+                Err(_) => ContractError::Dispatch(err.to_string()),
+            },
+            other => ContractError::Dispatch(other.to_string()),
+        }
+    }
+}
+
+fn docs_suffix(docs: &str) -> String {
+    if docs.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", docs)
+    }
+}
+
+/// Decode a SCALE-encoded `DispatchError` using the runtime metadata
+pub fn decode_dispatch_error(bytes: &[u8], metadata: &Metadata) -> ContractError {
+    match DispatchError::decode_from(bytes, metadata.clone()) {
+        Ok(err) => ContractError::from(&err),
+        Err(_) => ContractError::Dispatch(format!(
+            "Undecodable dispatch error: 0x{}",
+            hex::encode(bytes)
+        )),
+    }
+}
+
+/// Decode the output of a reverted message against its return type
+///
+/// ink! messages return `Result<T, LangError>`, where `T` is commonly itself a
+/// `Result<_, Error>`; the first `Err` found while unwrapping is reported.
+pub fn decode_revert(
+    data: &[u8],
+    return_type: &TypeSpec<PortableForm>,
+    metadata: &InkProject,
+) -> ContractError {
+    decode_revert_value(data, return_type.ty().id, metadata)
+        .unwrap_or_else(|| ContractError::raw_revert(data))
+}
+
+fn decode_revert_value(data: &[u8], type_id: u32, metadata: &InkProject) -> Option<ContractError> {
+    let mut input = data;
+    let mut type_id = type_id;
+
+    while let Some((ok_type, err_type)) = result_types(type_id, metadata) {
+        match u8::decode(&mut input).ok()? {
+            0 => type_id = ok_type,
+            1 => {
+                let name = variant_name(input, err_type, metadata);
+                if is_lang_error(err_type, metadata) {
+                    return Some(ContractError::Lang(name?));
+                }
+
+                let value = encoding::decode_value(&mut input, err_type, metadata).ok()?;
+                return Some(ContractError::Revert {
+                    name: name.unwrap_or_else(|| value.to_string()),
+                    value,
+                });
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// `Ok` and `Err` types if the type is a `Result`
+fn result_types(type_id: u32, metadata: &InkProject) -> Option<(u32, u32)> {
+    let ty = metadata.registry().resolve(type_id)?;
+    if ty.path.segments.last().map(|s| s.as_str()) != Some("Result") {
+        return None;
+    }
+
+    let TypeDef::Variant(variant_def) = &ty.type_def else {
+        return None;
+    };
+    let field_type = |name: &str| {
+        variant_def
+            .variants
+            .iter()
+            .find(|v| v.name == name)
+            .and_then(|v| v.fields.first())
+            .map(|f| f.ty.id)
+    };
+
+    Some((field_type("Ok")?, field_type("Err")?))
+}
+
+fn is_lang_error(type_id: u32, metadata: &InkProject) -> bool {
+    metadata
+        .registry()
+        .resolve(type_id)
+        .and_then(|ty| ty.path.segments.last())
+        .is_some_and(|name| name == "LangError")
+}
+
+/// Name of the enum variant encoded at the front of `input`
+fn variant_name(input: &[u8], type_id: u32, metadata: &InkProject) -> Option<String> {
+    let ty = metadata.registry().resolve(type_id)?;
+    let TypeDef::Variant(variant_def) = &ty.type_def else {
+        return None;
+    };
+
+    let index = *input.first()?;
+    variant_def
+        .variants
+        .iter()
+        .find(|v| v.index == index)
+        .map(|v| v.name.clone())
+}
+
+/// Error for a dry-run aborted by a dispatch error, keeping the debug message
+pub(crate) fn dry_run_error(err: &ContractError, debug_message: &str, what: &str) -> anyhow::Error {
+    let message = if debug_message.is_empty() {
+        format!("{}: {}", what, err)
+    } else {
+        format!("{}: {} (debug message: {})", what, err, debug_message)
+    };
+
+    anyhow::Error::new(err.clone()).context(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink_metadata::LangError;
+    use scale::Encode;
+    use scale_info::TypeInfo;
+
+    use crate::test_utils::project_with;
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo, Encode)]
+    enum Error {
+        InsufficientBalance,
+        Custom(String),
+    }
+
+    type MessageReturn = Result<Result<u8, Error>, LangError>;
+
+    fn return_type(metadata: &InkProject) -> &TypeSpec<PortableForm> {
+        metadata.spec().messages()[0].return_type().ret_type()
+    }
+
+    #[test]
+    fn test_decode_revert() {
+        let (metadata, _) = project_with::<Result<u8, Error>>();
+
+        let data = MessageReturn::Ok(Err(Error::InsufficientBalance)).encode();
+        let err = decode_revert(&data, return_type(&metadata), &metadata);
+        assert_eq!(
+            err.to_string(),
+            "Contract reverted with InsufficientBalance"
+        );
+
+        let data = MessageReturn::Ok(Err(Error::Custom("nope".into()))).encode();
+        match decode_revert(&data, return_type(&metadata), &metadata) {
+            ContractError::Revert { name, value } => {
+                assert_eq!(name, "Custom");
+                assert!(value.to_string().contains("nope"));
+            }
+            other => panic!("Unexpected error: {:?}", other),
+        }
+
+        let data = MessageReturn::Err(LangError::CouldNotReadInput).encode();
+        assert!(decode_revert(&data, return_type(&metadata), &metadata).is_could_not_read_input());
+
+        let err = decode_revert(&[7], return_type(&metadata), &metadata);
+        assert_eq!(err, ContractError::raw_revert(&[7]));
+    }
+
+    #[test]
+    fn test_module_error_display() {
+        let err = ContractError::Module {
+            pallet: "Contracts".into(),
+            name: "OutOfGas".into(),
+            docs: "The executed contract exhausted its gas limit.".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Contracts::OutOfGas: The executed contract exhausted its gas limit."
+        );
+
+        let wrapped = anyhow::Error::new(err.clone()).context("Contract call failed");
+        assert_eq!(wrapped.downcast_ref::<ContractError>(), Some(&err));
+    }
+}
@@ -0,0 +1,241 @@
+//! Contract event decoding
+//!
+//! Decodes the payload of `Contracts::ContractEmitted` events into named JSON
+//! fields using the contract's ink! metadata. ink! 5 events are identified by
+//! their signature topic, older metadata by the leading variant index.
+
+use anyhow::{Context, Result};
+use ink_metadata::InkProject;
+use scale::Decode;
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::events::EventDetails;
+use subxt::utils::AccountId32;
+use subxt::PolkadotConfig;
+
+use crate::encoding;
+
+type EventSpec = ink_metadata::EventSpec<PortableForm>;
+
+/// Raw `Contracts::ContractEmitted` event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractEmitted {
+    /// Address of the emitting contract (SS58)
+    pub contract: String,
+    /// SCALE-encoded event payload
+    pub data: Vec<u8>,
+    /// Topics attached to the event record
+    pub topics: Vec<[u8; 32]>,
+}
+
+impl ContractEmitted {
+    /// Extract a `ContractEmitted` event from a runtime event
+    ///
+    /// Returns `None` for any other event.
+    pub fn from_event(event: &EventDetails<PolkadotConfig>) -> Result<Option<Self>> {
+        if event.pallet_name() != "Contracts" || event.variant_name() != "ContractEmitted" {
+            return Ok(None);
+        }
+
+        let (contract, data) = <([u8; 32], Vec<u8>)>::decode(&mut event.field_bytes())
+            .context("Failed to decode ContractEmitted event")?;
+
+        Ok(Some(Self {
+            contract: AccountId32(contract).to_string(),
+            data,
+            topics: event.topics().iter().map(|topic| topic.0).collect(),
+        }))
+    }
+
+    /// Decode the event payload with the contract's metadata
+    pub fn decode(&self, metadata: &InkProject) -> Result<ContractEvent> {
+        decode_contract_event(metadata, &self.data, &self.topics)
+    }
+}
+
+/// A decoded contract event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractEvent {
+    /// Event label from the metadata
+    pub name: String,
+    /// Signature topic of the event (hex), `None` for anonymous or pre-ink! 5 events
+    pub signature_topic: Option<String>,
+    /// Decoded event fields in declaration order
+    pub fields: Vec<EventField>,
+    /// Raw topics of the event record (hex)
+    pub topics: Vec<String>,
+}
+
+impl ContractEvent {
+    /// Look up a decoded field by name
+    pub fn field(&self, name: &str) -> Option<&JsonValue> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| &field.value)
+    }
+
+    /// Fields as a JSON object keyed by field name
+    pub fn fields_json(&self) -> JsonValue {
+        JsonValue::Object(
+            self.fields
+                .iter()
+                .map(|field| (field.name.clone(), field.value.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// A single decoded event field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventField {
+    /// Field label
+    pub name: String,
+    /// Decoded value
+    pub value: JsonValue,
+    /// Whether the field is also published as a topic
+    pub indexed: bool,
+}
+
+/// Decode a contract event from its payload and topics
+///
+/// For ink! 5 metadata the event is matched by its signature topic (the first
+/// topic), falling back to the only anonymous event if there is exactly one.
+/// Older metadata prefixes the payload with the index of the event.
+pub fn decode_contract_event(
+    metadata: &InkProject,
+    data: &[u8],
+    topics: &[[u8; 32]],
+) -> Result<ContractEvent> {
+    let mut input = data;
+
+    let spec = if *metadata.version() >= 5 {
+        find_event_by_topic(metadata, topics)?
+    } else {
+        let index = u8::decode(&mut input).context("Failed to decode event index")?;
+        metadata
+            .spec()
+            .events()
+            .get(index as usize)
+            .ok_or_else(|| anyhow::anyhow!("Event index {} not found in metadata", index))?
+    };
+
+    let mut fields = Vec::with_capacity(spec.args().len());
+    for arg in spec.args() {
+        let value =
+            encoding::decode_value(&mut input, arg.ty().ty().id, metadata).with_context(|| {
+                format!(
+                    "Failed to decode field '{}' of event '{}'",
+                    arg.label(),
+                    spec.label()
+                )
+            })?;
+
+        fields.push(EventField {
+            name: arg.label().clone(),
+            value,
+            indexed: arg.indexed(),
+        });
+    }
+
+    if !input.is_empty() {
+        anyhow::bail!(
+            "Event '{}' has {} trailing bytes",
+            spec.label(),
+            input.len()
+        );
+    }
+
+    Ok(ContractEvent {
+        name: spec.label().clone(),
+        signature_topic: spec
+            .signature_topic()
+            .map(|topic| format!("0x{}", hex::encode(topic.as_bytes()))),
+        fields,
+        topics: topics
+            .iter()
+            .map(|topic| format!("0x{}", hex::encode(topic)))
+            .collect(),
+    })
+}
+
+/// Find an ink! 5 event by its signature topic
+fn find_event_by_topic<'a>(metadata: &'a InkProject, topics: &[[u8; 32]]) -> Result<&'a EventSpec> {
+    let events = metadata.spec().events();
+
+    if let Some(first) = topics.first() {
+        if let Some(spec) = events.iter().find(|event| {
+            event
+                .signature_topic()
+                .is_some_and(|topic| topic.as_bytes() == first)
+        }) {
+            return Ok(spec);
+        }
+    }
+
+    let mut anonymous = events
+        .iter()
+        .filter(|event| event.signature_topic().is_none());
+    match (anonymous.next(), anonymous.next()) {
+        (Some(spec), None) => Ok(spec),
+        (Some(_), Some(_)) => {
+            anyhow::bail!("No event matches the signature topic and anonymous events are ambiguous")
+        }
+        (None, _) => anyhow::bail!("No event in metadata matches the signature topic"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{erc20, v4_json, AccountId};
+    use scale::Encode;
+    use serde_json::json;
+
+    const TRANSFER_TOPIC: [u8; 32] = [0x11; 32];
+
+    fn transfer_payload() -> Vec<u8> {
+        (Some(AccountId([1u8; 32])), None::<AccountId>, 500u128).encode()
+    }
+
+    #[test]
+    fn test_decode_event_by_signature_topic() {
+        let metadata = erc20();
+        let event =
+            decode_contract_event(&metadata, &transfer_payload(), &[TRANSFER_TOPIC]).unwrap();
+
+        assert_eq!(event.name, "Transfer");
+        assert_eq!(event.field("to"), Some(&JsonValue::Null));
+        assert_eq!(event.field("value"), Some(&json!("500")));
+        assert!(event.fields[0].indexed);
+        assert!(!event.fields[2].indexed);
+        assert_eq!(
+            event.topics,
+            vec![format!("0x{}", hex::encode(TRANSFER_TOPIC))]
+        );
+
+        // Unknown topic with no anonymous event to fall back to
+        assert!(decode_contract_event(&metadata, &transfer_payload(), &[[0u8; 32]]).is_err());
+    }
+
+    #[test]
+    fn test_decode_legacy_event_by_index() {
+        let json = serde_json::to_string(&v4_json(&erc20())).unwrap();
+        let metadata = crate::metadata::parse_metadata(&json).unwrap();
+
+        let mut data = vec![0u8];
+        data.extend(transfer_payload());
+        let event = decode_contract_event(&metadata, &data, &[]).unwrap();
+
+        assert_eq!(event.name, "Transfer");
+        assert_eq!(
+            event.fields_json(),
+            json!({
+                "from": "5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT",
+                "to": null,
+                "value": "500",
+            })
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Gas and storage deposit estimation
+//!
+//! Dry-runs contract calls and instantiations through the contracts runtime API
+//! and derives a gas limit and storage deposit limit with a safety margin.
+//! [`call_contract`](crate::call_contract) estimates from the dry-run it
+//! already performs to detect reverts.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use glin_client::GlinClient;
+
+use crate::errors::{self, ContractError};
+use crate::runtime_api::{self, Code, ContractResult, ExecReturnValue, StorageDeposit};
+use crate::tx::Weight;
+
+/// Default safety margin applied to dry-run results (percent)
+pub const DEFAULT_MARGIN_PERCENT: u64 = 10;
+
+/// Options controlling how estimates are derived from dry-runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EstimateOptions {
+    /// Extra percentage added to the required gas and storage deposit
+    pub margin_percent: u64,
+}
+
+impl Default for EstimateOptions {
+    fn default() -> Self {
+        Self {
+            margin_percent: DEFAULT_MARGIN_PERCENT,
+        }
+    }
+}
+
+/// Gas and storage deposit limits ready to be used in an extrinsic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasEstimate {
+    /// Gas limit including the safety margin
+    pub gas_limit: Weight,
+    /// Storage deposit limit including the safety margin
+    pub storage_deposit_limit: u128,
+    /// Weight required by the dry-run
+    pub gas_required: Weight,
+    /// Storage deposit reported by the dry-run
+    pub storage_deposit: StorageDeposit,
+}
+
+impl GasEstimate {
+    /// Derive limits from a dry-run result
+    ///
+    /// Fails if the dry-run was aborted by a dispatch error.
+    pub fn from_dry_run<R>(dry_run: &ContractResult<R>, options: &EstimateOptions) -> Result<Self> {
+        if let Err(err) = &dry_run.result {
+            return Err(errors::dry_run_error(
+                err,
+                &dry_run.debug_message,
+                "Dry-run failed",
+            ));
+        }
+
+        Ok(Self {
+            gas_limit: Weight {
+                ref_time: add_weight_margin(dry_run.gas_required.ref_time, options),
+                proof_size: add_weight_margin(dry_run.gas_required.proof_size, options),
+            },
+            storage_deposit_limit: add_margin(dry_run.storage_deposit.charge_or_zero(), options),
+            gas_required: dry_run.gas_required,
+            storage_deposit: dry_run.storage_deposit,
+        })
+    }
+}
+
+/// Estimate limits for a contract message call without submitting it
+///
+/// A revert is reported as a [`ContractError::Revert`] with the raw output.
+pub async fn estimate_call(
+    client: &GlinClient,
+    origin: [u8; 32],
+    dest: [u8; 32],
+    value: u128,
+    input_data: Vec<u8>,
+    options: &EstimateOptions,
+) -> Result<GasEstimate> {
+    let dry_run =
+        runtime_api::dry_run_call(client, origin, dest, value, None, None, input_data).await?;
+
+    ensure_not_reverted(dry_run.result.as_ref().ok())?;
+    GasEstimate::from_dry_run(&dry_run, options)
+}
+
+/// Estimate limits for a contract instantiation
+///
+/// A constructor revert is reported as a [`ContractError::Revert`] with the
+/// raw output.
+pub async fn estimate_instantiate(
+    client: &GlinClient,
+    origin: [u8; 32],
+    value: u128,
+    code: Code,
+    data: Vec<u8>,
+    salt: Vec<u8>,
+    options: &EstimateOptions,
+) -> Result<GasEstimate> {
+    let dry_run =
+        runtime_api::dry_run_instantiate(client, origin, value, None, None, code, data, salt)
+            .await?;
+
+    ensure_not_reverted(dry_run.result.as_ref().ok().map(|output| &output.result))?;
+    GasEstimate::from_dry_run(&dry_run, options)
+}
+
+/// Fail with the decoded revert if the dry-run output reverted
+fn ensure_not_reverted(output: Option<&ExecReturnValue>) -> Result<()> {
+    match output {
+        Some(output) if output.did_revert() => {
+            let err = ContractError::raw_revert(&output.data);
+            let message = format!("Contract reverted during gas estimation: {}", err);
+            Err(anyhow::Error::new(err).context(message))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Add the configured margin to an amount
+fn add_margin(amount: u128, options: &EstimateOptions) -> u128 {
+    let with_margin = amount.saturating_mul(100 + options.margin_percent as u128) / 100;
+    with_margin.max(amount)
+}
+
+/// Add the configured margin to a weight component, saturating at `u64::MAX`
+fn add_weight_margin(amount: u64, options: &EstimateOptions) -> u64 {
+    add_margin(amount as u128, options).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dry_run(
+        result: std::result::Result<ExecReturnValue, ContractError>,
+    ) -> ContractResult<ExecReturnValue> {
+        ContractResult {
+            gas_consumed: Weight::new(800, 80),
+            gas_required: Weight::new(1_000, 100),
+            storage_deposit: StorageDeposit::Charge(500),
+            debug_message: String::new(),
+            result,
+        }
+    }
+
+    #[test]
+    fn test_estimate_applies_margin() {
+        let ok = dry_run(Ok(ExecReturnValue {
+            flags: 0,
+            data: vec![],
+        }));
+        let estimate = GasEstimate::from_dry_run(&ok, &EstimateOptions::default()).unwrap();
+
+        assert_eq!(estimate.gas_limit, Weight::new(1_100, 110));
+        assert_eq!(estimate.storage_deposit_limit, 550);
+        assert_eq!(estimate.gas_required, Weight::new(1_000, 100));
+    }
+
+    #[test]
+    fn test_estimate_fails_on_dispatch_error() {
+        let trapped = ContractError::Module {
+            pallet: "Contracts".to_string(),
+            name: "ContractTrapped".to_string(),
+            docs: String::new(),
+        };
+        let failed = dry_run(Err(trapped.clone()));
+
+        let err = GasEstimate::from_dry_run(&failed, &EstimateOptions::default()).unwrap_err();
+        assert_eq!(err.downcast_ref::<ContractError>(), Some(&trapped));
+    }
+
+    #[test]
+    fn test_estimate_reports_revert() {
+        let reverted = ExecReturnValue {
+            flags: 1,
+            data: vec![0xde, 0xad],
+        };
+        let err = ensure_not_reverted(Some(&reverted)).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ContractError>(),
+            Some(&ContractError::raw_revert(&[0xde, 0xad]))
+        );
+
+        let ok = ExecReturnValue {
+            flags: 0,
+            data: vec![],
+        };
+        assert!(ensure_not_reverted(Some(&ok)).is_ok());
+        assert!(ensure_not_reverted(None).is_ok());
+    }
+}
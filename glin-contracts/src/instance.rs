@@ -0,0 +1,402 @@
+//! Contract instance handle
+//!
+//! `ContractInstance` bundles a client, a contract address and its metadata so
+//! that messages can be queried and called without threading all three through
+//! every call site.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use futures::stream::{self, Stream, StreamExt};
+use ink_metadata::InkProject;
+use scale_info::form::PortableForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use subxt::blocks::Block;
+use subxt::utils::AccountId32;
+use subxt_signer::sr25519::Keypair;
+
+use glin_client::{GlinClient, GlinConfig};
+
+use crate::call::{self, CallOptions, CallResult, QueryResult, RawQueryResult};
+use crate::call_data::{self, DecodedCall};
+use crate::events::{ContractEmitted, ContractEvent};
+use crate::metadata_fetcher::{self, MetadataFetchOptions};
+use crate::metadata_source::MetadataResolver;
+use crate::{metadata, storage};
+
+type MessageSpec = ink_metadata::MessageSpec<PortableForm>;
+type ConstructorSpec = ink_metadata::ConstructorSpec<PortableForm>;
+
+type EventStreamInner = Pin<Box<dyn Stream<Item = Result<EmittedEvent>> + Send>>;
+
+/// A deployed contract bound to a client and its metadata
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use glin_client::{create_client, get_dev_account};
+/// use glin_contracts::{metadata, ContractInstance};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let client = create_client("ws://localhost:9944").await?;
+///     let signer = get_dev_account("alice")?;
+///     # let metadata_json = "{}";
+///     let flipper = ContractInstance::new(
+///         &client,
+///         "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+///         metadata::parse_metadata(metadata_json)?,
+///     )?;
+///
+///     flipper.call("flip", &[], &signer).await?;
+///     println!("Value: {}", flipper.query("get", &[]).await?.value);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ContractInstance {
+    client: GlinClient,
+    address: String,
+    metadata: Arc<InkProject>,
+}
+
+/// A contract event emitted in a finalized block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmittedEvent {
+    /// Number of the block containing the event
+    pub block_number: u64,
+    /// Hash of the block containing the event
+    pub block_hash: String,
+    /// The decoded event
+    pub event: ContractEvent,
+}
+
+/// Stream of decoded events emitted by a single contract
+pub struct ContractEventStream {
+    inner: EventStreamInner,
+}
+
+impl Stream for ContractEventStream {
+    type Item = Result<EmittedEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl ContractInstance {
+    /// Bind a contract address to its metadata
+    ///
+    /// The address may be SS58 or hex; it is stored in SS58 form.
+    pub fn new(client: &GlinClient, address: &str, metadata: InkProject) -> Result<Self> {
+        let account = call::to_account_bytes(address)?;
+
+        Ok(Self {
+            client: client.clone(),
+            address: AccountId32(account).to_string(),
+            metadata: Arc::new(metadata),
+        })
+    }
+
+    /// Bind a contract address, resolving its metadata with `fetch_contract_metadata`
+    pub async fn fetch(
+        client: &GlinClient,
+        address: &str,
+        options: MetadataFetchOptions,
+    ) -> Result<Self> {
+        let metadata = metadata_fetcher::fetch_contract_metadata(client, address, options).await?;
+        Self::new(client, address, metadata)
+    }
+
+    /// Bind a contract address, resolving its metadata with a custom resolver chain
+    pub async fn resolve(
+        client: &GlinClient,
+        address: &str,
+        resolver: &MetadataResolver,
+    ) -> Result<Self> {
+        let metadata = resolver.resolve_contract(client, address).await?;
+        Self::new(client, address, metadata)
+    }
+
+    /// Contract address (SS58)
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Contract metadata
+    pub fn metadata(&self) -> &InkProject {
+        &self.metadata
+    }
+
+    /// Client used for queries and calls
+    pub fn client(&self) -> &GlinClient {
+        &self.client
+    }
+
+    /// Query a message with default options and the zero account as caller
+    pub async fn query(&self, message: &str, args: &[String]) -> Result<QueryResult> {
+        self.query_with(message, args, None, CallOptions::default())
+            .await
+    }
+
+    /// Query a message with an explicit caller and options
+    pub async fn query_with(
+        &self,
+        message: &str,
+        args: &[String],
+        origin: Option<&str>,
+        options: CallOptions,
+    ) -> Result<QueryResult> {
+        call::query_contract(
+            &self.client,
+            &self.address,
+            &self.metadata,
+            message,
+            args,
+            origin,
+            options,
+        )
+        .await
+    }
+
+    /// Call a state-mutating message with default options
+    pub async fn call(
+        &self,
+        message: &str,
+        args: &[String],
+        signer: &Keypair,
+    ) -> Result<CallResult> {
+        self.call_with(message, args, signer, CallOptions::default())
+            .await
+    }
+
+    /// Call a state-mutating message with explicit options
+    pub async fn call_with(
+        &self,
+        message: &str,
+        args: &[String],
+        signer: &Keypair,
+        options: CallOptions,
+    ) -> Result<CallResult> {
+        call::call_contract(
+            &self.client,
+            signer,
+            &self.address,
+            &self.metadata,
+            message,
+            args,
+            options,
+        )
+        .await
+    }
+
+    /// Query with pre-encoded call data, returning the raw output
+    pub async fn query_raw(
+        &self,
+        input_data: Vec<u8>,
+        origin: Option<&str>,
+        options: CallOptions,
+    ) -> Result<RawQueryResult> {
+        call::query_raw(&self.client, &self.address, input_data, origin, options).await
+    }
+
+    /// Call with pre-encoded call data
+    pub async fn call_raw(
+        &self,
+        input_data: Vec<u8>,
+        signer: &Keypair,
+        options: CallOptions,
+    ) -> Result<CallResult> {
+        call::call_raw(&self.client, signer, &self.address, input_data, options).await
+    }
+
+    /// Read and decode the contract's storage (see [`storage::read_storage`])
+    pub async fn storage(&self) -> Result<JsonValue> {
+        storage::read_storage(&self.client, &self.address, &self.metadata).await
+    }
+
+    /// Read a single entry of a `Mapping` field (see [`storage::read_mapping`])
+    pub async fn mapping(&self, path: &str, key: &JsonValue) -> Result<Option<JsonValue>> {
+        storage::read_mapping(&self.client, &self.address, &self.metadata, path, key).await
+    }
+
+    /// Subscribe to events emitted by this contract in finalized blocks
+    ///
+    /// Events that fail to decode are yielded as errors without ending the stream.
+    pub async fn events(&self) -> Result<ContractEventStream> {
+        let subscription = self.client.blocks().subscribe_finalized().await?;
+        let address = self.address.clone();
+        let metadata = self.metadata.clone();
+
+        let events = subscription
+            .then(move |block| {
+                let address = address.clone();
+                let metadata = metadata.clone();
+                async move {
+                    match block {
+                        Ok(block) => block_events(block, &address, &metadata).await,
+                        Err(err) => vec![Err(err.into())],
+                    }
+                }
+            })
+            .flat_map(stream::iter);
+
+        Ok(ContractEventStream {
+            inner: Box::pin(events),
+        })
+    }
+
+    /// Decode call data sent to this contract into the target message and its arguments
+    pub fn decode_call_data(&self, data: &[u8]) -> Result<DecodedCall> {
+        call_data::decode_call_data(&self.metadata, data)
+    }
+
+    /// Names of all messages
+    pub fn messages(&self) -> Vec<String> {
+        metadata::list_messages(&self.metadata)
+    }
+
+    /// Names of all constructors
+    pub fn constructors(&self) -> Vec<String> {
+        metadata::list_constructors(&self.metadata)
+    }
+
+    /// Specification of a message
+    pub fn message(&self, name: &str) -> Result<&MessageSpec> {
+        metadata::get_message_spec(&self.metadata, name)
+    }
+
+    /// Specification of a constructor
+    pub fn constructor(&self, name: &str) -> Result<&ConstructorSpec> {
+        metadata::get_constructor_spec(&self.metadata, name)
+    }
+
+    /// Selector of a message
+    pub fn selector(&self, message: &str) -> Result<[u8; 4]> {
+        let spec = self.message(message)?;
+        Ok(metadata::selector_bytes(spec.selector()))
+    }
+
+    /// All messages with their selectors
+    pub fn selectors(&self) -> Vec<(String, [u8; 4])> {
+        metadata::list_message_selectors(&self.metadata)
+    }
+
+    /// Whether a message mutates state (and so must be called rather than queried)
+    pub fn is_mutable(&self, message: &str) -> Result<bool> {
+        Ok(metadata::is_message_mutable(self.message(message)?))
+    }
+}
+
+/// Decode the events a contract emitted in a block
+async fn block_events(
+    block: Block<GlinConfig, GlinClient>,
+    address: &str,
+    metadata: &InkProject,
+) -> Vec<Result<EmittedEvent>> {
+    let events = match block.events().await {
+        Ok(events) => events,
+        Err(err) => return vec![Err(err.into())],
+    };
+
+    let block_number = block.number() as u64;
+    let block_hash = format!("0x{}", hex::encode(block.hash()));
+
+    let mut emitted = Vec::new();
+    for event in events.iter() {
+        let record = event
+            .map_err(anyhow::Error::from)
+            .and_then(|event| ContractEmitted::from_event(&event));
+
+        match record {
+            Ok(Some(record)) if record.contract == address => {
+                emitted.push(record.decode(metadata).map(|event| EmittedEvent {
+                    block_number,
+                    block_hash: block_hash.clone(),
+                    event,
+                }));
+            }
+            Ok(_) => {}
+            Err(err) => emitted.push(Err(err)),
+        }
+    }
+
+    emitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{erc20, flipper, offline_client};
+    use scale::Encode;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[tokio::test]
+    async fn test_new_normalizes_address() {
+        let (client, _) = offline_client();
+
+        let hex_address = format!("0x{}", hex::encode([1u8; 32]));
+        let instance = ContractInstance::new(&client, &hex_address, flipper()).unwrap();
+        assert_eq!(instance.address(), AccountId32([1u8; 32]).to_string());
+
+        let instance = ContractInstance::new(&client, ALICE, flipper()).unwrap();
+        assert_eq!(instance.address(), ALICE);
+
+        assert!(ContractInstance::new(&client, "not-an-address", flipper()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_introspection() {
+        let (client, _) = offline_client();
+        let flipper = ContractInstance::new(&client, ALICE, flipper()).unwrap();
+
+        assert_eq!(flipper.messages(), ["flip", "get", "set"]);
+        assert_eq!(flipper.constructors(), ["new"]);
+        assert_eq!(flipper.selector("flip").unwrap(), [0x63, 0x3a, 0xa5, 0x51]);
+        assert_eq!(
+            flipper.selectors()[2],
+            ("set".to_string(), [0xe8, 0xc4, 0x5e, 0xb6])
+        );
+        assert!(flipper.is_mutable("flip").unwrap());
+        assert!(!flipper.is_mutable("get").unwrap());
+        assert!(flipper.message("missing").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_and_call_encode_arguments() {
+        let (client, rpc) = offline_client();
+        let token = ContractInstance::new(&client, ALICE, erc20()).unwrap();
+        let alice = AccountId32::from(subxt_signer::sr25519::dev::alice().public_key());
+
+        // The dry-run fails offline, after the call data has been sent
+        assert!(token.query("balance_of", &[ALICE.into()]).await.is_err());
+        let signer = subxt_signer::sr25519::dev::bob();
+        assert!(token
+            .call("transfer", &[ALICE.into(), "1000".into()], &signer)
+            .await
+            .is_err());
+
+        let mut balance_of = token.selector("balance_of").unwrap().to_vec();
+        balance_of.extend(alice.0);
+        let mut transfer = token.selector("transfer").unwrap().to_vec();
+        transfer.extend(alice.0);
+        transfer.extend(1000u128.encode());
+
+        {
+            let calls = rpc.state_calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            for ((function, params), input) in calls.iter().zip([balance_of, transfer]) {
+                assert_eq!(function, "ContractsApi_call");
+                assert!(params.ends_with(&input.encode()));
+            }
+        }
+
+        // Argument errors are reported before anything is sent
+        assert!(token.query("balance_of", &[]).await.is_err());
+        assert_eq!(rpc.state_calls.lock().unwrap().len(), 2);
+    }
+}
@@ -2,13 +2,104 @@
 //!
 //! Utilities for interacting with ink! smart contracts on GLIN Network.
 
+// Lets code generated by `contract!` refer to `::glin_contracts` inside this crate
+extern crate self as glin_contracts;
+
+pub mod abi_diff;
+pub mod build_runner;
+pub mod bundle;
+pub mod call;
+pub mod call_data;
 pub mod chain_info;
+pub mod deploy;
 pub mod encoding;
+pub mod errors;
+pub mod events;
+pub mod gas;
+pub mod instance;
 pub mod metadata;
+pub mod metadata_cache;
 pub mod metadata_fetcher;
+pub mod metadata_source;
+pub mod runtime_api;
+pub mod storage;
+pub mod tx;
+pub mod verification_queue;
+pub mod verification_report;
+pub mod verification_source;
 pub mod verifier;
+pub mod wasm_analysis;
+
+#[cfg(test)]
+mod test_utils;
 
 // Re-export commonly used types
-pub use chain_info::{get_contract_info, ContractInfo};
-pub use metadata_fetcher::{fetch_contract_metadata, get_default_cache_dir, MetadataFetchOptions};
+pub use abi_diff::{diff_metadata, AbiChange, AbiDiff, AbiItem, ChangeKind, Severity};
+pub use bundle::{BundleContract, BundleSource, ContractBundle, MetadataVersion};
+pub use call::{
+    call_contract, call_raw, query_contract, query_raw, CallOptions, CallResult, QueryResult,
+    RawQueryResult,
+};
+pub use call_data::{
+    decode_call_data, decode_call_data_hex, decode_constructor_data, decode_message_data, CallKind,
+    DecodedArg, DecodedCall,
+};
+pub use chain_info::{
+    get_code_info, get_contract_info, get_pristine_code, list_contracts_by_code_hash, CodeInfo,
+    ContractInfo, ListContractsOptions,
+};
+pub use deploy::{
+    deploy_contract, deploy_contract_json, deploy_wasm, deploy_wasm_json, deploy_wasm_with_data,
+    instantiate, instantiate_json, instantiate_with_data, DeployOptions, DeployResult,
+};
+pub use errors::{decode_dispatch_error, decode_revert, ContractError};
+pub use events::{decode_contract_event, ContractEmitted, ContractEvent, EventField};
+pub use gas::{EstimateOptions, GasEstimate};
+pub use instance::{ContractEventStream, ContractInstance, EmittedEvent};
+pub use metadata_cache::MetadataCache;
+pub use metadata_fetcher::{
+    fetch_contract_metadata, fetch_contract_metadata_with, get_default_cache_dir,
+    MetadataFetchOptions,
+};
+pub use metadata_source::{MetadataQuery, MetadataResolver, MetadataSource, SourceKind};
+pub use runtime_api::StorageDeposit;
+pub use storage::{read_mapping, read_storage};
+pub use tx::Weight;
+pub use verification_queue::{JobRecord, JobStatus, VerificationQueue, VerificationRequest};
+pub use verification_report::VerificationReport;
+pub use verification_source::{ArchiveFormat, VerificationSource};
 pub use verifier::{ContractVerifier, VerificationResult};
+pub use wasm_analysis::{analyze_code, UploadLimits, WasmAnalysis, WasmIssue};
+
+/// Generate a typed contract client from ink! metadata (see `glin-contracts-codegen`)
+pub use glin_contracts_macro::contract;
+
+/// Re-exports used by code generated with [`contract!`]
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use glin_client::GlinClient;
+    pub use ink_metadata::InkProject;
+    pub use scale;
+    pub use subxt_signer::sr25519::Keypair;
+}
+
+#[cfg(test)]
+mod tests {
+    // Generated code refers to `::glin_contracts`, see `extern crate self` above
+    crate::contract!("fixtures/flipper_v4.json");
+
+    #[test]
+    fn test_contract_macro_accepts_v4_metadata() {
+        let metadata = flipper::metadata().unwrap();
+        assert_eq!(
+            crate::MetadataVersion::of(&metadata),
+            crate::MetadataVersion::V4
+        );
+        assert_eq!(metadata.spec().messages().len(), 3);
+
+        let _ = flipper::Flipper::deploy_new;
+        let _ = flipper::Flipper::get;
+        let _ = flipper::Flipper::set;
+    }
+}
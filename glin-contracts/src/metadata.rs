@@ -1,20 +1,24 @@
 // Contract metadata parsing utilities using ink_metadata
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+use ink_metadata::layout::Layout;
 use ink_metadata::{InkProject, Selector};
 use scale_info::form::PortableForm;
 use serde_json::Value as JsonValue;
 
+use crate::bundle::ContractBundle;
+
 // Type aliases for PortableForm
 type ConstructorSpec = ink_metadata::ConstructorSpec<PortableForm>;
 type MessageSpec = ink_metadata::MessageSpec<PortableForm>;
 type TypeSpec = ink_metadata::TypeSpec<PortableForm>;
 
 /// Parse ink! contract metadata from JSON
+///
+/// Accepts metadata `.json` files and `.contract` bundles of ink! v3, v4 and v5;
+/// use [`ContractBundle`] to also keep the `source` and `contract` sections.
 pub fn parse_metadata(metadata_json: &str) -> Result<InkProject> {
-    let metadata: InkProject = serde_json::from_str(metadata_json)
-        .context("Failed to parse ink! contract metadata JSON")?;
-    Ok(metadata)
+    Ok(ContractBundle::from_json(metadata_json)?.metadata)
 }
 
 /// Get constructor specification by name
@@ -61,10 +65,24 @@ pub fn get_message_spec<'a>(metadata: &'a InkProject, name: &str) -> Result<&'a
 }
 
 /// Get contract name from metadata
-pub fn get_contract_name(_metadata: &InkProject) -> String {
-    // Note: Contract name not directly accessible in PortableForm
-    // Would need to traverse type registry or use different metadata source
-    String::from("unknown")
+///
+/// The metadata itself only carries the name of the storage struct; the crate
+/// name is in the `contract` section of the bundle ([`ContractBundle::name`]).
+pub fn get_contract_name(metadata: &InkProject) -> String {
+    let name = match metadata.layout() {
+        Layout::Root(root) => match root.layout() {
+            Layout::Struct(layout) => Some(layout.name().clone()),
+            _ => metadata
+                .registry()
+                .resolve(root.ty().id)
+                .and_then(|ty| ty.path.segments.last().cloned()),
+        },
+        Layout::Struct(layout) => Some(layout.name().clone()),
+        _ => None,
+    };
+
+    name.filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("unknown"))
 }
 
 /// Get contract version from metadata
@@ -92,6 +110,33 @@ pub fn list_messages(metadata: &InkProject) -> Vec<String> {
         .collect()
 }
 
+/// List all messages with their selectors
+pub fn list_message_selectors(metadata: &InkProject) -> Vec<(String, [u8; 4])> {
+    metadata
+        .spec()
+        .messages()
+        .iter()
+        .map(|m| (m.label().to_string(), selector_bytes(m.selector())))
+        .collect()
+}
+
+/// List all constructors with their selectors
+pub fn list_constructor_selectors(metadata: &InkProject) -> Vec<(String, [u8; 4])> {
+    metadata
+        .spec()
+        .constructors()
+        .iter()
+        .map(|c| (c.label().to_string(), selector_bytes(c.selector())))
+        .collect()
+}
+
+/// Copy a selector into a fixed-size array
+pub fn selector_bytes(selector: &Selector) -> [u8; 4] {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(selector.to_bytes());
+    bytes
+}
+
 /// Get message selector (first 4 bytes of Blake2_256 hash of label)
 pub fn get_message_selector(message: &MessageSpec) -> &Selector {
     message.selector()
@@ -114,8 +159,7 @@ pub fn get_message_return_type(message: &MessageSpec) -> &TypeSpec {
 
 /// Parse metadata from JSON value (for backward compatibility)
 pub fn parse_metadata_from_json(json: &JsonValue) -> Result<InkProject> {
-    let json_str = serde_json::to_string(json)?;
-    parse_metadata(&json_str)
+    Ok(ContractBundle::from_value(json.clone())?.metadata)
 }
 
 /// Validate metadata structure
@@ -161,4 +205,28 @@ mod tests {
         let json = serde_json::json!({});
         assert!(parse_metadata_from_json(&json).is_err());
     }
+
+    #[test]
+    fn test_list_selectors() {
+        let metadata = crate::test_utils::flipper();
+
+        assert_eq!(
+            list_message_selectors(&metadata),
+            vec![
+                ("flip".to_string(), [0x63, 0x3a, 0xa5, 0x51]),
+                ("get".to_string(), [0x2f, 0x86, 0x5b, 0xd9]),
+                ("set".to_string(), [0xe8, 0xc4, 0x5e, 0xb6]),
+            ]
+        );
+        assert_eq!(
+            list_constructor_selectors(&metadata),
+            vec![("new".to_string(), [0x9b, 0xae, 0x9d, 0x5e])]
+        );
+    }
+
+    #[test]
+    fn test_get_contract_name() {
+        assert_eq!(get_contract_name(&crate::test_utils::erc20()), "Erc20");
+        assert_eq!(get_contract_name(&crate::test_utils::flipper()), "Contract");
+    }
 }
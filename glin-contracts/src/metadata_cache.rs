@@ -0,0 +1,500 @@
+//! On-disk metadata cache keyed by code hash
+//!
+//! Metadata belongs to code, not to an address: after `set_code` or a redeploy
+//! the same address runs different code. Entries are therefore stored per code
+//! hash, with a separate address → code hash index, and are only returned for
+//! the code hash they were stored under, which must also be the source wasm
+//! hash recorded in the bundle. Metadata without a `source.hash` cannot be
+//! checked against the on-chain code and is not cached. Writes go to a temporary file that is atomically renamed into
+//! place, so concurrent processes never observe partially written entries.
+//!
+//! Layout:
+//!
+//! ```text
+//! <dir>/code/<code_hash>.json     cache entry with the metadata
+//! <dir>/addresses/<address>       code hash the address was last seen with
+//! ```
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ink_metadata::InkProject;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use glin_client::GlinClient;
+
+use crate::bundle::ContractBundle;
+use crate::metadata_source::{MetadataQuery, MetadataSource, SourceKind};
+
+/// A cached metadata file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Code hash the entry is stored under (hex)
+    code_hash: String,
+    /// Source wasm hash from the bundle (hex); entries without one are
+    /// rejected when read
+    source_hash: Option<String>,
+    /// Unix timestamp of when the entry was written
+    cached_at: u64,
+    /// The metadata JSON
+    metadata: JsonValue,
+}
+
+/// Code-hash-keyed metadata cache
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+}
+
+impl MetadataCache {
+    /// Cache in the given directory, without expiry
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: None,
+            max_entries: None,
+        }
+    }
+
+    /// Cache in the default directory (`~/.glin-forge/cache`)
+    pub fn default_location() -> Result<Self> {
+        Ok(Self::new(crate::metadata_fetcher::get_default_cache_dir()?))
+    }
+
+    /// Treat entries older than `ttl` as missing and remove them
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keep at most `max_entries` entries, evicting the oldest on insert
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get metadata cached for a code hash
+    ///
+    /// Expired entries and entries failing the integrity checks are removed
+    /// and reported as missing.
+    pub fn get(&self, code_hash: &[u8; 32]) -> Result<Option<InkProject>> {
+        let Some(entry) = self.read_entry(code_hash)? else {
+            return Ok(None);
+        };
+
+        if self.is_expired(&entry) || !entry_matches(&entry, code_hash) {
+            self.evict(code_hash)?;
+            return Ok(None);
+        }
+
+        let bundle = ContractBundle::from_value(entry.metadata)
+            .context("Invalid metadata in cache entry")?;
+        Ok(Some(bundle.metadata))
+    }
+
+    /// Store metadata JSON (a `.contract` bundle or metadata with a `source`
+    /// section) for a code hash
+    ///
+    /// Fails if the bundle has no source wasm hash or it does not match `code_hash`.
+    pub fn put_json(&self, code_hash: &[u8; 32], metadata: &JsonValue) -> Result<()> {
+        let source_hash = metadata
+            .get("source")
+            .and_then(|source| source.get("hash"))
+            .and_then(|hash| hash.as_str())
+            .map(normalize_hash)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Metadata has no source hash to check against code hash {}",
+                    hash_hex(code_hash)
+                )
+            })?;
+
+        if source_hash != hash_hex(code_hash) {
+            anyhow::bail!(
+                "Metadata source hash {} does not match code hash {}",
+                source_hash,
+                hash_hex(code_hash)
+            );
+        }
+
+        // The wasm is on-chain already; don't keep a copy of it
+        let mut metadata = metadata.clone();
+        if let Some(source) = metadata.get_mut("source").and_then(|s| s.as_object_mut()) {
+            source.remove("wasm");
+        }
+
+        let entry = CacheEntry {
+            code_hash: hash_hex(code_hash),
+            source_hash: Some(source_hash),
+            cached_at: now_secs(),
+            metadata,
+        };
+
+        let path = self.entry_path(code_hash);
+        write_atomic(&path, &serde_json::to_vec(&entry)?)?;
+        self.enforce_max_entries(&path)?;
+
+        Ok(())
+    }
+
+    /// Store a parsed bundle for a code hash
+    ///
+    /// Fails if the bundle's source wasm hash does not match `code_hash`.
+    pub fn put_bundle(&self, code_hash: &[u8; 32], bundle: &ContractBundle) -> Result<()> {
+        self.put_json(code_hash, &bundle.to_value()?)
+    }
+
+    /// Remove the entry for a code hash
+    pub fn evict(&self, code_hash: &[u8; 32]) -> Result<()> {
+        remove_if_exists(&self.entry_path(code_hash))
+    }
+
+    /// Record the code hash a contract address currently runs
+    pub fn link_address(&self, address: &str, code_hash: &[u8; 32]) -> Result<()> {
+        write_atomic(&self.address_path(address), hash_hex(code_hash).as_bytes())
+    }
+
+    /// Code hash an address was last linked to
+    ///
+    /// The contract's code may have changed since; use [`get_for_contract`](Self::get_for_contract)
+    /// to look up metadata against the on-chain code hash.
+    pub fn code_hash_for(&self, address: &str) -> Result<Option<[u8; 32]>> {
+        let path = self.address_path(address);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read cache index: {}", path.display()))?;
+        Ok(parse_hash(content.trim()))
+    }
+
+    /// Get cached metadata for a deployed contract
+    ///
+    /// Reads the contract's current code hash from chain, so stale metadata
+    /// of replaced code is never returned. Updates the address index.
+    pub async fn get_for_contract(
+        &self,
+        client: &GlinClient,
+        contract_address: &str,
+    ) -> Result<Option<InkProject>> {
+        let info = crate::chain_info::get_contract_info(client, contract_address).await?;
+
+        if self.code_hash_for(contract_address)? != Some(info.code_hash) {
+            self.link_address(contract_address, &info.code_hash)?;
+        }
+
+        self.get(&info.code_hash)
+    }
+
+    /// Remove expired and corrupt entries, returning the number removed
+    pub fn prune(&self) -> Result<usize> {
+        let mut removed = 0;
+
+        for (path, entry) in self.entries()? {
+            let valid = entry.as_ref().is_some_and(|entry| {
+                !self.is_expired(entry)
+                    && parse_hash(&entry.code_hash).is_some_and(|hash| {
+                        entry_matches(entry, &hash) && path == self.entry_path(&hash)
+                    })
+            });
+
+            if !valid {
+                remove_if_exists(&path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove all entries and the address index
+    pub fn clear(&self) -> Result<()> {
+        for dir in [self.dir.join("code"), self.dir.join("addresses")] {
+            if dir.is_dir() {
+                std::fs::remove_dir_all(&dir)
+                    .with_context(|| format!("Failed to remove {}", dir.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, code_hash: &[u8; 32]) -> PathBuf {
+        self.dir
+            .join("code")
+            .join(format!("{}.json", hash_hex(code_hash)))
+    }
+
+    fn address_path(&self, address: &str) -> PathBuf {
+        // Addresses are SS58 or hex; keep anything else out of the path
+        let name: String = address
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        self.dir.join("addresses").join(name)
+    }
+
+    fn read_entry(&self, code_hash: &[u8; 32]) -> Result<Option<CacheEntry>> {
+        let path = self.entry_path(code_hash);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read(&path)
+            .with_context(|| format!("Failed to read cache entry: {}", path.display()))?;
+
+        // A corrupt entry is a cache miss, not an error
+        match serde_json::from_slice(&content) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(_) => {
+                remove_if_exists(&path)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// All entry files with their parsed contents (`None` if corrupt)
+    fn entries(&self) -> Result<Vec<(PathBuf, Option<CacheEntry>)>> {
+        let dir = self.dir.join("code");
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for file in std::fs::read_dir(&dir)? {
+            let path = file?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let entry = std::fs::read(&path)
+                .ok()
+                .and_then(|content| serde_json::from_slice(&content).ok());
+            entries.push((path, entry));
+        }
+
+        Ok(entries)
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now_secs().saturating_sub(entry.cached_at) > ttl.as_secs())
+    }
+
+    /// Evict the oldest entries other than `keep` above `max_entries`
+    fn enforce_max_entries(&self, keep: &Path) -> Result<()> {
+        let Some(max_entries) = self.max_entries else {
+            return Ok(());
+        };
+
+        let mut entries: Vec<(PathBuf, u64)> = self
+            .entries()?
+            .into_iter()
+            .map(|(path, entry)| (path, entry.map_or(0, |entry| entry.cached_at)))
+            .collect();
+
+        if entries.len() <= max_entries {
+            return Ok(());
+        }
+
+        // Oldest first
+        entries.sort_by_key(|(_, cached_at)| *cached_at);
+        let excess = entries.len() - max_entries;
+        for (path, _) in entries
+            .into_iter()
+            .filter(|(path, _)| path != keep)
+            .take(excess)
+        {
+            remove_if_exists(&path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Only code hash queries are answered, as an address alone cannot tell
+/// whether the cached code is still current.
+#[async_trait]
+impl MetadataSource for MetadataCache {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Cache
+    }
+
+    async fn resolve(&self, query: &MetadataQuery) -> Result<Option<InkProject>> {
+        match &query.code_hash {
+            Some(code_hash) => self.get(code_hash),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Whether an entry belongs to the code hash it was requested for
+///
+/// Entries without a source hash (written by older versions) are unverified
+/// and never match.
+fn entry_matches(entry: &CacheEntry, code_hash: &[u8; 32]) -> bool {
+    let expected = hash_hex(code_hash);
+    normalize_hash(&entry.code_hash) == expected
+        && entry
+            .source_hash
+            .as_deref()
+            .is_some_and(|source_hash| normalize_hash(source_hash) == expected)
+}
+
+/// Write a file by renaming a fully written temporary file into place
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid cache path: {}", path.display()))?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path)
+        .with_context(|| format!("Failed to write cache file: {}", path.display()))?;
+
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+}
+
+fn hash_hex(hash: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(hash))
+}
+
+fn normalize_hash(hash: &str) -> String {
+    format!(
+        "0x{}",
+        hash.strip_prefix("0x").unwrap_or(hash).to_lowercase()
+    )
+}
+
+fn parse_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash.strip_prefix("0x").unwrap_or(hash))
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{flipper, v4_json};
+
+    const CODE_HASH: [u8; 32] = [0xaa; 32];
+
+    fn bundle_for(code_hash: &[u8; 32]) -> JsonValue {
+        let mut bundle = serde_json::to_value(flipper()).unwrap();
+        bundle["source"] = serde_json::json!({ "hash": hash_hex(code_hash) });
+        bundle
+    }
+
+    #[test]
+    fn test_put_get_and_integrity() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MetadataCache::new(dir.path());
+
+        let mut bundle = serde_json::to_value(flipper()).unwrap();
+        bundle["source"] = serde_json::json!({
+            "hash": hash_hex(&CODE_HASH),
+            "wasm": "0x0061736d",
+        });
+
+        cache.put_json(&CODE_HASH, &bundle).unwrap();
+        assert!(cache.get(&CODE_HASH).unwrap().is_some());
+        assert!(cache.get(&[0xbb; 32]).unwrap().is_none());
+        assert!(cache.put_json(&[0xbb; 32], &bundle).is_err());
+
+        let stored = std::fs::read_to_string(cache.entry_path(&CODE_HASH)).unwrap();
+        assert!(!stored.contains("0061736d"));
+
+        // An entry copied under another code hash is rejected and evicted
+        std::fs::copy(cache.entry_path(&CODE_HASH), cache.entry_path(&[0xcc; 32])).unwrap();
+        assert!(cache.get(&[0xcc; 32]).unwrap().is_none());
+        assert!(!cache.entry_path(&[0xcc; 32]).exists());
+
+        std::fs::write(cache.entry_path(&[0xdd; 32]), "corrupt").unwrap();
+        assert_eq!(cache.prune().unwrap(), 1);
+
+        // Entries in older metadata formats go through the versioned loader
+        let mut v4_bundle = v4_json(&flipper());
+        v4_bundle["source"] = serde_json::json!({ "hash": hash_hex(&[0xee; 32]) });
+        cache.put_json(&[0xee; 32], &v4_bundle).unwrap();
+        let metadata = cache.get(&[0xee; 32]).unwrap().unwrap();
+        assert_eq!(*metadata.version(), 4);
+
+        let bundle = ContractBundle::from_value(v4_bundle).unwrap();
+        assert!(cache.put_bundle(&[0xef; 32], &bundle).is_err());
+
+        // Metadata without a source hash is neither stored nor returned
+        let bare = serde_json::to_value(flipper()).unwrap();
+        assert!(cache.put_json(&[0xab; 32], &bare).is_err());
+        let mut unverified = cache.read_entry(&CODE_HASH).unwrap().unwrap();
+        unverified.source_hash = None;
+        write_atomic(
+            &cache.entry_path(&CODE_HASH),
+            &serde_json::to_vec(&unverified).unwrap(),
+        )
+        .unwrap();
+        assert!(cache.get(&CODE_HASH).unwrap().is_none());
+
+        cache.link_address("5Contract", &CODE_HASH).unwrap();
+        assert_eq!(cache.code_hash_for("5Contract").unwrap(), Some(CODE_HASH));
+        assert_eq!(cache.code_hash_for("5Other").unwrap(), None);
+    }
+
+    #[test]
+    fn test_ttl_and_max_entries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let cache = MetadataCache::new(dir.path()).with_max_entries(2);
+        for byte in 1..=3u8 {
+            cache
+                .put_json(&[byte; 32], &bundle_for(&[byte; 32]))
+                .unwrap();
+        }
+        assert_eq!(cache.entries().unwrap().len(), 2);
+
+        let cache = MetadataCache::new(dir.path()).with_ttl(Duration::from_secs(60));
+        let mut entry = cache.read_entry(&[3; 32]).unwrap().unwrap();
+        entry.cached_at -= 120;
+        write_atomic(
+            &cache.entry_path(&[3; 32]),
+            &serde_json::to_vec(&entry).unwrap(),
+        )
+        .unwrap();
+
+        assert!(cache.get(&[3; 32]).unwrap().is_none());
+        assert!(!cache.entry_path(&[3; 32]).exists());
+    }
+}
@@ -1,190 +1,112 @@
 // Multi-strategy metadata fetching for ink! contracts
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use ink_metadata::InkProject;
 use std::path::Path;
 
 use glin_client::GlinClient;
 
+use crate::metadata_cache::MetadataCache;
+use crate::metadata_source::{
+    load_metadata_file, ExplorerSource, FileSource, MetadataQuery, MetadataResolver, SourceKind,
+};
+
 /// Options for fetching metadata
+#[derive(Debug, Clone, Default)]
 pub struct MetadataFetchOptions {
     pub local_path: Option<String>,
     pub explorer_url: Option<String>,
     pub cache_dir: Option<String>,
 }
 
+impl MetadataFetchOptions {
+    /// Resolver chain for these options: local file, cache directory, explorer
+    ///
+    /// Append custom sources to the returned resolver and pass it to
+    /// [`fetch_contract_metadata_with`] to extend the default lookup.
+    pub fn resolver(&self) -> Result<MetadataResolver> {
+        let mut resolver = MetadataResolver::new();
+
+        if let Some(path) = &self.local_path {
+            resolver = resolver.with_source(FileSource::new(path));
+        }
+        if let Some(cache_dir) = &self.cache_dir {
+            resolver = resolver.with_source(MetadataCache::new(cache_dir));
+        }
+        if let Some(explorer_url) = &self.explorer_url {
+            resolver = resolver.with_source(ExplorerSource::new(explorer_url)?);
+        }
+
+        Ok(resolver)
+    }
+}
+
 /// Fetch contract metadata using cascading fallback strategy
 ///
 /// Strategy priority:
 /// 1. Local file (if provided via --metadata flag)
-/// 2. Local cache (~/.glin-forge/cache/{address}.json)
-/// 3. Get code_hash from chain ContractInfoOf storage
-/// 4. Fetch from explorer API using code_hash
-/// 5. Fail with helpful error message
+/// 2. Local cache (~/.glin-forge/cache), keyed by the code hash from chain ContractInfoOf storage
+/// 3. Explorer API, by address and by code hash
+/// 4. Fail with helpful error message
+///
+/// Metadata fetched from the explorer is saved to the cache directory under the
+/// contract's current code hash (see [`MetadataCache`]), if its bundle's
+/// `source.hash` matches that code hash.
 pub async fn fetch_contract_metadata(
     client: &GlinClient,
     contract_address: &str,
     options: MetadataFetchOptions,
 ) -> Result<InkProject> {
-    // Strategy 1: Load from local file if provided
-    if let Some(path) = options.local_path {
-        return load_metadata_from_file(&path);
-    }
-
-    // Strategy 2: Try loading from cache
-    if let Some(cache_dir) = &options.cache_dir {
-        if let Ok(metadata) = try_load_from_cache(cache_dir, contract_address) {
-            return Ok(metadata);
-        }
+    // A local file is authoritative; don't fall back to other sources
+    if let Some(path) = &options.local_path {
+        return load_metadata_file(Path::new(path));
     }
 
-    // Strategy 3: Get code hash from blockchain
-    let code_hash_hex = match crate::chain_info::get_contract_info(client, contract_address).await {
-        Ok(info) => Some(format!("0x{}", hex::encode(info.code_hash))),
-        Err(_e) => None,
-    };
-
-    // Strategy 4: Fetch from explorer API
-    if let Some(explorer_url) = options.explorer_url {
-        match fetch_from_explorer(&explorer_url, contract_address, code_hash_hex.as_deref()).await {
-            Ok(metadata) => {
-                // Cache it for future use
-                if let Some(cache_dir) = &options.cache_dir {
-                    let _ = save_to_cache(cache_dir, contract_address, &metadata);
+    let resolver = options.resolver()?;
+    let query = MetadataQuery::for_contract(client, contract_address).await;
+
+    match resolver.resolve_with_source(&query).await {
+        Ok((bundle, source)) => {
+            // Cache it for future use, but only if the bundle proves which
+            // code it belongs to: metadata found by address may be stale
+            if let (Some(cache_dir), Some(code_hash), SourceKind::Remote) =
+                (&options.cache_dir, &query.code_hash, source.kind())
+            {
+                if bundle.source.code_hash() == Some(*code_hash) {
+                    let cache = MetadataCache::new(cache_dir);
+                    let _ = cache.put_bundle(code_hash, &bundle);
+                    let _ = cache.link_address(contract_address, code_hash);
                 }
-
-                return Ok(metadata);
-            }
-            Err(_e) => {
-                // Continue to error
             }
-        }
-    }
 
-    // All strategies failed
-    Err(anyhow::anyhow!(
-        r#"Could not fetch metadata for contract {}
+            Ok(bundle.metadata)
+        }
+        Err(err) => Err(anyhow::anyhow!(
+            r#"Could not fetch metadata for contract {}
 
 Metadata is not stored on-chain. Please provide it using one of these methods:
 
 1. Specify metadata file via local_path option
 2. Use an explorer with verification via explorer_url option
-3. Place metadata in cache directory: ~/.glin-forge/cache/{}.json
+3. Add the .contract bundle to the cache with MetadataCache::put_json
 
 For more info, see: https://use.ink/basics/metadata
-"#,
-        contract_address,
-        contract_address
-    ))
-}
-
-/// Load metadata from local file (.json or .contract bundle)
-fn load_metadata_from_file(path: &str) -> Result<InkProject> {
-    let content = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read metadata file: {}", path))?;
-
-    // Check if it's a .contract bundle file
-    if path.ends_with(".contract") {
-        let bundle: serde_json::Value =
-            serde_json::from_str(&content).context("Invalid .contract bundle format")?;
 
-        // Extract spec from bundle
-        let metadata: InkProject = serde_json::from_value(bundle["spec"].clone())
-            .context("Invalid metadata in .contract bundle")?;
-
-        Ok(metadata)
-    } else {
-        // Pure metadata JSON
-        let metadata: InkProject =
-            serde_json::from_str(&content).context("Invalid metadata JSON format")?;
-
-        Ok(metadata)
+Caused by: {:#}
+"#,
+            contract_address,
+            err
+        )),
     }
 }
 
-/// Fetch metadata from explorer API
-async fn fetch_from_explorer(
-    explorer_url: &str,
+/// Fetch contract metadata from a custom resolver chain
+pub async fn fetch_contract_metadata_with(
+    client: &GlinClient,
     contract_address: &str,
-    code_hash: Option<&str>,
+    resolver: &MetadataResolver,
 ) -> Result<InkProject> {
-    // Try common explorer API endpoints with both contract address and code hash
-    let mut endpoints = vec![
-        // Try contract address first
-        format!(
-            "{}/api/contract/{}/metadata",
-            explorer_url, contract_address
-        ),
-        format!("{}/api/contracts/{}/abi", explorer_url, contract_address),
-        format!(
-            "{}/api/v1/contracts/{}/metadata",
-            explorer_url, contract_address
-        ),
-    ];
-
-    // If we have code hash, also try those endpoints
-    if let Some(hash) = code_hash {
-        endpoints.extend(vec![
-            format!("{}/api/contract/{}/metadata", explorer_url, hash),
-            format!("{}/api/contracts/metadata/{}", explorer_url, hash),
-            format!("{}/api/v1/code/{}/abi", explorer_url, hash),
-        ]);
-    }
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
-
-    for url in endpoints {
-        match client.get(&url).send().await {
-            Ok(response) if response.status().is_success() => {
-                // Try to parse as InkProject directly
-                if let Ok(metadata) = response.json::<InkProject>().await {
-                    return Ok(metadata);
-                }
-            }
-            Ok(response) => {
-                // Log non-success status
-                eprintln!(
-                    "    Endpoint {} returned status: {}",
-                    url,
-                    response.status()
-                );
-            }
-            Err(e) => {
-                // Log connection errors
-                eprintln!("    Failed to connect to {}: {}", url, e);
-            }
-        }
-    }
-
-    Err(anyhow::anyhow!(
-        "No explorer endpoint returned valid metadata for contract {}",
-        contract_address
-    ))
-}
-
-/// Try to load metadata from local cache
-fn try_load_from_cache(cache_dir: &str, contract_address: &str) -> Result<InkProject> {
-    let cache_path = Path::new(cache_dir).join(format!("{}.json", contract_address));
-
-    if !cache_path.exists() {
-        anyhow::bail!("No cache found");
-    }
-
-    load_metadata_from_file(cache_path.to_str().unwrap())
-}
-
-/// Save metadata to local cache
-fn save_to_cache(cache_dir: &str, contract_address: &str, metadata: &InkProject) -> Result<()> {
-    std::fs::create_dir_all(cache_dir)?;
-
-    let cache_path = Path::new(cache_dir).join(format!("{}.json", contract_address));
-
-    let json = serde_json::to_string_pretty(metadata)?;
-    std::fs::write(cache_path, json)?;
-
-    Ok(())
+    resolver.resolve_contract(client, contract_address).await
 }
 
 /// Get default cache directory
@@ -206,6 +128,18 @@ mod tests {
         // Skipping for now
     }
 
+    #[test]
+    fn test_options_resolver_order() {
+        let options = MetadataFetchOptions {
+            local_path: None,
+            explorer_url: Some("https://explorer.example".to_string()),
+            cache_dir: Some("/tmp/cache".to_string()),
+        };
+
+        let resolver = options.resolver().unwrap();
+        assert_eq!(resolver.source_names(), vec!["cache", "explorer"]);
+    }
+
     #[test]
     fn test_get_default_cache_dir() {
         let cache_dir = get_default_cache_dir();
@@ -0,0 +1,741 @@
+//! Pluggable metadata sources
+//!
+//! A [`MetadataSource`] looks up ink! metadata for a contract address and/or
+//! code hash. Sources are combined into a [`MetadataResolver`], which asks
+//! them in order until one returns metadata. Besides the built-in sources
+//! (local file, bundle directory, HTTP explorer, in-memory), any type
+//! implementing the trait can be added, e.g. for an internal registry.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ink_metadata::InkProject;
+use serde_json::Value as JsonValue;
+
+use glin_client::GlinClient;
+
+use crate::bundle::ContractBundle;
+use crate::metadata;
+
+/// What to look up metadata for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataQuery {
+    /// Contract address, as given by the caller
+    pub address: Option<String>,
+    /// Code hash of the contract
+    pub code_hash: Option<[u8; 32]>,
+}
+
+impl MetadataQuery {
+    /// Query by contract address only
+    pub fn address(address: impl Into<String>) -> Self {
+        Self {
+            address: Some(address.into()),
+            code_hash: None,
+        }
+    }
+
+    /// Query by code hash only
+    pub fn code_hash(code_hash: [u8; 32]) -> Self {
+        Self {
+            address: None,
+            code_hash: Some(code_hash),
+        }
+    }
+
+    /// Query for a deployed contract, with its code hash looked up on-chain
+    ///
+    /// The code hash is left empty if the contract info cannot be read.
+    pub async fn for_contract(client: &GlinClient, contract_address: &str) -> Self {
+        let code_hash = crate::chain_info::get_contract_info(client, contract_address)
+            .await
+            .ok()
+            .map(|info| info.code_hash);
+
+        Self {
+            address: Some(contract_address.to_string()),
+            code_hash,
+        }
+    }
+
+    /// Code hash as `0x`-prefixed hex
+    pub fn code_hash_hex(&self) -> Option<String> {
+        self.code_hash
+            .map(|hash| format!("0x{}", hex::encode(hash)))
+    }
+}
+
+/// Where a source gets its metadata from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Files or memory supplied by the caller
+    Local,
+    /// A cache of metadata previously fetched from other sources
+    Cache,
+    /// A remote service; its results are worth caching
+    Remote,
+}
+
+/// A place ink! metadata can be loaded from
+///
+/// `resolve` returns `Ok(None)` if the source has no metadata for the query;
+/// errors are reserved for failures such as unreadable files or invalid JSON.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use async_trait::async_trait;
+/// use glin_contracts::metadata_source::{MetadataQuery, MetadataSource};
+/// use ink_metadata::InkProject;
+///
+/// struct Registry;
+///
+/// #[async_trait]
+/// impl MetadataSource for Registry {
+///     fn name(&self) -> &str {
+///         "registry"
+///     }
+///
+///     async fn resolve(&self, query: &MetadataQuery) -> anyhow::Result<Option<InkProject>> {
+///         let Some(code_hash) = query.code_hash_hex() else {
+///             return Ok(None);
+///         };
+///         // Look up `code_hash` in the registry...
+///         # let _ = code_hash;
+///         Ok(None)
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait MetadataSource: Send + Sync {
+    /// Short name of the source, used in error messages
+    fn name(&self) -> &str;
+
+    /// Where the metadata comes from; custom sources are assumed to be remote
+    fn kind(&self) -> SourceKind {
+        SourceKind::Remote
+    }
+
+    /// Look up metadata for the query
+    async fn resolve(&self, query: &MetadataQuery) -> Result<Option<InkProject>>;
+
+    /// Look up metadata together with its bundle sections
+    ///
+    /// Sources that receive whole bundles should override this, so that the
+    /// `source.hash` can be checked against the code hash, e.g. before the
+    /// result is cached. By default the metadata is returned without them.
+    async fn resolve_bundle(&self, query: &MetadataQuery) -> Result<Option<ContractBundle>> {
+        Ok(self
+            .resolve(query)
+            .await?
+            .map(ContractBundle::from_metadata))
+    }
+}
+
+/// Ordered chain of metadata sources
+#[derive(Clone, Default)]
+pub struct MetadataResolver {
+    sources: Vec<Arc<dyn MetadataSource>>,
+}
+
+impl MetadataResolver {
+    /// Create a resolver without any sources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a source to the chain
+    pub fn with_source(mut self, source: impl MetadataSource + 'static) -> Self {
+        self.push(Arc::new(source));
+        self
+    }
+
+    /// Append a shared source to the chain
+    pub fn push(&mut self, source: Arc<dyn MetadataSource>) {
+        self.sources.push(source);
+    }
+
+    /// Names of the sources, in resolution order
+    pub fn source_names(&self) -> Vec<&str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+
+    /// Resolve metadata, asking each source in order
+    pub async fn resolve(&self, query: &MetadataQuery) -> Result<InkProject> {
+        self.resolve_with_source(query)
+            .await
+            .map(|(bundle, _)| bundle.metadata)
+    }
+
+    /// Resolve a bundle, also returning the source that provided it
+    pub async fn resolve_with_source(
+        &self,
+        query: &MetadataQuery,
+    ) -> Result<(ContractBundle, &dyn MetadataSource)> {
+        let mut failures = Vec::new();
+
+        for source in &self.sources {
+            match source.resolve_bundle(query).await {
+                Ok(Some(bundle)) => return Ok((bundle, source.as_ref())),
+                Ok(None) => {}
+                Err(err) => failures.push(format!("{}: {:#}", source.name(), err)),
+            }
+        }
+
+        let target = match (&query.address, query.code_hash_hex()) {
+            (Some(address), _) => format!("contract {}", address),
+            (None, Some(code_hash)) => format!("code hash {}", code_hash),
+            (None, None) => "empty query".to_string(),
+        };
+
+        if failures.is_empty() {
+            anyhow::bail!(
+                "No metadata source has metadata for {} (tried: {})",
+                target,
+                self.source_names().join(", ")
+            );
+        }
+
+        anyhow::bail!(
+            "No metadata source has metadata for {}; failed sources:\n  {}",
+            target,
+            failures.join("\n  ")
+        )
+    }
+
+    /// Resolve metadata for a deployed contract (see [`MetadataQuery::for_contract`])
+    pub async fn resolve_contract(
+        &self,
+        client: &GlinClient,
+        contract_address: &str,
+    ) -> Result<InkProject> {
+        let query = MetadataQuery::for_contract(client, contract_address).await;
+        self.resolve(&query).await
+    }
+}
+
+/// A single metadata file (`.json` or `.contract` bundle), returned for every query
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl MetadataSource for FileSource {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Local
+    }
+
+    async fn resolve(&self, _query: &MetadataQuery) -> Result<Option<InkProject>> {
+        load_metadata_file(&self.path).map(Some)
+    }
+}
+
+/// A directory of metadata files and `.contract` bundles
+///
+/// Files are indexed by the code hash in their `source.hash` field. Files named
+/// `<address>.json` are also found by contract address, unless their code hash
+/// differs from the queried one.
+///
+/// The index is built on first use and kept; files are only read again when
+/// they change or a code hash is not found. Lookups run on the blocking pool.
+#[derive(Debug, Clone)]
+pub struct DirectorySource {
+    dir: PathBuf,
+    index: Arc<Mutex<DirectoryIndex>>,
+}
+
+/// Code hashes of the files in a directory, as of the last scan
+#[derive(Debug, Default)]
+struct DirectoryIndex {
+    /// Modification time of the directory at the last scan
+    scanned: Option<SystemTime>,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+/// A metadata file and the state it was indexed in
+#[derive(Debug)]
+struct IndexedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    code_hash: Option<[u8; 32]>,
+}
+
+impl IndexedFile {
+    /// Whether the file is unchanged since it was indexed
+    fn is_current(&self, path: &Path) -> bool {
+        std::fs::metadata(path)
+            .is_ok_and(|meta| meta.modified().ok() == self.modified && meta.len() == self.len)
+    }
+}
+
+impl DirectoryIndex {
+    fn find(&self, code_hash: &[u8; 32]) -> Option<(&PathBuf, &IndexedFile)> {
+        self.files
+            .iter()
+            .find(|(_, file)| file.code_hash.as_ref() == Some(code_hash))
+    }
+}
+
+impl DirectorySource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            index: Arc::default(),
+        }
+    }
+
+    /// Map of code hash to file for all metadata files in the directory
+    pub fn index(&self) -> Result<HashMap<[u8; 32], PathBuf>> {
+        let mut index = self.lock_index();
+        self.rescan(&mut index)?;
+
+        Ok(index
+            .files
+            .iter()
+            .filter_map(|(path, file)| file.code_hash.map(|hash| (hash, path.clone())))
+            .collect())
+    }
+
+    fn lock_index(&self) -> std::sync::MutexGuard<'_, DirectoryIndex> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// File indexed under `code_hash`, rescanning if the directory or the
+    /// file changed or the hash is unknown
+    fn lookup(&self, code_hash: &[u8; 32]) -> Result<Option<PathBuf>> {
+        let mut index = self.lock_index();
+
+        let dir_modified = std::fs::metadata(&self.dir)
+            .and_then(|meta| meta.modified())
+            .ok();
+        let fresh = index.scanned.is_some()
+            && index.scanned == dir_modified
+            && index
+                .find(code_hash)
+                .is_some_and(|(path, file)| file.is_current(path));
+        if !fresh {
+            self.rescan(&mut index)?;
+        }
+
+        Ok(index.find(code_hash).map(|(path, _)| path.clone()))
+    }
+
+    /// Bring the index up to date, reading only new and changed files
+    fn rescan(&self, index: &mut DirectoryIndex) -> Result<()> {
+        if !self.dir.is_dir() {
+            *index = DirectoryIndex::default();
+            return Ok(());
+        }
+
+        let scanned = std::fs::metadata(&self.dir)
+            .and_then(|meta| meta.modified())
+            .ok();
+        let entries = std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read directory: {}", self.dir.display()))?;
+
+        let mut files = HashMap::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !is_metadata_file(&path) {
+                continue;
+            }
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+
+            let file = match index.files.remove(&path) {
+                Some(file) if file.is_current(&path) => file,
+                _ => IndexedFile {
+                    modified: meta.modified().ok(),
+                    len: meta.len(),
+                    code_hash: read_code_hash(&path),
+                },
+            };
+            files.insert(path, file);
+        }
+
+        index.files = files;
+        index.scanned = scanned;
+        Ok(())
+    }
+
+    fn resolve_blocking(&self, query: &MetadataQuery) -> Result<Option<ContractBundle>> {
+        if let Some(address) = &query.address {
+            let path = self.dir.join(format!("{}.json", address));
+            if path.is_file() {
+                let bundle = ContractBundle::from_file(&path)?;
+
+                // The contract may run different code since the file was written
+                let stale = matches!(
+                    (&query.code_hash, bundle.source.code_hash()),
+                    (Some(expected), Some(actual)) if *expected != actual
+                );
+                if !stale {
+                    return Ok(Some(bundle));
+                }
+            }
+        }
+
+        if let Some(code_hash) = &query.code_hash {
+            if let Some(path) = self.lookup(code_hash)? {
+                return ContractBundle::from_file(&path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl MetadataSource for DirectorySource {
+    fn name(&self) -> &str {
+        "directory"
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Local
+    }
+
+    async fn resolve(&self, query: &MetadataQuery) -> Result<Option<InkProject>> {
+        Ok(self
+            .resolve_bundle(query)
+            .await?
+            .map(|bundle| bundle.metadata))
+    }
+
+    async fn resolve_bundle(&self, query: &MetadataQuery) -> Result<Option<ContractBundle>> {
+        let source = self.clone();
+        let query = query.clone();
+
+        tokio::task::spawn_blocking(move || source.resolve_blocking(&query))
+            .await
+            .context("Directory lookup failed")?
+    }
+}
+
+/// A block explorer serving verified contract metadata over HTTP
+#[derive(Debug, Clone)]
+pub struct ExplorerSource {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl ExplorerSource {
+    pub fn new(url: impl Into<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+
+        Ok(Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            http,
+        })
+    }
+
+    /// Endpoints tried for a query, by address first
+    fn endpoints(&self, query: &MetadataQuery) -> Vec<String> {
+        let url = &self.url;
+        let mut endpoints = Vec::new();
+
+        if let Some(address) = &query.address {
+            endpoints.extend([
+                format!("{}/api/contract/{}/metadata", url, address),
+                format!("{}/api/contracts/{}/abi", url, address),
+                format!("{}/api/v1/contracts/{}/metadata", url, address),
+            ]);
+        }
+
+        if let Some(hash) = query.code_hash_hex() {
+            endpoints.extend([
+                format!("{}/api/contract/{}/metadata", url, hash),
+                format!("{}/api/contracts/metadata/{}", url, hash),
+                format!("{}/api/v1/code/{}/abi", url, hash),
+            ]);
+        }
+
+        endpoints
+    }
+}
+
+#[async_trait]
+impl MetadataSource for ExplorerSource {
+    fn name(&self) -> &str {
+        "explorer"
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Remote
+    }
+
+    async fn resolve(&self, query: &MetadataQuery) -> Result<Option<InkProject>> {
+        Ok(self
+            .resolve_bundle(query)
+            .await?
+            .map(|bundle| bundle.metadata))
+    }
+
+    async fn resolve_bundle(&self, query: &MetadataQuery) -> Result<Option<ContractBundle>> {
+        let mut errors = Vec::new();
+
+        for url in self.endpoints(query) {
+            match self.http.get(&url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    // Any metadata version or bundle; an unparsable body is
+                    // reported if no other endpoint has the metadata
+                    let parsed = match response.text().await {
+                        Ok(body) => ContractBundle::from_json(&body),
+                        Err(e) => Err(e.into()),
+                    };
+                    match parsed {
+                        Ok(bundle) => return Ok(Some(bundle)),
+                        Err(e) => errors.push(format!("{}: {:#}", url, e)),
+                    }
+                }
+                // Not verified on this explorer (or endpoint not supported)
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: {}", url, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(None)
+        } else {
+            anyhow::bail!("Explorer requests failed: {}", errors.join("; "))
+        }
+    }
+}
+
+/// Metadata held in memory, keyed by address or code hash
+///
+/// Metadata is stored as JSON and parsed on every lookup.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    by_address: Arc<RwLock<HashMap<String, String>>>,
+    by_code_hash: Arc<RwLock<HashMap<[u8; 32], String>>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register metadata JSON for a contract address
+    pub fn insert_address(&self, address: impl Into<String>, metadata_json: impl Into<String>) {
+        self.by_address
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(address.into(), metadata_json.into());
+    }
+
+    /// Register metadata JSON for a code hash
+    pub fn insert_code_hash(&self, code_hash: [u8; 32], metadata_json: impl Into<String>) {
+        self.by_code_hash
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(code_hash, metadata_json.into());
+    }
+}
+
+#[async_trait]
+impl MetadataSource for MemorySource {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn kind(&self) -> SourceKind {
+        SourceKind::Local
+    }
+
+    async fn resolve(&self, query: &MetadataQuery) -> Result<Option<InkProject>> {
+        let json = {
+            let by_address = self.by_address.read().unwrap_or_else(|e| e.into_inner());
+            let by_code_hash = self.by_code_hash.read().unwrap_or_else(|e| e.into_inner());
+
+            query
+                .address
+                .as_ref()
+                .and_then(|address| by_address.get(address))
+                .or_else(|| query.code_hash.as_ref().and_then(|h| by_code_hash.get(h)))
+                .cloned()
+        };
+
+        json.map(|json| metadata::parse_metadata(&json)).transpose()
+    }
+}
+
+/// Load metadata from a `.json` file or `.contract` bundle
+pub(crate) fn load_metadata_file(path: &Path) -> Result<InkProject> {
+    Ok(ContractBundle::from_file(path)?.metadata)
+}
+
+fn is_metadata_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("json" | "contract")
+    )
+}
+
+/// Code hash of a metadata file; `None` for unreadable or foreign files
+fn read_code_hash(path: &Path) -> Option<[u8; 32]> {
+    let content = std::fs::read_to_string(path).ok()?;
+    source_code_hash(&serde_json::from_str(&content).ok()?)
+}
+
+/// Code hash from the `source.hash` field of metadata JSON
+fn source_code_hash(json: &JsonValue) -> Option<[u8; 32]> {
+    let hash = json.get("source")?.get("hash")?.as_str()?;
+    hex::decode(hash.strip_prefix("0x").unwrap_or(hash))
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{flipper, v4_json};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn flipper_json(code_hash: [u8; 32]) -> String {
+        let mut json = serde_json::to_value(flipper()).unwrap();
+        json["source"] = serde_json::json!({ "hash": format!("0x{}", hex::encode(code_hash)) });
+        json.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_resolver_chain_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("flipper.contract"), flipper_json([1; 32])).unwrap();
+        std::fs::write(dir.path().join("notes.json"), "not json").unwrap();
+
+        let memory = MemorySource::new();
+        memory.insert_address("5Memory", flipper_json([2; 32]));
+
+        let resolver = MetadataResolver::new()
+            .with_source(memory)
+            .with_source(DirectorySource::new(dir.path()));
+        assert_eq!(resolver.source_names(), vec!["memory", "directory"]);
+
+        let (_, source) = resolver
+            .resolve_with_source(&MetadataQuery::address("5Memory"))
+            .await
+            .unwrap();
+        assert_eq!(source.name(), "memory");
+        assert_eq!(source.kind(), SourceKind::Local);
+
+        let (bundle, source) = resolver
+            .resolve_with_source(&MetadataQuery::code_hash([1; 32]))
+            .await
+            .unwrap();
+        assert_eq!(source.name(), "directory");
+        assert_eq!(bundle.metadata.spec().messages().len(), 3);
+
+        let err = resolver
+            .resolve(&MetadataQuery::code_hash([3; 32]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memory, directory"));
+    }
+
+    #[tokio::test]
+    async fn test_directory_index_and_address_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("flipper.contract"), flipper_json([1; 32])).unwrap();
+        std::fs::write(dir.path().join("5Flipper.json"), flipper_json([2; 32])).unwrap();
+        let source = DirectorySource::new(dir.path());
+
+        let query = MetadataQuery::code_hash([1; 32]);
+        let bundle = source.resolve_bundle(&query).await.unwrap().unwrap();
+        assert_eq!(bundle.source.code_hash(), Some([1; 32]));
+
+        // Files added after the first scan are picked up on a miss
+        std::fs::write(dir.path().join("other.contract"), flipper_json([3; 32])).unwrap();
+        let query = MetadataQuery::code_hash([3; 32]);
+        assert!(source.resolve(&query).await.unwrap().is_some());
+        assert_eq!(source.index().unwrap().len(), 3);
+
+        // An address file is skipped when its code hash is not the queried one
+        let query = MetadataQuery {
+            address: Some("5Flipper".into()),
+            code_hash: Some([1; 32]),
+        };
+        let bundle = source.resolve_bundle(&query).await.unwrap().unwrap();
+        assert_eq!(bundle.source.code_hash(), Some([1; 32]));
+
+        let query = MetadataQuery::address("5Flipper");
+        let bundle = source.resolve_bundle(&query).await.unwrap().unwrap();
+        assert_eq!(bundle.source.code_hash(), Some([2; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_source_errors_are_reported() {
+        let resolver =
+            MetadataResolver::new().with_source(FileSource::new("/nonexistent/metadata.json"));
+
+        let err = resolver
+            .resolve(&MetadataQuery::address("5Contract"))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("file: Failed to read metadata file"));
+    }
+
+    /// Serve `body` with status 200 for every request
+    async fn serve(body: String) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_explorer_parses_versioned_metadata() {
+        let url = serve(v4_json(&flipper()).to_string()).await;
+        let explorer = ExplorerSource::new(url).unwrap();
+
+        let metadata = explorer
+            .resolve(&MetadataQuery::address("5Contract"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*metadata.version(), 4);
+
+        // Bodies that aren't metadata are reported, not skipped silently
+        let url = serve("{}".to_string()).await;
+        let err = ExplorerSource::new(url)
+            .unwrap()
+            .resolve(&MetadataQuery::address("5Contract"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Missing `version` field"));
+    }
+}
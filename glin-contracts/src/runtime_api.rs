@@ -0,0 +1,260 @@
+//! Contracts runtime API (dry-run) calls
+//!
+//! Encodes `ContractsApi_*` state_call payloads and decodes the
+//! `ContractResult` returned by the runtime.
+
+use anyhow::{Context, Result};
+use scale::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use subxt::Metadata;
+
+use glin_client::GlinClient;
+
+use crate::errors::{self, ContractError};
+use crate::tx::Weight;
+
+/// Flag set in [`ExecReturnValue::flags`] when the contract reverted
+pub const REVERT_FLAG: u32 = 0x0000_0001;
+
+/// Storage deposit charged or refunded by a contract execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub enum StorageDeposit {
+    /// Deposit refunded to the caller
+    Refund(u128),
+    /// Deposit charged from the caller
+    Charge(u128),
+}
+
+impl StorageDeposit {
+    /// Amount charged, or zero for refunds
+    pub fn charge_or_zero(&self) -> u128 {
+        match self {
+            StorageDeposit::Charge(amount) => *amount,
+            StorageDeposit::Refund(_) => 0,
+        }
+    }
+}
+
+impl Default for StorageDeposit {
+    fn default() -> Self {
+        StorageDeposit::Charge(0)
+    }
+}
+
+/// Output of a contract execution
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct ExecReturnValue {
+    /// Return flags set by the contract
+    pub flags: u32,
+    /// Raw output buffer returned by the contract
+    pub data: Vec<u8>,
+}
+
+impl ExecReturnValue {
+    /// Whether the contract reverted its state changes
+    pub fn did_revert(&self) -> bool {
+        self.flags & REVERT_FLAG != 0
+    }
+}
+
+/// Output of a successful contract instantiation
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct InstantiateReturnValue {
+    /// Output of the constructor
+    pub result: ExecReturnValue,
+    /// Address of the new contract
+    pub account_id: [u8; 32],
+}
+
+/// Code to instantiate a contract from
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum Code {
+    /// Upload new WASM code
+    Upload(Vec<u8>),
+    /// Use code already stored on-chain
+    Existing([u8; 32]),
+}
+
+/// Result of a contracts runtime API dry-run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractResult<R> {
+    /// Weight consumed by the execution
+    pub gas_consumed: Weight,
+    /// Weight required to execute successfully
+    pub gas_required: Weight,
+    /// Storage deposit charged or refunded
+    pub storage_deposit: StorageDeposit,
+    /// Debug message emitted by the contract (debug builds only)
+    pub debug_message: String,
+    /// Contract output, or the dispatch error that aborted execution
+    pub result: std::result::Result<R, ContractError>,
+}
+
+/// Result of a `ContractsApi_call` dry-run
+pub type CallDryRunResult = ContractResult<ExecReturnValue>;
+
+/// Result of a `ContractsApi_instantiate` dry-run
+pub type InstantiateDryRunResult = ContractResult<InstantiateReturnValue>;
+
+/// Dry-run a contract message call through `ContractsApi_call`
+pub async fn dry_run_call(
+    client: &GlinClient,
+    origin: [u8; 32],
+    dest: [u8; 32],
+    value: u128,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<u128>,
+    input_data: Vec<u8>,
+) -> Result<CallDryRunResult> {
+    let params = (
+        origin,
+        dest,
+        value,
+        gas_limit,
+        storage_deposit_limit,
+        input_data,
+    )
+        .encode();
+
+    let raw = state_call(client, "ContractsApi_call", &params).await?;
+
+    decode_contract_result(&raw, client.metadata())
+}
+
+/// Dry-run a contract instantiation through `ContractsApi_instantiate`
+#[allow(clippy::too_many_arguments)]
+pub async fn dry_run_instantiate(
+    client: &GlinClient,
+    origin: [u8; 32],
+    value: u128,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<u128>,
+    code: Code,
+    data: Vec<u8>,
+    salt: Vec<u8>,
+) -> Result<InstantiateDryRunResult> {
+    let params = (
+        origin,
+        value,
+        gas_limit,
+        storage_deposit_limit,
+        code,
+        data,
+        salt,
+    )
+        .encode();
+
+    let raw = state_call(client, "ContractsApi_instantiate", &params).await?;
+
+    decode_contract_result(&raw, client.metadata())
+}
+
+/// Read a raw storage item of a contract through `ContractsApi_get_storage`
+///
+/// `key` is the unhashed storage key as used by the contract. Returns `None`
+/// if the item is not set.
+pub async fn get_storage(
+    client: &GlinClient,
+    address: [u8; 32],
+    key: Vec<u8>,
+) -> Result<Option<Vec<u8>>> {
+    let params = (address, key).encode();
+
+    let raw = state_call(client, "ContractsApi_get_storage", &params).await?;
+
+    decode_get_storage_result(&raw)
+}
+
+/// Decode `Result<Option<Vec<u8>>, ContractAccessError>`
+fn decode_get_storage_result(raw: &[u8]) -> Result<Option<Vec<u8>>> {
+    let mut cursor = raw;
+
+    match u8::decode(&mut cursor).context("Failed to decode storage result")? {
+        0 => Option::<Vec<u8>>::decode(&mut cursor).context("Failed to decode storage value"),
+        1 => match u8::decode(&mut cursor).context("Failed to decode access error")? {
+            0 => anyhow::bail!("Contract does not exist"),
+            1 => anyhow::bail!("Storage key could not be decoded"),
+            2 => anyhow::bail!("Contract storage migration in progress"),
+            other => anyhow::bail!("Unknown contract access error: {}", other),
+        },
+        other => anyhow::bail!("Invalid result variant index: {}", other),
+    }
+}
+
+/// Execute a raw runtime API call on the latest block
+async fn state_call(client: &GlinClient, function: &str, params: &[u8]) -> Result<Vec<u8>> {
+    client
+        .runtime_api()
+        .at_latest()
+        .await?
+        .call_raw(function, Some(params))
+        .await
+        .with_context(|| format!("{} failed", function))
+}
+
+/// Decode a `ContractResult` returned by the contracts runtime API
+fn decode_contract_result<R: Decode>(raw: &[u8], metadata: Metadata) -> Result<ContractResult<R>> {
+    let mut cursor = raw;
+
+    let gas_consumed = Weight::decode(&mut cursor).context("Failed to decode gas_consumed")?;
+    let gas_required = Weight::decode(&mut cursor).context("Failed to decode gas_required")?;
+    let storage_deposit =
+        StorageDeposit::decode(&mut cursor).context("Failed to decode storage_deposit")?;
+    let debug_message = Vec::<u8>::decode(&mut cursor).context("Failed to decode debug_message")?;
+
+    // Result<R, DispatchError>; trailing events are ignored
+    let result = match u8::decode(&mut cursor).context("Failed to decode execution result")? {
+        0 => Ok(R::decode(&mut cursor).context("Failed to decode return value")?),
+        1 => Err(errors::decode_dispatch_error(cursor, &metadata)),
+        other => anyhow::bail!("Invalid result variant index: {}", other),
+    };
+
+    Ok(ContractResult {
+        gas_consumed,
+        gas_required,
+        storage_deposit,
+        debug_message: String::from_utf8_lossy(&debug_message).to_string(),
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revert_flag() {
+        let value = ExecReturnValue {
+            flags: REVERT_FLAG,
+            data: vec![],
+        };
+        assert!(value.did_revert());
+
+        let value = ExecReturnValue {
+            flags: 0,
+            data: vec![],
+        };
+        assert!(!value.did_revert());
+    }
+
+    #[test]
+    fn test_decode_get_storage_result() {
+        let ok: std::result::Result<Option<Vec<u8>>, u8> = Ok(Some(vec![1, 2, 3]));
+        assert_eq!(
+            decode_get_storage_result(&ok.encode()).unwrap(),
+            Some(vec![1, 2, 3])
+        );
+
+        let missing: std::result::Result<Option<Vec<u8>>, u8> = Ok(None);
+        assert_eq!(decode_get_storage_result(&missing.encode()).unwrap(), None);
+
+        let err: std::result::Result<Option<Vec<u8>>, u8> = Err(0);
+        assert!(decode_get_storage_result(&err.encode()).is_err());
+    }
+
+    #[test]
+    fn test_storage_deposit_charge_or_zero() {
+        assert_eq!(StorageDeposit::Charge(10).charge_or_zero(), 10);
+        assert_eq!(StorageDeposit::Refund(10).charge_or_zero(), 0);
+    }
+}
@@ -0,0 +1,376 @@
+//! Contract storage reads
+//!
+//! Reads raw contract storage through the `ContractsApi_get_storage` runtime
+//! API and decodes it using the storage layout of the contract's ink! metadata.
+//! Each root key of the layout is a separate storage cell holding the packed
+//! fields below it; `Mapping` entries are stored under the mapping's root key
+//! followed by the encoded entry key.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context, Result};
+use ink_metadata::layout::{Layout, RootLayout};
+use ink_metadata::InkProject;
+use scale::Encode;
+use scale_info::form::PortableForm;
+use serde_json::{Map, Value as JsonValue};
+
+use glin_client::GlinClient;
+
+use crate::chain_info::parse_address;
+use crate::{encoding, runtime_api};
+
+type PortableLayout = Layout<PortableForm>;
+type PortableRootLayout = RootLayout<PortableForm>;
+
+/// Storage cells of a contract keyed by root key (`None` if unset)
+pub type StorageCells = BTreeMap<u32, Option<Vec<u8>>>;
+
+/// Kind of lazily loaded storage behind a root key
+enum RootKind {
+    /// `Mapping<K, V>`: one cell per entry, not enumerable
+    Mapping,
+    /// `Lazy<T>`, `StorageVec` internals or the contract itself: a single cell
+    Cell,
+}
+
+/// Read and decode the storage of a contract
+///
+/// Returns the contract's root struct as JSON. `Lazy` fields are loaded and
+/// decoded in place; `Mapping` fields are returned as
+/// `{"mapping": {"root_key": "0x..."}}`, use [`read_mapping`] to look up entries.
+pub async fn read_storage(
+    client: &GlinClient,
+    contract_address: &str,
+    metadata: &InkProject,
+) -> Result<JsonValue> {
+    let address = contract_account(contract_address)?;
+
+    let mut cells = StorageCells::new();
+    for root_key in cell_root_keys(metadata)? {
+        let cell = runtime_api::get_storage(client, address, root_key.encode())
+            .await
+            .with_context(|| format!("Failed to read storage cell 0x{:08x}", root_key))?;
+        cells.insert(root_key, cell);
+    }
+
+    decode_storage(metadata, &cells)
+}
+
+/// Read a single `Mapping` entry of a contract
+///
+/// `path` names the mapping field, using dots for nested structs (e.g.
+/// `"balances"` or `"data.allowances"`). The key is given as JSON, in the same
+/// format accepted for message arguments. Returns `None` if the entry is unset.
+pub async fn read_mapping(
+    client: &GlinClient,
+    contract_address: &str,
+    metadata: &InkProject,
+    path: &str,
+    key: &JsonValue,
+) -> Result<Option<JsonValue>> {
+    let address = contract_account(contract_address)?;
+    let storage_key = mapping_storage_key(metadata, path, key)?;
+
+    let cell = runtime_api::get_storage(client, address, storage_key)
+        .await
+        .with_context(|| format!("Failed to read mapping entry of '{}'", path))?;
+
+    cell.map(|bytes| decode_mapping_value(metadata, path, &bytes))
+        .transpose()
+}
+
+/// Root keys of all storage cells needed to decode the contract's root struct
+///
+/// Mapping roots are skipped as their entries cannot be enumerated.
+pub fn cell_root_keys(metadata: &InkProject) -> Result<BTreeSet<u32>> {
+    let mut keys = BTreeSet::new();
+    collect_root_keys(metadata.layout(), metadata, &mut keys)?;
+    Ok(keys)
+}
+
+/// Decode the contract's root struct from its storage cells
+pub fn decode_storage(metadata: &InkProject, cells: &StorageCells) -> Result<JsonValue> {
+    let mut decoder = LayoutDecoder { metadata, cells };
+    decoder.decode(metadata.layout(), None, "storage")
+}
+
+/// Unhashed storage key of a `Mapping` entry
+pub fn mapping_storage_key(metadata: &InkProject, path: &str, key: &JsonValue) -> Result<Vec<u8>> {
+    let root = find_mapping(metadata, path)?;
+    let key_type = mapping_key_type(root, metadata)?;
+
+    let mut storage_key = root.root_key().key().encode();
+    storage_key.extend(
+        encoding::encode_value(key, key_type, metadata)
+            .with_context(|| format!("Invalid key for mapping '{}'", path))?,
+    );
+
+    Ok(storage_key)
+}
+
+/// Decode the value of a `Mapping` entry
+pub fn decode_mapping_value(metadata: &InkProject, path: &str, bytes: &[u8]) -> Result<JsonValue> {
+    let root = find_mapping(metadata, path)?;
+
+    let cells = StorageCells::new();
+    let mut decoder = LayoutDecoder {
+        metadata,
+        cells: &cells,
+    };
+
+    decoder.decode_cell(root.layout(), bytes, path)
+}
+
+fn contract_account(contract_address: &str) -> Result<[u8; 32]> {
+    let bytes = parse_address(contract_address)?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid contract address: {}", contract_address))
+}
+
+fn root_kind(root: &PortableRootLayout, metadata: &InkProject) -> RootKind {
+    let name = metadata
+        .registry()
+        .resolve(root.ty().id)
+        .and_then(|ty| ty.path.segments.last());
+
+    match name.map(|s| s.as_str()) {
+        Some("Mapping") => RootKind::Mapping,
+        _ => RootKind::Cell,
+    }
+}
+
+fn collect_root_keys(
+    layout: &PortableLayout,
+    metadata: &InkProject,
+    keys: &mut BTreeSet<u32>,
+) -> Result<()> {
+    match layout {
+        Layout::Root(root) => {
+            if let RootKind::Cell = root_kind(root, metadata) {
+                keys.insert(*root.root_key().key());
+                collect_root_keys(root.layout(), metadata, keys)?;
+            }
+        }
+        Layout::Struct(st) => {
+            for field in st.fields() {
+                collect_root_keys(field.layout(), metadata, keys)?;
+            }
+        }
+        Layout::Enum(en) => {
+            for variant in en.variants().values() {
+                for field in variant.fields() {
+                    collect_root_keys(field.layout(), metadata, keys)?;
+                }
+            }
+        }
+        Layout::Array(arr) => collect_root_keys(arr.layout(), metadata, keys)?,
+        Layout::Leaf(_) => {}
+        Layout::Hash(_) => anyhow::bail!("Hash storage layouts (ink! 3) are not supported"),
+    }
+
+    Ok(())
+}
+
+/// Find the `Mapping` root at a dot-separated field path
+fn find_mapping<'a>(metadata: &'a InkProject, path: &str) -> Result<&'a PortableRootLayout> {
+    let mut layout = metadata.layout();
+
+    for name in path.split('.') {
+        // Cell roots are transparent when walking struct fields
+        while let Layout::Root(root) = layout {
+            if let RootKind::Mapping = root_kind(root, metadata) {
+                break;
+            }
+            layout = root.layout();
+        }
+
+        let Layout::Struct(st) = layout else {
+            anyhow::bail!("Storage path '{}' does not lead through a struct", path);
+        };
+
+        layout = st
+            .fields()
+            .iter()
+            .find(|field| field.name() == name)
+            .map(|field| field.layout())
+            .ok_or_else(|| anyhow::anyhow!("Storage field '{}' not found in '{}'", name, path))?;
+    }
+
+    match layout {
+        Layout::Root(root) if matches!(root_kind(root, metadata), RootKind::Mapping) => Ok(root),
+        _ => anyhow::bail!("Storage field '{}' is not a Mapping", path),
+    }
+}
+
+/// Type ID of the key type of a `Mapping<K, V>`
+fn mapping_key_type(root: &PortableRootLayout, metadata: &InkProject) -> Result<u32> {
+    let ty = metadata
+        .registry()
+        .resolve(root.ty().id)
+        .ok_or_else(|| anyhow::anyhow!("Type {} not found in registry", root.ty().id))?;
+
+    ty.type_params
+        .iter()
+        .find(|param| param.name == "K")
+        .or_else(|| ty.type_params.first())
+        .and_then(|param| param.ty)
+        .map(|param_ty| param_ty.id)
+        .ok_or_else(|| anyhow::anyhow!("Mapping type {} has no key type parameter", ty.path))
+}
+
+/// Decodes a storage layout from the contract's cells
+struct LayoutDecoder<'a> {
+    metadata: &'a InkProject,
+    cells: &'a StorageCells,
+}
+
+impl LayoutDecoder<'_> {
+    /// Decode `layout`, reading packed fields from `cell` if inside a storage cell
+    fn decode(
+        &mut self,
+        layout: &PortableLayout,
+        mut cell: Option<&mut &[u8]>,
+        path: &str,
+    ) -> Result<JsonValue> {
+        match layout {
+            Layout::Root(root) => match root_kind(root, self.metadata) {
+                RootKind::Mapping => Ok(serde_json::json!({
+                    "mapping": {
+                        "root_key": format!("0x{}", hex::encode(root.root_key().key().encode())),
+                    }
+                })),
+                RootKind::Cell => {
+                    let root_key = *root.root_key().key();
+                    match self.cells.get(&root_key) {
+                        Some(Some(bytes)) => self.decode_cell(root.layout(), bytes, path),
+                        _ => Ok(JsonValue::Null),
+                    }
+                }
+            },
+            Layout::Leaf(leaf) => {
+                let Some(input) = cell else {
+                    anyhow::bail!("Field '{}' is not inside a storage cell", path);
+                };
+                encoding::decode_value(input, leaf.ty().id, self.metadata)
+                    .with_context(|| format!("Failed to decode storage field '{}'", path))
+            }
+            Layout::Struct(st) => {
+                let mut fields = Map::new();
+                for field in st.fields() {
+                    let field_path = format!("{}.{}", path, field.name());
+                    let value = self.decode(field.layout(), cell.as_deref_mut(), &field_path)?;
+                    fields.insert(field.name().to_string(), value);
+                }
+                Ok(JsonValue::Object(fields))
+            }
+            Layout::Enum(en) => {
+                let Some(input) = cell else {
+                    anyhow::bail!("Enum '{}' is not inside a storage cell", path);
+                };
+                let discriminant = <u8 as scale::Decode>::decode(input)
+                    .with_context(|| format!("Failed to decode variant of '{}'", path))?;
+
+                let variant = en
+                    .variants()
+                    .iter()
+                    .find(|(d, _)| d.value() == discriminant as usize)
+                    .map(|(_, variant)| variant)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid variant {} of '{}'", discriminant, path)
+                    })?;
+
+                let mut fields = Map::new();
+                for field in variant.fields() {
+                    let field_path = format!("{}.{}", path, field.name());
+                    let value = self.decode(field.layout(), Some(&mut *input), &field_path)?;
+                    fields.insert(field.name().to_string(), value);
+                }
+
+                Ok(serde_json::json!({
+                    "variant": variant.name(),
+                    "fields": fields,
+                }))
+            }
+            Layout::Array(arr) => {
+                let mut elements = Vec::with_capacity(arr.len() as usize);
+                for i in 0..arr.len() {
+                    let element_path = format!("{}[{}]", path, i);
+                    elements.push(self.decode(arr.layout(), cell.as_deref_mut(), &element_path)?);
+                }
+                Ok(JsonValue::Array(elements))
+            }
+            Layout::Hash(_) => anyhow::bail!("Hash storage layouts (ink! 3) are not supported"),
+        }
+    }
+
+    /// Decode `layout` from the full contents of a storage cell
+    fn decode_cell(
+        &mut self,
+        layout: &PortableLayout,
+        bytes: &[u8],
+        path: &str,
+    ) -> Result<JsonValue> {
+        let mut input = bytes;
+        let value = self.decode(layout, Some(&mut input), path)?;
+
+        if !input.is_empty() {
+            anyhow::bail!(
+                "Storage cell of '{}' has {} trailing bytes after decoding",
+                path,
+                input.len()
+            );
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, AccountId};
+
+    #[test]
+    fn test_decode_storage() {
+        let metadata = test_utils::erc20();
+
+        let keys = cell_root_keys(&metadata).unwrap();
+        assert_eq!(keys.into_iter().collect::<Vec<_>>(), vec![0, 0x99]);
+
+        let mut root = 1_000u128.encode();
+        root.extend(AccountId([7; 32]).encode());
+
+        let mut cells = StorageCells::new();
+        cells.insert(0, Some(root));
+        cells.insert(0x99, Some(42u32.encode()));
+
+        let storage = decode_storage(&metadata, &cells).unwrap();
+        assert_eq!(storage["total_supply"], "1000");
+        assert_eq!(storage["balances"]["mapping"]["root_key"], "0x34120000");
+        assert_eq!(storage["owner"].as_str().map(|s| s.len()), Some(48));
+        assert_eq!(storage["config"], 42);
+
+        // Unset Lazy values decode to null
+        cells.insert(0x99, None);
+        let storage = decode_storage(&metadata, &cells).unwrap();
+        assert!(storage["config"].is_null());
+    }
+
+    #[test]
+    fn test_mapping_entry() {
+        let metadata = test_utils::erc20();
+        let owner = format!("0x{}", hex::encode([7u8; 32]));
+
+        let key = mapping_storage_key(&metadata, "balances", &JsonValue::String(owner)).unwrap();
+        assert_eq!(&key[..4], &[0x34, 0x12, 0, 0]);
+        assert_eq!(&key[4..], &[7u8; 32]);
+
+        let value = decode_mapping_value(&metadata, "balances", &500u128.encode()).unwrap();
+        assert_eq!(value, "500");
+
+        assert!(mapping_storage_key(&metadata, "total_supply", &JsonValue::Null).is_err());
+        assert!(mapping_storage_key(&metadata, "missing", &JsonValue::Null).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Metadata fixtures and an offline client shared by unit tests
+
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use ink_metadata::layout::{FieldLayout, Layout, LayoutKey, LeafLayout, RootLayout, StructLayout};
+use ink_metadata::{
+    ConstructorSpec, ContractSpec, EnvironmentSpec, EventParamSpec, EventSpec, InkProject,
+    LangError, MessageParamSpec, MessageSpec, ReturnTypeSpec, TypeSpec,
+};
+use scale::{Decode, Encode};
+use scale_info::{MetaType, TypeInfo};
+use serde_json::{json, Value as JsonValue};
+use subxt::backend::rpc::{RawRpcFuture, RawRpcSubscription, RawValue, RpcClient, RpcClientT};
+use subxt::client::RuntimeVersion;
+use subxt::ext::frame_metadata::{v15, RuntimeMetadataPrefixed};
+use subxt::ext::subxt_rpcs;
+
+use glin_client::GlinClient;
+
+/// `ink::primitives::AccountId` lookalike
+#[derive(TypeInfo, scale::Encode)]
+pub struct AccountId(pub [u8; 32]);
+
+/// `ink::primitives::Hash` lookalike
+#[derive(TypeInfo, scale::Encode)]
+pub struct Hash(pub [u8; 32]);
+
+/// `ink::storage::Mapping` lookalike
+#[derive(TypeInfo)]
+pub struct Mapping<K, V>(PhantomData<(K, V)>);
+
+/// `ink::storage::Lazy` lookalike
+#[derive(TypeInfo)]
+pub struct Lazy<V>(PhantomData<V>);
+
+/// Contract storage struct of [`erc20`]
+#[allow(dead_code)]
+#[derive(TypeInfo)]
+pub struct Erc20 {
+    pub total_supply: u128,
+    pub balances: Mapping<AccountId, u128>,
+    pub owner: AccountId,
+    pub config: Lazy<u32>,
+}
+
+type MessageResult<T> = Result<T, LangError>;
+
+/// Build a project with a single `test` message taking and returning `T`
+///
+/// Returns the project and the registry type ID of `T`.
+pub fn project_with<T: TypeInfo + 'static>() -> (InkProject, u32) {
+    let message = MessageSpec::from_label("test")
+        .selector([0, 0, 0, 1])
+        .mutates(false)
+        .payable(false)
+        .args(vec![MessageParamSpec::new("value")
+            .of_type(TypeSpec::of_type::<T>())
+            .done()])
+        .returns(ReturnTypeSpec::new(TypeSpec::of_type::<MessageResult<T>>()))
+        .done();
+
+    let project = project_from_messages(vec![message]);
+    let type_id = project.spec().messages()[0].args()[0].ty().ty().id;
+
+    (project, type_id)
+}
+
+/// Build a flipper-like project with `flip`, `get` and `set` messages
+pub fn flipper() -> InkProject {
+    project_from_messages(vec![
+        MessageSpec::from_label("flip")
+            .selector([0x63, 0x3a, 0xa5, 0x51])
+            .mutates(true)
+            .payable(false)
+            .args(Vec::new())
+            .returns(ReturnTypeSpec::new(TypeSpec::of_type::<MessageResult<()>>()))
+            .done(),
+        MessageSpec::from_label("get")
+            .selector([0x2f, 0x86, 0x5b, 0xd9])
+            .mutates(false)
+            .payable(false)
+            .args(Vec::new())
+            .returns(ReturnTypeSpec::new(
+                TypeSpec::of_type::<MessageResult<bool>>(),
+            ))
+            .done(),
+        MessageSpec::from_label("set")
+            .selector([0xe8, 0xc4, 0x5e, 0xb6])
+            .mutates(true)
+            .payable(false)
+            .args(vec![MessageParamSpec::new("value")
+                .of_type(TypeSpec::of_type::<bool>())
+                .done()])
+            .returns(ReturnTypeSpec::new(TypeSpec::of_type::<MessageResult<()>>()))
+            .done(),
+    ])
+}
+
+/// Build an ERC-20 style project with `total_supply`, `balance_of` and
+/// `transfer` messages and `Transfer` / `Approval` events
+///
+/// `Transfer` has the signature topic `[0x11; 32]`, `Approval` `[0x22; 32]`.
+///
+/// The storage layout has `total_supply` and `owner` packed in the root cell,
+/// a `balances` mapping at root key `0x1234` and a lazy `config` at `0x99`.
+pub fn erc20() -> InkProject {
+    let param = |label: &'static str, ty: TypeSpec, indexed: bool| {
+        EventParamSpec::new(label)
+            .of_type(ty)
+            .indexed(indexed)
+            .done()
+    };
+
+    let events = vec![
+        EventSpec::new("Transfer")
+            .module_path("erc20")
+            .signature_topic(Some([0x11; 32]))
+            .args(vec![
+                param("from", TypeSpec::of_type::<Option<AccountId>>(), true),
+                param("to", TypeSpec::of_type::<Option<AccountId>>(), true),
+                param("value", TypeSpec::of_type::<u128>(), false),
+            ])
+            .done(),
+        EventSpec::new("Approval")
+            .module_path("erc20")
+            .signature_topic(Some([0x22; 32]))
+            .args(vec![
+                param("owner", TypeSpec::of_type::<AccountId>(), true),
+                param("spender", TypeSpec::of_type::<AccountId>(), true),
+                param("value", TypeSpec::of_type::<u128>(), false),
+            ])
+            .done(),
+    ];
+
+    let messages = vec![
+        MessageSpec::from_label("total_supply")
+            .selector([0xdb, 0x63, 0x75, 0xa8])
+            .mutates(false)
+            .payable(false)
+            .args(Vec::new())
+            .returns(ReturnTypeSpec::new(
+                TypeSpec::of_type::<MessageResult<u128>>(),
+            ))
+            .done(),
+        MessageSpec::from_label("balance_of")
+            .selector([0x0f, 0x75, 0x5a, 0x56])
+            .mutates(false)
+            .payable(false)
+            .args(vec![MessageParamSpec::new("owner")
+                .of_type(TypeSpec::of_type::<AccountId>())
+                .done()])
+            .returns(ReturnTypeSpec::new(
+                TypeSpec::of_type::<MessageResult<u128>>(),
+            ))
+            .done(),
+        MessageSpec::from_label("transfer")
+            .selector([0x84, 0xa1, 0x5d, 0xa1])
+            .mutates(true)
+            .payable(false)
+            .args(vec![
+                MessageParamSpec::new("to")
+                    .of_type(TypeSpec::of_type::<AccountId>())
+                    .done(),
+                MessageParamSpec::new("value")
+                    .of_type(TypeSpec::of_type::<u128>())
+                    .done(),
+            ])
+            .returns(ReturnTypeSpec::new(TypeSpec::of_type::<MessageResult<()>>()))
+            .done(),
+    ];
+
+    let field = |name: &'static str, layout: Layout| FieldLayout::new(name, layout);
+    let leaf = |ty| Layout::Leaf(LeafLayout::new(LayoutKey::new(0u32), ty));
+    let layout = Layout::Root(RootLayout::new(
+        LayoutKey::new(0u32),
+        Layout::Struct(StructLayout::new(
+            "Erc20",
+            vec![
+                field("total_supply", leaf(scale_info::meta_type::<u128>())),
+                field(
+                    "balances",
+                    Layout::Root(RootLayout::new(
+                        LayoutKey::new(0x1234u32),
+                        leaf(scale_info::meta_type::<u128>()),
+                        scale_info::meta_type::<Mapping<AccountId, u128>>(),
+                    )),
+                ),
+                field("owner", leaf(scale_info::meta_type::<AccountId>())),
+                field(
+                    "config",
+                    Layout::Root(RootLayout::new(
+                        LayoutKey::new(0x99u32),
+                        leaf(scale_info::meta_type::<u32>()),
+                        scale_info::meta_type::<Lazy<u32>>(),
+                    )),
+                ),
+            ],
+        )),
+        scale_info::meta_type::<Erc20>(),
+    ));
+
+    project_from_spec(messages, events, layout)
+}
+
+fn project_from_messages(messages: Vec<MessageSpec>) -> InkProject {
+    let layout = Layout::Struct(StructLayout::new("Contract", Vec::new()));
+    project_from_spec(messages, Vec::new(), layout)
+}
+
+fn project_from_spec(
+    messages: Vec<MessageSpec>,
+    events: Vec<EventSpec>,
+    layout: Layout,
+) -> InkProject {
+    let spec = ContractSpec::new()
+        .constructors(vec![ConstructorSpec::from_label("new")
+            .selector([0x9b, 0xae, 0x9d, 0x5e])
+            .payable(false)
+            .args(vec![MessageParamSpec::new("init_value")
+                .of_type(TypeSpec::of_type::<bool>())
+                .done()])
+            .returns(ReturnTypeSpec::new(TypeSpec::of_type::<MessageResult<()>>()))
+            .done()])
+        .messages(messages)
+        .events(events)
+        .lang_error(TypeSpec::of_type::<LangError>())
+        .environment(
+            EnvironmentSpec::new()
+                .account_id(TypeSpec::of_type::<AccountId>())
+                .balance(TypeSpec::of_type::<u128>())
+                .hash(TypeSpec::of_type::<Hash>())
+                .timestamp(TypeSpec::of_type::<u64>())
+                .block_number(TypeSpec::of_type::<u32>())
+                .chain_extension(TypeSpec::of_type::<()>())
+                .max_event_topics(4)
+                .static_buffer_size(16 * 1024)
+                .done(),
+        )
+        .done();
+
+    InkProject::new(layout, spec)
+}
+
+/// Serialize `project` as cargo-contract 4.x would have written it
+///
+/// The version is the string `"4"` and messages, constructors and events
+/// lack the `default`, `module_path` and `signature_topic` fields.
+pub fn v4_json(project: &InkProject) -> JsonValue {
+    let mut json = serde_json::to_value(project).unwrap();
+    json["version"] = json!("4");
+
+    let spec = json["spec"].as_object_mut().unwrap();
+    spec.remove("environment");
+    for kind in ["constructors", "messages"] {
+        for item in spec[kind].as_array_mut().unwrap() {
+            item.as_object_mut().unwrap().remove("default");
+        }
+    }
+    for event in spec["events"].as_array_mut().unwrap() {
+        let event = event.as_object_mut().unwrap();
+        event.remove("module_path");
+        event.remove("signature_topic");
+    }
+
+    json
+}
+
+/// RPC backend of [`offline_client`]
+///
+/// Answers `chain_getFinalizedHead` and records the function name and
+/// parameters of every `state_call`, failing it. Everything else fails.
+#[derive(Default)]
+pub struct RecordingRpc {
+    pub state_calls: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+impl RpcClientT for RecordingRpc {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            match method {
+                "chain_getFinalizedHead" => {
+                    let hash = format!("\"0x{}\"", "00".repeat(32));
+                    Ok(RawValue::from_string(hash).unwrap())
+                }
+                "state_call" => {
+                    let params: Vec<String> =
+                        serde_json::from_str(params.as_ref().unwrap().get()).unwrap();
+                    let data = hex::decode(params[1].trim_start_matches("0x")).unwrap();
+                    self.state_calls
+                        .lock()
+                        .unwrap()
+                        .push((params[0].clone(), data));
+                    Err(subxt_rpcs::Error::Client("offline".into()))
+                }
+                _ => Err(subxt_rpcs::Error::Client(
+                    format!("offline: {}", method).into(),
+                )),
+            }
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        _sub: &'a str,
+        _params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        Box::pin(async { Err(subxt_rpcs::Error::Client("offline".into())) })
+    }
+}
+
+/// Client with empty runtime metadata backed by a [`RecordingRpc`]
+pub fn offline_client() -> (GlinClient, Arc<RecordingRpc>) {
+    let unit = MetaType::new::<()>;
+    let metadata = v15::RuntimeMetadataV15::new(
+        Vec::new(),
+        v15::ExtrinsicMetadata {
+            version: 4,
+            address_ty: unit(),
+            call_ty: unit(),
+            signature_ty: unit(),
+            extra_ty: unit(),
+            signed_extensions: Vec::new(),
+        },
+        unit(),
+        Vec::new(),
+        v15::OuterEnums {
+            call_enum_ty: unit(),
+            event_enum_ty: unit(),
+            error_enum_ty: unit(),
+        },
+        v15::CustomMetadata {
+            map: Default::default(),
+        },
+    );
+
+    let rpc = Arc::new(RecordingRpc::default());
+    let client = GlinClient::from_rpc_client_with(
+        Default::default(),
+        RuntimeVersion {
+            spec_version: 1,
+            transaction_version: 1,
+        },
+        subxt::Metadata::decode(&mut &RuntimeMetadataPrefixed::from(metadata).encode()[..])
+            .unwrap(),
+        RpcClient::new(rpc.clone()),
+    )
+    .unwrap();
+
+    (client, rpc)
+}
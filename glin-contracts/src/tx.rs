@@ -0,0 +1,150 @@
+//! Shared transaction primitives for contract extrinsics
+//!
+//! Weight type used by `pallet-contracts` and helpers to submit a dynamic
+//! extrinsic, wait for it to be finalized and collect its events.
+
+use anyhow::{Context, Result};
+use glin_client::GlinClient;
+use glin_types::EventData;
+use scale::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use subxt::blocks::ExtrinsicEvents;
+use subxt::dynamic::Value;
+use subxt::tx::Payload;
+use subxt::PolkadotConfig;
+use subxt_signer::sr25519::Keypair;
+
+use crate::errors::ContractError;
+
+/// Two-dimensional weight (`sp_weights::Weight`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct Weight {
+    /// Computational time
+    #[codec(compact)]
+    pub ref_time: u64,
+    /// Size of the storage proof
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+impl Weight {
+    /// Create a new weight
+    pub fn new(ref_time: u64, proof_size: u64) -> Self {
+        Self {
+            ref_time,
+            proof_size,
+        }
+    }
+
+    /// Convert to a dynamic value for extrinsic construction
+    pub(crate) fn to_value(self) -> Value {
+        Value::named_composite([
+            ("ref_time", Value::u128(self.ref_time as u128)),
+            ("proof_size", Value::u128(self.proof_size as u128)),
+        ])
+    }
+}
+
+/// Encode an optional storage deposit limit as a dynamic `Option<Compact<Balance>>`
+pub(crate) fn storage_deposit_limit_value(limit: Option<u128>) -> Value {
+    match limit {
+        Some(limit) => Value::unnamed_variant("Some", [Value::u128(limit)]),
+        None => Value::unnamed_variant("None", []),
+    }
+}
+
+/// Outcome of a finalized contract extrinsic
+pub(crate) struct SubmittedTx {
+    pub block_hash: String,
+    pub extrinsic_hash: String,
+    pub block_number: u64,
+    pub events: ExtrinsicEvents<PolkadotConfig>,
+}
+
+/// Sign and submit an extrinsic, then wait until it is finalized successfully
+pub(crate) async fn submit_and_watch<Call: Payload>(
+    client: &GlinClient,
+    signer: &Keypair,
+    call: &Call,
+) -> Result<SubmittedTx> {
+    let progress = client
+        .tx()
+        .sign_and_submit_then_watch_default(call, signer)
+        .await
+        .context("Failed to submit extrinsic")?;
+
+    let extrinsic_hash = format!("0x{}", hex::encode(progress.extrinsic_hash()));
+
+    let in_block = progress
+        .wait_for_finalized()
+        .await
+        .context("Extrinsic was not finalized")?;
+
+    let events = in_block
+        .wait_for_success()
+        .await
+        .map_err(|err| match &err {
+            subxt::Error::Runtime(dispatch) => {
+                let decoded = ContractError::from(dispatch);
+                let message = format!("Extrinsic failed: {}", decoded);
+                anyhow::Error::new(decoded).context(message)
+            }
+            _ => anyhow::Error::new(err).context("Extrinsic failed"),
+        })?;
+
+    let block_hash = in_block.block_hash();
+    let block_number = client
+        .blocks()
+        .at(block_hash)
+        .await
+        .context("Failed to fetch inclusion block")?
+        .number() as u64;
+
+    Ok(SubmittedTx {
+        block_hash: format!("0x{}", hex::encode(block_hash)),
+        extrinsic_hash,
+        block_number,
+        events,
+    })
+}
+
+/// Convert extrinsic events into serializable event data
+pub(crate) fn collect_events(
+    events: &ExtrinsicEvents<PolkadotConfig>,
+    block_number: u64,
+) -> Result<Vec<EventData>> {
+    let mut collected = Vec::new();
+
+    for event in events.iter() {
+        let event = event?;
+
+        let data = match event.field_values() {
+            Ok(values) => serde_json::to_value(&values)?,
+            Err(_) => serde_json::json!({
+                "raw": format!("0x{}", hex::encode(event.field_bytes()))
+            }),
+        };
+
+        collected.push(EventData {
+            pallet: event.pallet_name().to_string(),
+            method: event.variant_name().to_string(),
+            data,
+            block_number,
+            event_index: event.index(),
+        });
+    }
+
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weight_encoding_is_compact() {
+        let weight = Weight::new(1, 2);
+        assert_eq!(weight.encode(), vec![4, 8]);
+        assert_eq!(Weight::decode(&mut &[4u8, 8][..]).unwrap(), weight);
+    }
+}
@@ -0,0 +1,583 @@
+//! Verification job queue
+//!
+//! Verification builds take minutes, so services verifying contracts on
+//! behalf of users run them in the background. [`VerificationQueue`] runs
+//! jobs on a bounded number of workers and hands out job IDs to poll for
+//! status and results.
+//!
+//! Job records are stored on disk and survive restarts; jobs that were still
+//! queued or building when the process stopped are reported as failed. Built
+//! bundles are cached by source hash and toolchain, so resubmitting the same
+//! source (e.g. to compare it against another deployment) skips the build.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sp_core_hashing::blake2_256;
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::AbortHandle;
+
+use crate::build_runner::Toolchain;
+use crate::bundle::ContractBundle;
+use crate::metadata_cache::{now_secs, write_atomic};
+use crate::verification_report::{self, VerificationReport};
+use crate::verification_source::VerificationSource;
+use crate::verifier::{self, Build, ContractVerifier};
+
+/// What the built code is compared against
+#[derive(Debug, Clone)]
+pub enum Expectation {
+    /// The deployed code hash, built with the given toolchain
+    CodeHash {
+        code_hash: [u8; 32],
+        toolchain: Toolchain,
+    },
+    /// A submitted bundle, built with the toolchain it records
+    Bundle(Arc<ContractBundle>),
+}
+
+/// A verification job to submit to the queue
+#[derive(Debug, Clone)]
+pub struct VerificationRequest {
+    pub source: VerificationSource,
+    pub expected: Expectation,
+}
+
+impl VerificationRequest {
+    /// Verify `source` against a deployed code hash
+    pub fn code_hash(
+        source: VerificationSource,
+        code_hash: [u8; 32],
+        toolchain: Toolchain,
+    ) -> Self {
+        Self {
+            source,
+            expected: Expectation::CodeHash {
+                code_hash,
+                toolchain,
+            },
+        }
+    }
+
+    /// Verify `source` against a submitted bundle, see [`ContractVerifier::verify_bundle`]
+    pub fn bundle(source: VerificationSource, bundle: ContractBundle) -> Self {
+        Self {
+            source,
+            expected: Expectation::Bundle(Arc::new(bundle)),
+        }
+    }
+
+    fn toolchain(&self) -> Toolchain {
+        match &self.expected {
+            Expectation::CodeHash { toolchain, .. } => toolchain.clone(),
+            Expectation::Bundle(bundle) => Toolchain::from_bundle(bundle),
+        }
+    }
+}
+
+/// Status of a verification job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a free worker
+    Queued,
+    /// Building the contract
+    Building,
+    /// Finished, the report is available
+    Done,
+    /// The job could not be run; see the job's error
+    Failed,
+    /// Cancelled before it finished
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has finished, successfully or not
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// State of a verification job, as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    /// Job ID
+    pub id: String,
+    pub status: JobStatus,
+    /// Hash of the submitted source (hex)
+    pub source_hash: String,
+    /// Submission time (unix seconds)
+    pub submitted_at: u64,
+    /// Completion time (unix seconds)
+    pub finished_at: Option<u64>,
+    /// Whether the build was served from the artifact cache
+    pub cached: bool,
+    /// Verification report of a finished job
+    ///
+    /// Build failures are reported here too, as a
+    /// [`CompilationFailed`](crate::VerificationResult::CompilationFailed) or
+    /// [`TimedOut`](crate::VerificationResult::TimedOut) result.
+    pub report: Option<VerificationReport>,
+    /// Why the job failed
+    pub error: Option<String>,
+}
+
+/// Background verification with bounded parallelism and persistent results
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use glin_contracts::build_runner::Toolchain;
+/// use glin_contracts::verification_queue::{VerificationQueue, VerificationRequest};
+/// use glin_contracts::{ContractVerifier, VerificationSource};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let verifier = ContractVerifier::new("/tmp/verification")?
+///         .with_container_runtime()
+///         .await;
+///     let queue = VerificationQueue::new(verifier, "/var/lib/glin/verification", 2)?;
+///     # let code_hash = [0u8; 32]; // Placeholder for doctest
+///
+///     let source = VerificationSource::archive("token-1.0.0.tar.gz");
+///     let id = queue.submit(VerificationRequest::code_hash(source, code_hash, Toolchain::default()))?;
+///
+///     let job = queue.wait(&id).await?;
+///     println!("{:?}: {:?}", job.status, job.report.map(|r| r.result));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct VerificationQueue {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    verifier: ContractVerifier,
+    dir: PathBuf,
+    workers: Semaphore,
+    jobs: Mutex<HashMap<String, Job>>,
+    changed: Notify,
+    next_seq: AtomicU64,
+}
+
+struct Job {
+    record: JobRecord,
+    task: Option<AbortHandle>,
+}
+
+impl VerificationQueue {
+    /// Open a queue storing jobs and build artifacts in `dir`
+    ///
+    /// At most `workers` builds run at the same time. Records of earlier jobs
+    /// are loaded from `dir`.
+    pub fn new(verifier: ContractVerifier, dir: impl AsRef<Path>, workers: usize) -> Result<Self> {
+        anyhow::ensure!(
+            workers > 0,
+            "A verification queue needs at least one worker"
+        );
+
+        let dir = dir.as_ref().to_path_buf();
+        let jobs_dir = dir.join("jobs");
+        std::fs::create_dir_all(&jobs_dir)
+            .with_context(|| format!("Failed to create queue directory: {}", jobs_dir.display()))?;
+
+        let inner = Inner {
+            verifier,
+            dir,
+            workers: Semaphore::new(workers),
+            jobs: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+            next_seq: AtomicU64::new(0),
+        };
+
+        let mut jobs = HashMap::new();
+        for entry in std::fs::read_dir(&jobs_dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            let content = std::fs::read(&path)
+                .with_context(|| format!("Failed to read job record: {}", path.display()))?;
+            let mut record: JobRecord = serde_json::from_slice(&content)
+                .with_context(|| format!("Invalid job record: {}", path.display()))?;
+
+            // The process stopped while the job was pending
+            if !record.status.is_finished() {
+                record.status = JobStatus::Failed;
+                record.finished_at = Some(now_secs());
+                record.error = Some("Interrupted before completion".to_string());
+                inner.persist(&record)?;
+            }
+
+            jobs.insert(record.id.clone(), Job { record, task: None });
+        }
+        *inner.lock() = jobs;
+
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Queue a verification job and return its ID
+    ///
+    /// Must be called within a tokio runtime.
+    pub fn submit(&self, request: VerificationRequest) -> Result<String> {
+        let source_hash = request.source.content_hash()?;
+        let seq = self.inner.next_seq.fetch_add(1, Ordering::Relaxed);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let id = hex::encode(
+            &blake2_256(&[&source_hash[..], &nanos.to_le_bytes(), &seq.to_le_bytes()].concat())
+                [..8],
+        );
+
+        let record = JobRecord {
+            id: id.clone(),
+            status: JobStatus::Queued,
+            source_hash: hex::encode(source_hash),
+            submitted_at: now_secs(),
+            finished_at: None,
+            cached: false,
+            report: None,
+            error: None,
+        };
+        self.inner.persist(&record)?;
+
+        // Register the job before the task can update it
+        let mut jobs = self.inner.lock();
+        let task = tokio::spawn(Inner::run(
+            self.inner.clone(),
+            id.clone(),
+            request,
+            source_hash,
+        ));
+        jobs.insert(
+            id.clone(),
+            Job {
+                record,
+                task: Some(task.abort_handle()),
+            },
+        );
+
+        Ok(id)
+    }
+
+    /// Status of a job
+    pub fn status(&self, id: &str) -> Option<JobStatus> {
+        self.inner.lock().get(id).map(|job| job.record.status)
+    }
+
+    /// Full record of a job, including the report once it is done
+    pub fn job(&self, id: &str) -> Option<JobRecord> {
+        self.inner.lock().get(id).map(|job| job.record.clone())
+    }
+
+    /// All jobs, oldest first
+    pub fn jobs(&self) -> Vec<JobRecord> {
+        let mut jobs: Vec<_> = self
+            .inner
+            .lock()
+            .values()
+            .map(|job| job.record.clone())
+            .collect();
+        jobs.sort_by(|a, b| a.submitted_at.cmp(&b.submitted_at).then(a.id.cmp(&b.id)));
+        jobs
+    }
+
+    /// Cancel a queued or running job
+    ///
+    /// A running build is stopped by dropping it, which kills the build
+    /// process and its container. Returns `false` if the job had already
+    /// finished.
+    pub fn cancel(&self, id: &str) -> Result<bool> {
+        let mut jobs = self.inner.lock();
+        let job = jobs
+            .get_mut(id)
+            .ok_or_else(|| anyhow!("Unknown verification job: {}", id))?;
+        if job.record.status.is_finished() {
+            return Ok(false);
+        }
+
+        if let Some(task) = job.task.take() {
+            task.abort();
+        }
+        job.record.status = JobStatus::Cancelled;
+        job.record.finished_at = Some(now_secs());
+        self.inner.persist(&job.record)?;
+        self.inner.changed.notify_waiters();
+
+        Ok(true)
+    }
+
+    /// Wait until a job has finished and return its record
+    pub async fn wait(&self, id: &str) -> Result<JobRecord> {
+        loop {
+            // Register before checking so a completion in between is not missed
+            let changed = self.inner.changed.notified();
+
+            let record = self
+                .job(id)
+                .ok_or_else(|| anyhow!("Unknown verification job: {}", id))?;
+            if record.status.is_finished() {
+                return Ok(record);
+            }
+
+            changed.await;
+        }
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn persist(&self, record: &JobRecord) -> Result<()> {
+        let path = self.dir.join("jobs").join(format!("{}.json", record.id));
+        write_atomic(&path, &serde_json::to_vec_pretty(record)?)
+    }
+
+    /// Worker task of a job
+    async fn run(self: Arc<Self>, id: String, request: VerificationRequest, source_hash: [u8; 32]) {
+        let outcome = match self.workers.acquire().await {
+            Ok(_permit) => self.verify(&id, &request, &source_hash).await,
+            Err(e) => Err(e.into()),
+        };
+
+        self.update(&id, |record| {
+            match outcome {
+                Ok((report, cached)) => {
+                    record.status = JobStatus::Done;
+                    record.report = Some(report);
+                    record.cached = cached;
+                }
+                Err(e) => {
+                    record.status = JobStatus::Failed;
+                    record.error = Some(format!("{:#}", e));
+                }
+            }
+            record.finished_at = Some(now_secs());
+        });
+    }
+
+    /// Build (or load the cached build) and compare; returns whether the
+    /// build came from the cache
+    async fn verify(
+        &self,
+        id: &str,
+        request: &VerificationRequest,
+        source_hash: &[u8; 32],
+    ) -> Result<(VerificationReport, bool)> {
+        let toolchain = request.toolchain();
+        let artifact = self.artifact_path(source_hash, &toolchain)?;
+
+        let (bundle, cached) = if artifact.is_file() {
+            let content = std::fs::read(&artifact).with_context(|| {
+                format!("Failed to read build artifact: {}", artifact.display())
+            })?;
+            (serde_json::from_slice::<JsonValue>(&content)?, true)
+        } else {
+            self.update(id, |record| record.status = JobStatus::Building);
+
+            match self.verifier.build(&request.source, &toolchain).await? {
+                Build::Bundle(bundle) => {
+                    write_atomic(&artifact, &serde_json::to_vec(&bundle)?)?;
+                    (bundle, false)
+                }
+                // Failed builds are not cached, they may succeed on retry
+                Build::Failed(result) => return Ok((result.into(), false)),
+            }
+        };
+
+        let report = match &request.expected {
+            Expectation::CodeHash { code_hash, .. } => {
+                verifier::compare_code_hash(bundle, code_hash)?.into()
+            }
+            Expectation::Bundle(submitted) => verification_report::compare_bundles(
+                submitted,
+                &verifier::expected_code_hash(submitted)?,
+                bundle,
+            )?,
+        };
+
+        Ok((report, cached))
+    }
+
+    /// Cached bundle built from a source with a toolchain
+    fn artifact_path(&self, source_hash: &[u8; 32], toolchain: &Toolchain) -> Result<PathBuf> {
+        let key = blake2_256(&[&source_hash[..], &serde_json::to_vec(toolchain)?].concat());
+        Ok(self
+            .dir
+            .join("artifacts")
+            .join(format!("{}.contract", hex::encode(key))))
+    }
+
+    /// Update a pending job's record, persist it and wake waiters
+    ///
+    /// Does nothing if the job has finished, e.g. when it was cancelled.
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobRecord)) {
+        let mut jobs = self.lock();
+        let Some(job) = jobs.get_mut(id) else {
+            return;
+        };
+        if job.record.status.is_finished() {
+            return;
+        }
+
+        f(&mut job.record);
+        if job.record.status.is_finished() {
+            job.task = None;
+        }
+        // The in-memory record stays authoritative if the disk write fails
+        let _ = self.persist(&job.record);
+        self.changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_runner::{BuildLimits, BuildOutput, BuildRunner, BuildTarget};
+    use crate::test_utils::flipper;
+    use crate::VerificationResult;
+    use async_trait::async_trait;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    const CARGO_TOML: &str = r#"
+        [package]
+        name = "flipper"
+        version = "0.1.0"
+
+        [dependencies]
+        ink = "5.0"
+    "#;
+
+    const WASM: &[u8] = b"\0asm\x01\0\0\0";
+
+    /// Writes a flipper bundle after `delay`, counting builds
+    struct FakeRunner {
+        builds: Arc<AtomicUsize>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl BuildRunner for FakeRunner {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        async fn build(
+            &self,
+            project_dir: &Path,
+            _target: &BuildTarget,
+            _toolchain: &Toolchain,
+            _limits: &BuildLimits,
+        ) -> Result<BuildOutput> {
+            self.builds.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+
+            let mut bundle = serde_json::to_value(flipper())?;
+            bundle["source"] = serde_json::json!({ "wasm": format!("0x{}", hex::encode(WASM)) });
+            let ink_dir = project_dir.join("target/ink");
+            std::fs::create_dir_all(&ink_dir)?;
+            std::fs::write(ink_dir.join("flipper.contract"), bundle.to_string())?;
+
+            Ok(BuildOutput {
+                success: true,
+                ..Default::default()
+            })
+        }
+    }
+
+    fn queue(dir: &Path, delay: Duration) -> (VerificationQueue, Arc<AtomicUsize>) {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let verifier = ContractVerifier::new(dir.join("workspace"))
+            .unwrap()
+            .with_runner(FakeRunner {
+                builds: builds.clone(),
+                delay,
+            });
+        let queue = VerificationQueue::new(verifier, dir.join("queue"), 2).unwrap();
+        (queue, builds)
+    }
+
+    fn request(source_code: &str) -> VerificationRequest {
+        VerificationRequest::code_hash(
+            VerificationSource::inline(source_code, CARGO_TOML),
+            blake2_256(WASM),
+            Toolchain::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_resubmission_uses_cached_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let (queue, builds) = queue(dir.path(), Duration::ZERO);
+
+        let first = queue
+            .wait(&queue.submit(request("")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(first.status, JobStatus::Done);
+        assert!(!first.cached);
+        assert!(first.report.unwrap().result.is_verified());
+
+        let second = queue
+            .wait(&queue.submit(request("")).unwrap())
+            .await
+            .unwrap();
+        assert!(second.cached);
+        assert!(second.report.unwrap().result.is_verified());
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // A different source is built
+        queue
+            .wait(&queue.submit(request("// v2")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+        assert_eq!(queue.jobs().len(), 3);
+
+        // Records survive reopening the queue
+        drop(queue);
+        let (reopened, _) = self::queue(dir.path(), Duration::ZERO);
+        let record = reopened.job(&first.id).unwrap();
+        assert_eq!(record.status, JobStatus::Done);
+        assert!(matches!(
+            record.report.unwrap().result,
+            VerificationResult::Verified { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let (queue, builds) = queue(dir.path(), Duration::from_secs(60));
+
+        let id = queue.submit(request("")).unwrap();
+        while queue.status(&id) != Some(JobStatus::Building) {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        assert!(queue.cancel(&id).unwrap());
+        assert!(!queue.cancel(&id).unwrap());
+        let record = queue.wait(&id).await.unwrap();
+        assert_eq!(record.status, JobStatus::Cancelled);
+        assert!(record.report.is_none());
+        assert!(queue.cancel("unknown").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+//! Detailed verification reports
+//!
+//! A hash comparison tells *that* a rebuild differs from the deployed code,
+//! not *why*. Comparing the rebuilt bundle with the bundle submitted for
+//! verification shows toolchain differences, which wasm sections differ and
+//! how the ABI differs. When code and data are identical and only custom
+//! sections (names, producers) or the metadata differ, the result is a
+//! [`VerificationResult::PartialMatch`].
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sp_core_hashing::blake2_256;
+use wasmparser::{Parser, Payload};
+
+use crate::abi_diff::{self, AbiDiff};
+use crate::bundle::ContractBundle;
+use crate::verifier::VerificationResult;
+
+/// Category of a wasm section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SectionKind {
+    /// Function bodies
+    Code,
+    /// Data segments and the data count
+    Data,
+    /// Custom sections (`name`, `producers`, ...), no effect on execution
+    Custom,
+    /// Types, imports, exports and the other module structure sections
+    Module,
+}
+
+/// Comparison of one section between the expected and the built wasm
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionDiff {
+    /// Section name, `custom:<name>` for custom sections
+    pub name: String,
+    pub kind: SectionKind,
+    /// Size in the expected wasm, `None` if missing there
+    pub expected_size: Option<usize>,
+    /// Size in the built wasm, `None` if missing there
+    pub built_size: Option<usize>,
+    /// Whether the section contents are byte-identical
+    pub identical: bool,
+}
+
+/// Section-level comparison of two wasm binaries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmComparison {
+    pub expected_size: usize,
+    pub built_size: usize,
+    /// All sections, in the order of the expected wasm
+    pub sections: Vec<SectionDiff>,
+}
+
+impl WasmComparison {
+    /// Compare the sections of two wasm binaries
+    pub fn compare(expected: &[u8], built: &[u8]) -> Result<Self> {
+        let expected_sections = sections(expected).context("Invalid expected wasm")?;
+        let built_sections = sections(built).context("Invalid built wasm")?;
+
+        let mut diffs: Vec<SectionDiff> = expected_sections
+            .iter()
+            .map(|section| {
+                let other = built_sections.iter().find(|s| s.name == section.name);
+                SectionDiff {
+                    name: section.name.clone(),
+                    kind: section.kind,
+                    expected_size: Some(section.bytes.len()),
+                    built_size: other.map(|s| s.bytes.len()),
+                    identical: other.is_some_and(|s| s.bytes == section.bytes),
+                }
+            })
+            .collect();
+
+        diffs.extend(
+            built_sections
+                .iter()
+                .filter(|section| !expected_sections.iter().any(|s| s.name == section.name))
+                .map(|section| SectionDiff {
+                    name: section.name.clone(),
+                    kind: section.kind,
+                    expected_size: None,
+                    built_size: Some(section.bytes.len()),
+                    identical: false,
+                }),
+        );
+
+        Ok(Self {
+            expected_size: expected.len(),
+            built_size: built.len(),
+            sections: diffs,
+        })
+    }
+
+    /// Sections that differ
+    pub fn differences(&self) -> impl Iterator<Item = &SectionDiff> {
+        self.sections.iter().filter(|section| !section.identical)
+    }
+
+    /// Whether code, data and module structure are identical
+    pub fn is_code_identical(&self) -> bool {
+        self.differences()
+            .all(|section| section.kind == SectionKind::Custom)
+    }
+}
+
+/// A field of the build environment that differs from the submitted bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolchainDifference {
+    /// Field name, e.g. `compiler` or `cargo_contract_version`
+    pub field: String,
+    /// Value in the submitted bundle
+    pub expected: Option<String>,
+    /// Value in the rebuilt bundle
+    pub built: Option<String>,
+}
+
+/// Verification outcome with the details of any differences
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Overall result
+    pub result: VerificationResult,
+    /// Build environment fields that differ from the submitted bundle
+    pub toolchain_differences: Vec<ToolchainDifference>,
+    /// Section-level wasm comparison, if the submitted bundle has wasm
+    pub wasm: Option<WasmComparison>,
+    /// Differences between the submitted and the rebuilt metadata
+    pub abi: Option<AbiDiff>,
+}
+
+impl VerificationReport {
+    /// Report with a result only, e.g. for a build that did not produce a bundle
+    pub fn failed(result: VerificationResult) -> Self {
+        Self {
+            result,
+            toolchain_differences: Vec::new(),
+            wasm: None,
+            abi: None,
+        }
+    }
+}
+
+impl From<VerificationResult> for VerificationReport {
+    fn from(result: VerificationResult) -> Self {
+        Self::failed(result)
+    }
+}
+
+/// Compare a rebuilt bundle with the submitted one
+///
+/// `expected_hash` is the code hash the rebuilt wasm must have.
+pub(crate) fn compare_bundles(
+    submitted: &ContractBundle,
+    expected_hash: &[u8; 32],
+    mut built_json: JsonValue,
+) -> Result<VerificationReport> {
+    let built_wasm = built_json
+        .get("source")
+        .and_then(|source| source.get("wasm"))
+        .and_then(|wasm| wasm.as_str())
+        .ok_or_else(|| anyhow::anyhow!("No WASM in built bundle"))
+        .and_then(|wasm| Ok(hex::decode(wasm.trim_start_matches("0x"))?))?;
+    let built_hash = blake2_256(&built_wasm);
+
+    let built = ContractBundle::from_value(built_json.clone())?;
+    if let Some(source) = built_json
+        .get_mut("source")
+        .and_then(|source| source.as_object_mut())
+    {
+        source.remove("wasm");
+    }
+
+    let wasm = submitted
+        .wasm()
+        .map(|expected| WasmComparison::compare(expected, &built_wasm))
+        .transpose()?;
+    let abi = abi_diff::diff_metadata(&submitted.metadata, &built.metadata);
+
+    let code_hash = hex::encode(built_hash);
+    let result = if &built_hash == expected_hash && abi.is_empty() {
+        VerificationResult::Verified {
+            code_hash,
+            metadata: Some(built_json),
+        }
+    } else if &built_hash == expected_hash || wasm.as_ref().is_some_and(|w| w.is_code_identical()) {
+        VerificationResult::PartialMatch {
+            expected: hex::encode(expected_hash),
+            actual: code_hash,
+            metadata: Some(built_json),
+        }
+    } else {
+        VerificationResult::HashMismatch {
+            expected: hex::encode(expected_hash),
+            actual: code_hash,
+        }
+    };
+
+    Ok(VerificationReport {
+        result,
+        toolchain_differences: toolchain_differences(submitted, &built),
+        wasm,
+        abi: Some(abi),
+    })
+}
+
+/// Build environment fields that differ between two bundles
+pub fn toolchain_differences(
+    expected: &ContractBundle,
+    built: &ContractBundle,
+) -> Vec<ToolchainDifference> {
+    let fields = |bundle: &ContractBundle| {
+        let build_info = |key: &str| {
+            bundle
+                .source
+                .build_info
+                .as_ref()
+                .and_then(|info| info.get(key))
+                .map(|value| match value {
+                    JsonValue::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+        };
+
+        [
+            ("language", bundle.source.language.clone()),
+            ("compiler", bundle.source.compiler.clone()),
+            (
+                "cargo_contract_version",
+                build_info("cargo_contract_version"),
+            ),
+            ("rust_toolchain", build_info("rust_toolchain")),
+            ("build_mode", build_info("build_mode")),
+            ("wasm_opt_settings", build_info("wasm_opt_settings")),
+            ("image", bundle.image.clone()),
+        ]
+    };
+
+    fields(expected)
+        .into_iter()
+        .zip(fields(built))
+        .filter(|((_, expected), (_, built))| expected != built)
+        .map(|((field, expected), (_, built))| ToolchainDifference {
+            field: field.to_string(),
+            expected,
+            built,
+        })
+        .collect()
+}
+
+struct Section<'a> {
+    name: String,
+    kind: SectionKind,
+    bytes: &'a [u8],
+}
+
+/// Sections of a wasm binary; repeated custom sections get a `#<n>` suffix
+fn sections(wasm: &[u8]) -> Result<Vec<Section<'_>>> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        let Some((id, range)) = payload.as_section() else {
+            continue;
+        };
+
+        let (mut name, kind) = match &payload {
+            Payload::CustomSection(reader) => {
+                (format!("custom:{}", reader.name()), SectionKind::Custom)
+            }
+            _ => section_name(id),
+        };
+
+        let repeats = sections.iter().filter(|s| s.name == name).count();
+        if repeats > 0 {
+            name = format!("{}#{}", name, repeats + 1);
+        }
+
+        sections.push(Section {
+            name,
+            kind,
+            bytes: &wasm[range],
+        });
+    }
+
+    Ok(sections)
+}
+
+fn section_name(id: u8) -> (String, SectionKind) {
+    let (name, kind) = match id {
+        1 => ("type", SectionKind::Module),
+        2 => ("import", SectionKind::Module),
+        3 => ("function", SectionKind::Module),
+        4 => ("table", SectionKind::Module),
+        5 => ("memory", SectionKind::Module),
+        6 => ("global", SectionKind::Module),
+        7 => ("export", SectionKind::Module),
+        8 => ("start", SectionKind::Module),
+        9 => ("element", SectionKind::Module),
+        10 => ("code", SectionKind::Code),
+        11 => ("data", SectionKind::Data),
+        12 => ("datacount", SectionKind::Data),
+        13 => ("tag", SectionKind::Module),
+        other => return (format!("section {}", other), SectionKind::Module),
+    };
+    (name.to_string(), kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::flipper;
+    use serde_json::json;
+
+    /// Module with one empty function (`body` is its single instruction) and
+    /// a custom section
+    fn module(body: u8, custom: &[u8]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.extend([1, 4, 1, 0x60, 0, 0]); // type: () -> ()
+        wasm.extend([3, 2, 1, 0]); // function 0: type 0
+        wasm.extend([10, 5, 1, 3, 0, body, 0x0b]); // code
+        wasm.extend([0, custom.len() as u8 + 10, 9]);
+        wasm.extend(b"producers");
+        wasm.extend(custom);
+        wasm
+    }
+
+    fn bundle_json(wasm: &[u8], compiler: &str) -> JsonValue {
+        let mut json = serde_json::to_value(flipper()).unwrap();
+        json["source"] = json!({
+            "hash": format!("0x{}", hex::encode(blake2_256(wasm))),
+            "compiler": compiler,
+            "wasm": format!("0x{}", hex::encode(wasm)),
+        });
+        json
+    }
+
+    #[test]
+    fn test_wasm_comparison() {
+        let expected = module(0x01, b"rustc 1.78");
+        let comparison = WasmComparison::compare(&expected, &module(0x01, b"rustc 1.79")).unwrap();
+        assert!(comparison.is_code_identical());
+        assert_eq!(
+            comparison
+                .differences()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            ["custom:producers"]
+        );
+
+        // `unreachable` instead of `nop`
+        let comparison = WasmComparison::compare(&expected, &module(0x00, b"rustc 1.78")).unwrap();
+        assert!(!comparison.is_code_identical());
+        assert_eq!(
+            comparison.differences().next().unwrap().kind,
+            SectionKind::Code
+        );
+    }
+
+    #[test]
+    fn test_compare_bundles() {
+        let expected_wasm = module(0x01, b"rustc 1.78");
+        let submitted =
+            ContractBundle::from_value(bundle_json(&expected_wasm, "rustc 1.78.0")).unwrap();
+        let expected_hash = blake2_256(&expected_wasm);
+
+        let report = compare_bundles(
+            &submitted,
+            &expected_hash,
+            bundle_json(&expected_wasm, "rustc 1.78.0"),
+        )
+        .unwrap();
+        assert!(report.result.is_verified());
+        assert!(report.toolchain_differences.is_empty());
+
+        // Only the custom section differs
+        let built = bundle_json(&module(0x01, b"rustc 1.79"), "rustc 1.79.0");
+        let report = compare_bundles(&submitted, &expected_hash, built).unwrap();
+        assert!(matches!(
+            report.result,
+            VerificationResult::PartialMatch { .. }
+        ));
+        assert_eq!(
+            report.toolchain_differences,
+            [ToolchainDifference {
+                field: "compiler".into(),
+                expected: Some("rustc 1.78.0".into()),
+                built: Some("rustc 1.79.0".into()),
+            }]
+        );
+
+        let built = bundle_json(&module(0x00, b"rustc 1.78"), "rustc 1.78.0");
+        let report = compare_bundles(&submitted, &expected_hash, built).unwrap();
+        assert!(matches!(
+            report.result,
+            VerificationResult::HashMismatch { .. }
+        ));
+
+        let json = serde_json::to_value(VerificationReport::failed(
+            VerificationResult::CompilationFailed("error[E0425]".into()),
+        ))
+        .unwrap();
+        assert_eq!(
+            json["result"],
+            json!({ "status": "CompilationFailed", "error": "error[E0425]" })
+        );
+    }
+}
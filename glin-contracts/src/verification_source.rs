@@ -0,0 +1,558 @@
+//! Source input for contract verification
+//!
+//! Real contracts consist of several modules, often live in a workspace next
+//! to other crates and pin their dependencies with a `Cargo.lock`. A
+//! [`VerificationSource`] provides the full source tree, from a directory or a
+//! `.tar`, `.tar.gz` or `.zip` archive, and locates the contract crate in it:
+//! either at the configured manifest path or as the only crate depending on
+//! ink!. If a `Cargo.lock` is present the build runs with `--locked`.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core_hashing::blake2_256;
+
+use crate::build_runner::BuildTarget;
+
+/// Archive format of a source archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Format from a file name (`.tar`, `.tar.gz`, `.tgz` or `.zip`)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where the contract source comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceInput {
+    /// A single-file contract: `src/lib.rs` and its `Cargo.toml`
+    Inline {
+        source_code: String,
+        cargo_toml: String,
+    },
+    /// A directory tree (copied, without `target` and hidden directories)
+    Directory(PathBuf),
+    /// An archive file, format detected from the extension
+    Archive(PathBuf),
+    /// An archive in memory, e.g. an upload
+    ArchiveBytes {
+        data: Vec<u8>,
+        format: ArchiveFormat,
+    },
+}
+
+/// Contract source to verify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationSource {
+    /// The source files
+    pub input: SourceInput,
+    /// Manifest of the contract crate relative to the source root; detected
+    /// if unset
+    pub manifest_path: Option<PathBuf>,
+    /// Contract package to pick when the source contains several contracts
+    pub package: Option<String>,
+}
+
+impl VerificationSource {
+    /// Single-file source: `src/lib.rs` and its `Cargo.toml`
+    pub fn inline(source_code: impl Into<String>, cargo_toml: impl Into<String>) -> Self {
+        Self::from_input(SourceInput::Inline {
+            source_code: source_code.into(),
+            cargo_toml: cargo_toml.into(),
+        })
+        .with_manifest_path("Cargo.toml")
+    }
+
+    /// Source tree in a directory
+    pub fn directory(path: impl Into<PathBuf>) -> Self {
+        Self::from_input(SourceInput::Directory(path.into()))
+    }
+
+    /// Source tree in a `.tar`, `.tar.gz`/`.tgz` or `.zip` file
+    pub fn archive(path: impl Into<PathBuf>) -> Self {
+        Self::from_input(SourceInput::Archive(path.into()))
+    }
+
+    /// Source tree in an in-memory archive
+    pub fn archive_bytes(data: Vec<u8>, format: ArchiveFormat) -> Self {
+        Self::from_input(SourceInput::ArchiveBytes { data, format })
+    }
+
+    fn from_input(input: SourceInput) -> Self {
+        Self {
+            input,
+            manifest_path: None,
+            package: None,
+        }
+    }
+
+    /// Build the crate at `path`, relative to the source root
+    pub fn with_manifest_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest_path = Some(path.into());
+        self
+    }
+
+    /// Build the contract package `name`
+    pub fn with_package(mut self, name: impl Into<String>) -> Self {
+        self.package = Some(name.into());
+        self
+    }
+
+    /// Hash identifying the source files and crate selection
+    ///
+    /// Directories are hashed by relative path and content of each file,
+    /// skipping the same build output and hidden directories as the copy.
+    pub fn content_hash(&self) -> Result<[u8; 32]> {
+        let mut content = Vec::new();
+        let mut add = |bytes: &[u8]| {
+            content.extend((bytes.len() as u64).to_le_bytes());
+            content.extend(bytes);
+        };
+
+        match &self.input {
+            SourceInput::Inline {
+                source_code,
+                cargo_toml,
+            } => {
+                add(b"inline");
+                add(source_code.as_bytes());
+                add(cargo_toml.as_bytes());
+            }
+            SourceInput::Directory(dir) => {
+                add(b"directory");
+                for (path, file_hash) in tree_hashes(dir, dir)? {
+                    add(path.as_bytes());
+                    add(&file_hash);
+                }
+            }
+            SourceInput::Archive(path) => {
+                add(b"archive");
+                add(&blake2_256(&std::fs::read(path).with_context(|| {
+                    format!("Failed to read archive: {}", path.display())
+                })?));
+            }
+            SourceInput::ArchiveBytes { data, .. } => {
+                add(b"archive");
+                add(&blake2_256(data));
+            }
+        }
+
+        if let Some(manifest_path) = &self.manifest_path {
+            add(manifest_path.to_string_lossy().as_bytes());
+        }
+        if let Some(package) = &self.package {
+            add(package.as_bytes());
+        }
+
+        Ok(blake2_256(&content))
+    }
+
+    /// Write the source tree to `dest` and locate the contract crate
+    pub fn prepare(&self, dest: &Path) -> Result<PreparedSource> {
+        match &self.input {
+            SourceInput::Inline {
+                source_code,
+                cargo_toml,
+            } => {
+                std::fs::create_dir_all(dest.join("src"))?;
+                std::fs::write(dest.join("src").join("lib.rs"), source_code)?;
+                std::fs::write(dest.join("Cargo.toml"), cargo_toml)?;
+            }
+            SourceInput::Directory(dir) => copy_tree(dir, dest)
+                .with_context(|| format!("Failed to copy sources from {}", dir.display()))?,
+            SourceInput::Archive(path) => {
+                let format = ArchiveFormat::from_path(path)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown archive format: {}", path.display()))?;
+                let data = std::fs::read(path)
+                    .with_context(|| format!("Failed to read archive: {}", path.display()))?;
+                extract_archive(&data, format, dest)?;
+            }
+            SourceInput::ArchiveBytes { data, format } => extract_archive(data, *format, dest)?,
+        }
+
+        let (manifest_path, package) = self.locate_crate(dest)?;
+        let locked = dest
+            .join(&manifest_path)
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(dest))
+            .any(|dir| dir.join("Cargo.lock").is_file());
+
+        Ok(PreparedSource {
+            root: dest.to_path_buf(),
+            manifest_path,
+            package,
+            locked,
+        })
+    }
+
+    /// Manifest path and package name of the contract crate
+    fn locate_crate(&self, root: &Path) -> Result<(PathBuf, String)> {
+        if let Some(manifest_path) = &self.manifest_path {
+            if manifest_path.is_absolute()
+                || manifest_path
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+            {
+                anyhow::bail!(
+                    "Manifest path must be relative to the source root: {}",
+                    manifest_path.display()
+                );
+            }
+
+            let manifest = read_manifest(&root.join(manifest_path))?;
+            let name = package_name(&manifest).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} has no [package]; point the manifest path at the contract crate",
+                    manifest_path.display()
+                )
+            })?;
+            return Ok((manifest_path.clone(), name));
+        }
+
+        let mut crates = contract_crates(root, root)?;
+        if let Some(package) = &self.package {
+            crates.retain(|(_, name)| name == package);
+        }
+
+        match crates.len() {
+            1 => Ok(crates.remove(0)),
+            0 => match &self.package {
+                Some(package) => anyhow::bail!("No ink! contract crate named '{}' found", package),
+                None => anyhow::bail!("No ink! contract crate found in the source"),
+            },
+            _ => {
+                let names: Vec<_> = crates.iter().map(|(_, name)| name.as_str()).collect();
+                anyhow::bail!(
+                    "Several ink! contract crates found ({}); select one by package or manifest path",
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Source tree written to disk, with the contract crate located
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedSource {
+    /// Root of the source tree
+    pub root: PathBuf,
+    /// Manifest of the contract crate, relative to `root`
+    pub manifest_path: PathBuf,
+    /// Package name of the contract crate
+    pub package: String,
+    /// Whether a `Cargo.lock` applies to the crate
+    pub locked: bool,
+}
+
+impl PreparedSource {
+    /// What the build runner should build
+    pub fn build_target(&self) -> BuildTarget {
+        BuildTarget {
+            manifest_path: Some(self.manifest_path.clone()),
+            locked: self.locked,
+        }
+    }
+
+    /// The `.contract` bundle cargo-contract wrote for the contract crate
+    ///
+    /// Bundles are written to `target/ink` of the crate or, in a workspace,
+    /// of the workspace root (in a per-crate subdirectory with newer
+    /// cargo-contract versions).
+    pub fn find_bundle(&self) -> Result<Option<PathBuf>> {
+        let artifact = format!("{}.contract", self.package.replace('-', "_"));
+        let manifest_dir = self
+            .root
+            .join(&self.manifest_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone());
+
+        let ink_dirs: Vec<PathBuf> = manifest_dir
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&self.root))
+            .flat_map(|dir| {
+                let ink_dir = dir.join("target").join("ink");
+                [ink_dir.join(&self.package), ink_dir]
+            })
+            .filter(|dir| dir.is_dir())
+            .collect();
+
+        if let Some(bundle) = ink_dirs
+            .iter()
+            .map(|dir| dir.join(&artifact))
+            .find(|path| path.is_file())
+        {
+            return Ok(Some(bundle));
+        }
+
+        // Artifact named differently, e.g. after a `[lib] name`
+        for dir in &ink_dirs {
+            let bundles: Vec<_> = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "contract"))
+                .collect();
+            if let [bundle] = bundles.as_slice() {
+                return Ok(Some(bundle.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Invalid manifest: {}", path.display()))
+}
+
+fn package_name(manifest: &toml::Table) -> Option<String> {
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(String::from)
+}
+
+/// Whether a manifest depends on ink! (`ink` since 4.0, `ink_lang` before)
+fn depends_on_ink(manifest: &toml::Table) -> bool {
+    manifest
+        .get("dependencies")
+        .and_then(|deps| deps.as_table())
+        .is_some_and(|deps| deps.contains_key("ink") || deps.contains_key("ink_lang"))
+}
+
+/// `(manifest path relative to root, package name)` of all ink! crates in `dir`
+fn contract_crates(root: &Path, dir: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let mut crates = Vec::new();
+
+    let manifest_path = dir.join("Cargo.toml");
+    if manifest_path.is_file() {
+        let manifest = read_manifest(&manifest_path)?;
+        if let Some(name) = package_name(&manifest).filter(|_| depends_on_ink(&manifest)) {
+            crates.push((manifest_path.strip_prefix(root)?.to_path_buf(), name));
+        }
+    }
+
+    let mut subdirs: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .filter(|path| !is_skipped_dir(path))
+        .collect();
+    subdirs.sort();
+
+    for subdir in subdirs {
+        crates.extend(contract_crates(root, &subdir)?);
+    }
+
+    Ok(crates)
+}
+
+/// Build output and hidden directories (`.git`, ...)
+fn is_skipped_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == "target" || name.starts_with('.'))
+}
+
+/// Copy a source tree, skipping build output, hidden directories and symlinks
+fn copy_tree(src: &Path, dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            if !is_skipped_dir(&path) {
+                copy_tree(&path, &dest.join(entry.file_name()))?;
+            }
+        } else if file_type.is_file() {
+            std::fs::copy(&path, dest.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `(relative path, content hash)` of the files in a tree, sorted by path
+fn tree_hashes(root: &Path, dir: &Path) -> Result<Vec<(String, [u8; 32])>> {
+    let mut hashes = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            if !is_skipped_dir(&path) {
+                hashes.extend(tree_hashes(root, &path)?);
+            }
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            hashes.push((relative, blake2_256(&std::fs::read(&path)?)));
+        }
+    }
+
+    hashes.sort();
+    Ok(hashes)
+}
+
+/// Unpack an archive into `dest`
+///
+/// Entries escaping `dest` (absolute paths, `..`) are rejected by the
+/// archive readers.
+fn extract_archive(data: &[u8], format: ArchiveFormat, dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+
+    match format {
+        ArchiveFormat::Tar => tar::Archive::new(Cursor::new(data)).unpack(dest),
+        ArchiveFormat::TarGz => {
+            tar::Archive::new(flate2::read::GzDecoder::new(Cursor::new(data))).unpack(dest)
+        }
+        ArchiveFormat::Zip => {
+            return zip::ZipArchive::new(Cursor::new(data))
+                .and_then(|mut archive| archive.extract(dest))
+                .context("Failed to extract zip archive");
+        }
+    }
+    .context("Failed to extract tar archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT_TOML: &str = r#"
+        [package]
+        name = "my-token"
+        version = "0.1.0"
+
+        [dependencies]
+        ink = { version = "5.0", default-features = false }
+    "#;
+
+    const HELPER_TOML: &str = r#"
+        [package]
+        name = "helpers"
+        version = "0.1.0"
+    "#;
+
+    fn workspace_tar() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut add = |path: &str, content: &str| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        };
+
+        add("Cargo.toml", "[workspace]\nmembers = [\"contracts/*\"]\n");
+        add("Cargo.lock", "version = 3\n");
+        add("contracts/token/Cargo.toml", CONTRACT_TOML);
+        add("contracts/token/src/lib.rs", "mod erc20;");
+        add("contracts/token/src/erc20.rs", "");
+        add("contracts/helpers/Cargo.toml", HELPER_TOML);
+
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_prepare_archive_detects_contract_crate() {
+        let dest = tempfile::tempdir().unwrap();
+        let source = VerificationSource::archive_bytes(workspace_tar(), ArchiveFormat::Tar);
+
+        let prepared = source.prepare(dest.path()).unwrap();
+        assert_eq!(
+            prepared.manifest_path,
+            Path::new("contracts/token/Cargo.toml")
+        );
+        assert_eq!(prepared.package, "my-token");
+        assert!(prepared.locked);
+        assert!(dest.path().join("contracts/token/src/erc20.rs").is_file());
+
+        // `cargo contract build` output in a workspace
+        let ink_dir = dest.path().join("target/ink/my-token");
+        std::fs::create_dir_all(&ink_dir).unwrap();
+        std::fs::write(ink_dir.join("my_token.contract"), "{}").unwrap();
+        assert_eq!(
+            prepared.find_bundle().unwrap(),
+            Some(ink_dir.join("my_token.contract"))
+        );
+
+        let source = VerificationSource::archive_bytes(workspace_tar(), ArchiveFormat::Tar)
+            .with_manifest_path("Cargo.toml");
+        assert!(source.prepare(tempfile::tempdir().unwrap().path()).is_err());
+    }
+
+    #[test]
+    fn test_prepare_directory() {
+        let src = tempfile::tempdir().unwrap();
+        for (dir, name) in [("a", "token"), ("b", "other")] {
+            let dir = src.path().join(dir);
+            std::fs::create_dir_all(dir.join("target")).unwrap();
+            std::fs::write(
+                dir.join("Cargo.toml"),
+                CONTRACT_TOML.replace("my-token", name),
+            )
+            .unwrap();
+        }
+
+        // Two contracts: ambiguous unless a package is selected
+        let dest = tempfile::tempdir().unwrap();
+        assert!(VerificationSource::directory(src.path())
+            .prepare(dest.path())
+            .is_err());
+
+        let prepared = VerificationSource::directory(src.path())
+            .with_package("other")
+            .prepare(dest.path())
+            .unwrap();
+        assert_eq!(prepared.manifest_path, Path::new("b/Cargo.toml"));
+        assert!(!prepared.locked);
+        assert!(!dest.path().join("a/target").exists());
+
+        // Build output does not change the source hash, sources do
+        let source = VerificationSource::directory(src.path()).with_package("other");
+        let hash = source.content_hash().unwrap();
+        std::fs::write(src.path().join("a/target/lib.rlib"), "").unwrap();
+        assert_eq!(source.content_hash().unwrap(), hash);
+        std::fs::write(src.path().join("b/lib.rs"), "").unwrap();
+        assert_ne!(source.content_hash().unwrap(), hash);
+        assert_ne!(
+            VerificationSource::directory(src.path())
+                .content_hash()
+                .unwrap(),
+            hash
+        );
+    }
+}
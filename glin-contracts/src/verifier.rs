@@ -2,12 +2,27 @@
 //!
 //! Provides tools to verify smart contracts by compiling source code and
 //! comparing the resulting WASM hash with the deployed code hash.
+//!
+//! Builds run through a [`BuildRunner`]. For reproducible results, use a
+//! container runner and pass the [`Toolchain`] recorded in the deployed
+//! contract's bundle to [`ContractVerifier::verify_source`]. Contracts with
+//! several files, workspaces or a `Cargo.lock` are verified from a directory
+//! or archive [`VerificationSource`].
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use sp_core_hashing::blake2_256;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::Arc;
+
+use glin_client::GlinClient;
+
+use crate::build_runner::{self, BuildLimits, BuildRunner, LocalRunner, Toolchain};
+use crate::bundle::ContractBundle;
+use crate::chain_info;
+use crate::metadata_cache::MetadataCache;
+use crate::verification_report::{self, VerificationReport};
+use crate::verification_source::VerificationSource;
 
 /// Contract verifier
 ///
@@ -43,15 +58,54 @@ use std::process::Command;
 /// ```
 pub struct ContractVerifier {
     workspace_dir: PathBuf,
+    runner: Arc<dyn BuildRunner>,
+    limits: BuildLimits,
+    toolchain: Toolchain,
 }
 
 impl ContractVerifier {
     /// Create a new contract verifier with a workspace directory
+    ///
+    /// Builds run with the host's `cargo contract` until another runner is set.
     pub fn new<P: AsRef<Path>>(workspace_dir: P) -> Result<Self> {
         let workspace_dir = workspace_dir.as_ref().to_path_buf();
         std::fs::create_dir_all(&workspace_dir)?;
 
-        Ok(Self { workspace_dir })
+        Ok(Self {
+            workspace_dir,
+            runner: Arc::new(LocalRunner),
+            limits: BuildLimits::default(),
+            toolchain: Toolchain::default(),
+        })
+    }
+
+    /// Build with the given runner
+    pub fn with_runner(mut self, runner: impl BuildRunner + 'static) -> Self {
+        self.runner = Arc::new(runner);
+        self
+    }
+
+    /// Build in a container if docker or podman is available, locally otherwise
+    pub async fn with_container_runtime(mut self) -> Self {
+        self.runner = build_runner::detect_runner().await;
+        self
+    }
+
+    /// Set the build timeout and resource limits
+    pub fn with_limits(mut self, limits: BuildLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Toolchain used by [`verify`](Self::verify)
+    pub fn with_toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    /// Name of the runner builds use
+    pub fn runner_name(&self) -> &str {
+        self.runner.name()
     }
 
     /// Verify a contract by compiling source and comparing hashes
@@ -61,80 +115,194 @@ impl ContractVerifier {
         cargo_toml: &str,
         deployed_code_hash: &[u8; 32],
     ) -> Result<VerificationResult> {
-        // Create temporary workspace
-        let temp_dir = tempfile::tempdir_in(&self.workspace_dir)?;
+        self.verify_with_toolchain(source_code, cargo_toml, deployed_code_hash, &self.toolchain)
+            .await
+    }
+
+    /// Verify a single-file contract, building with a pinned toolchain
+    ///
+    /// Use [`Toolchain::from_bundle`] to build with the versions the deployed
+    /// contract was built with.
+    pub async fn verify_with_toolchain(
+        &self,
+        source_code: &str,
+        cargo_toml: &str,
+        deployed_code_hash: &[u8; 32],
+        toolchain: &Toolchain,
+    ) -> Result<VerificationResult> {
+        let source = VerificationSource::inline(source_code, cargo_toml);
+        self.verify_source(&source, deployed_code_hash, toolchain)
+            .await
+    }
 
-        // Write source files
-        let src_dir = temp_dir.path().join("src");
-        std::fs::create_dir_all(&src_dir)?;
-        std::fs::write(src_dir.join("lib.rs"), source_code)?;
-        std::fs::write(temp_dir.path().join("Cargo.toml"), cargo_toml)?;
+    /// Verify the contract deployed at `address`
+    ///
+    /// Compares against the code hash the contract currently runs, building
+    /// with `toolchain`: the one recorded in the deployed contract's bundle
+    /// ([`Toolchain::from_bundle`]). On success the result carries the verified
+    /// metadata; store it with [`VerificationResult::cache_metadata`] so
+    /// [`fetch_contract_metadata`] finds it.
+    ///
+    /// [`fetch_contract_metadata`]: crate::fetch_contract_metadata
+    pub async fn verify_address(
+        &self,
+        client: &GlinClient,
+        address: &str,
+        source: &VerificationSource,
+        toolchain: &Toolchain,
+    ) -> Result<VerificationResult> {
+        let info = chain_info::get_contract_info(client, address).await?;
+        self.verify_source(source, &info.code_hash, toolchain).await
+    }
 
-        // Compile contract
-        let output = Command::new("cargo")
-            .arg("contract")
-            .arg("build")
-            .arg("--release")
-            .current_dir(temp_dir.path())
-            .output()?;
-
-        if !output.status.success() {
-            return Ok(VerificationResult::CompilationFailed(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ));
+    /// Verify a contract from a source tree or archive
+    pub async fn verify_source(
+        &self,
+        source: &VerificationSource,
+        deployed_code_hash: &[u8; 32],
+        toolchain: &Toolchain,
+    ) -> Result<VerificationResult> {
+        match self.build(source, toolchain).await? {
+            Build::Bundle(bundle) => compare_code_hash(bundle, deployed_code_hash),
+            Build::Failed(result) => Ok(result),
         }
+    }
 
-        // Extract code hash from bundle
-        let bundle_path = temp_dir
-            .path()
-            .join("target")
-            .join("ink")
-            .join("lib.contract");
+    /// Verify a contract against the bundle submitted with its source
+    ///
+    /// Builds with the toolchain recorded in `submitted` and reports how the
+    /// rebuilt bundle differs: build environment, wasm sections and metadata.
+    /// The expected code hash is the hash of the submitted wasm, or its
+    /// `source.hash` if the bundle has no wasm.
+    pub async fn verify_bundle(
+        &self,
+        source: &VerificationSource,
+        submitted: &ContractBundle,
+    ) -> Result<VerificationReport> {
+        let expected_hash = expected_code_hash(submitted)?;
 
-        if !bundle_path.exists() {
-            return Ok(VerificationResult::CompilationFailed(
-                "Contract bundle not found".to_string(),
-            ));
+        match self
+            .build(source, &Toolchain::from_bundle(submitted))
+            .await?
+        {
+            Build::Bundle(bundle) => {
+                verification_report::compare_bundles(submitted, &expected_hash, bundle)
+            }
+            Build::Failed(result) => Ok(VerificationReport::failed(result)),
         }
+    }
+
+    /// Build the contract and read the resulting bundle
+    pub(crate) async fn build(
+        &self,
+        source: &VerificationSource,
+        toolchain: &Toolchain,
+    ) -> Result<Build> {
+        // Create temporary workspace
+        let temp_dir = tempfile::tempdir_in(&self.workspace_dir)?;
+        let prepared = source.prepare(temp_dir.path())?;
+
+        // Compile contract
+        let output = self
+            .runner
+            .build(
+                &prepared.root,
+                &prepared.build_target(),
+                toolchain,
+                &self.limits,
+            )
+            .await?;
 
-        let bundle: serde_json::Value =
-            serde_json::from_str(&std::fs::read_to_string(bundle_path)?)?;
-
-        let compiled_hash = self.extract_code_hash(&bundle)?;
-
-        // Compare hashes
-        if &compiled_hash == deployed_code_hash {
-            Ok(VerificationResult::Verified {
-                code_hash: hex::encode(compiled_hash),
-                metadata: bundle.get("spec").cloned(),
-            })
-        } else {
-            Ok(VerificationResult::HashMismatch {
-                expected: hex::encode(deployed_code_hash),
-                actual: hex::encode(compiled_hash),
-            })
+        if output.timed_out {
+            return Ok(Build::Failed(VerificationResult::TimedOut {
+                timeout_secs: self.limits.timeout.as_secs(),
+            }));
         }
+        if !output.success {
+            return Ok(Build::Failed(VerificationResult::CompilationFailed(
+                output.stderr,
+            )));
+        }
+
+        let Some(bundle_path) = prepared.find_bundle()? else {
+            return Ok(Build::Failed(VerificationResult::CompilationFailed(
+                "Contract bundle not found".to_string(),
+            )));
+        };
+
+        let bundle = serde_json::from_str(&std::fs::read_to_string(bundle_path)?)?;
+        Ok(Build::Bundle(bundle))
     }
+}
 
-    /// Extract code hash from contract bundle
-    fn extract_code_hash(&self, bundle: &serde_json::Value) -> Result<[u8; 32]> {
-        // Extract WASM from bundle
-        let wasm_hex = bundle
-            .get("source")
-            .and_then(|s| s.get("wasm"))
-            .and_then(|w| w.as_str())
-            .ok_or_else(|| anyhow!("No WASM in bundle"))?
-            .trim_start_matches("0x");
+/// Code hash a rebuild of a submitted bundle must have
+pub(crate) fn expected_code_hash(submitted: &ContractBundle) -> Result<[u8; 32]> {
+    match submitted.wasm() {
+        Some(wasm) => Ok(blake2_256(wasm)),
+        None => submitted
+            .source
+            .code_hash()
+            .ok_or_else(|| anyhow!("Submitted bundle has neither wasm nor a code hash")),
+    }
+}
 
-        let wasm_bytes = hex::decode(wasm_hex)?;
+/// Compare a built bundle's code hash with the deployed one
+pub(crate) fn compare_code_hash(
+    mut bundle: serde_json::Value,
+    deployed_code_hash: &[u8; 32],
+) -> Result<VerificationResult> {
+    let compiled_hash = extract_code_hash(&bundle)?;
 
-        // Compute Blake2_256 hash
-        let hash = blake2_256(&wasm_bytes);
+    // Compare hashes
+    if &compiled_hash == deployed_code_hash {
+        // Keep the metadata, the wasm is on-chain; record the verified hash so
+        // the metadata can be cached under it
+        if let Some(source) = bundle.get_mut("source").and_then(|s| s.as_object_mut()) {
+            source.remove("wasm");
+            source.insert(
+                "hash".to_string(),
+                serde_json::Value::String(format!("0x{}", hex::encode(compiled_hash))),
+            );
+        }
 
-        Ok(hash)
+        Ok(VerificationResult::Verified {
+            code_hash: hex::encode(compiled_hash),
+            metadata: Some(bundle),
+        })
+    } else {
+        Ok(VerificationResult::HashMismatch {
+            expected: hex::encode(deployed_code_hash),
+            actual: hex::encode(compiled_hash),
+        })
     }
 }
 
+/// Extract code hash from contract bundle
+fn extract_code_hash(bundle: &serde_json::Value) -> Result<[u8; 32]> {
+    // Extract WASM from bundle
+    let wasm_hex = bundle
+        .get("source")
+        .and_then(|s| s.get("wasm"))
+        .and_then(|w| w.as_str())
+        .ok_or_else(|| anyhow!("No WASM in bundle"))?
+        .trim_start_matches("0x");
+
+    let wasm_bytes = hex::decode(wasm_hex)?;
+
+    // Compute Blake2_256 hash
+    let hash = blake2_256(&wasm_bytes);
+
+    Ok(hash)
+}
+
+/// Outcome of building a contract for verification
+pub(crate) enum Build {
+    /// The built `.contract` bundle
+    Bundle(serde_json::Value),
+    /// The build failed or timed out
+    Failed(VerificationResult),
+}
+
 /// Verification result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status")]
@@ -142,12 +310,86 @@ pub enum VerificationResult {
     /// Contract successfully verified
     Verified {
         code_hash: String,
+        /// The built bundle without its wasm, usable as contract metadata
         metadata: Option<serde_json::Value>,
     },
     /// Hash mismatch between compiled and deployed code
     HashMismatch { expected: String, actual: String },
+    /// Code and data match the deployed code; only custom sections (e.g.
+    /// the producers section) or the metadata differ
+    PartialMatch {
+        expected: String,
+        actual: String,
+        /// The built bundle without its wasm
+        metadata: Option<serde_json::Value>,
+    },
     /// Compilation failed
-    CompilationFailed(String),
+    CompilationFailed(#[serde(with = "error_message")] String),
+    /// The build exceeded the configured timeout
+    TimedOut { timeout_secs: u64 },
+}
+
+/// (De)serializes the message of [`VerificationResult::CompilationFailed`] as
+/// `{"error": ...}`, since internally tagged enums cannot hold bare strings
+mod error_message {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct ErrorMessage {
+        error: String,
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<S: Serializer>(message: &String, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorMessage {
+            error: message.clone(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        Ok(ErrorMessage::deserialize(deserializer)?.error)
+    }
+}
+
+impl VerificationResult {
+    /// Whether the compiled code matches the deployed code
+    pub fn is_verified(&self) -> bool {
+        matches!(self, VerificationResult::Verified { .. })
+    }
+
+    /// Metadata of a verified contract
+    pub fn verified_metadata(&self) -> Option<&serde_json::Value> {
+        match self {
+            VerificationResult::Verified { metadata, .. } => metadata.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Store the metadata of a verified contract in a metadata cache
+    ///
+    /// The entry is keyed by the verified code hash and, if given, `address`
+    /// is linked to it. Returns `false` if there is nothing to store.
+    pub fn cache_metadata(&self, cache: &MetadataCache, address: Option<&str>) -> Result<bool> {
+        let VerificationResult::Verified {
+            code_hash,
+            metadata: Some(metadata),
+        } = self
+        else {
+            return Ok(false);
+        };
+
+        let code_hash: [u8; 32] = hex::decode(code_hash.trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid code hash: {}", code_hash))?;
+
+        cache.put_json(&code_hash, metadata)?;
+        if let Some(address) = address {
+            cache.link_address(address, &code_hash)?;
+        }
+
+        Ok(true)
+    }
 }
 
 #[cfg(test)]
@@ -158,5 +400,26 @@ mod tests {
     fn test_verifier_creation() {
         let verifier = ContractVerifier::new("/tmp/test-verification").unwrap();
         assert!(verifier.workspace_dir.exists());
+        assert_eq!(verifier.runner_name(), "local");
+    }
+
+    #[test]
+    fn test_cache_verified_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MetadataCache::new(dir.path());
+        let address = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+        let failed = VerificationResult::CompilationFailed("error".into());
+        assert!(!failed.cache_metadata(&cache, Some(address)).unwrap());
+
+        let mut metadata = serde_json::to_value(crate::test_utils::erc20()).unwrap();
+        metadata["source"] = serde_json::json!({ "hash": format!("0x{}", hex::encode([3u8; 32])) });
+        let verified = VerificationResult::Verified {
+            code_hash: hex::encode([3u8; 32]),
+            metadata: Some(metadata),
+        };
+        assert!(verified.cache_metadata(&cache, Some(address)).unwrap());
+        assert!(cache.get(&[3u8; 32]).unwrap().is_some());
+        assert_eq!(cache.code_hash_for(address).unwrap(), Some([3u8; 32]));
     }
 }
@@ -0,0 +1,667 @@
+//! Static analysis of contract wasm
+//!
+//! Inspects a contract's wasm without running it: the host functions it
+//! imports (and their `seal<N>` API versions), its exports, memory limits,
+//! size, instructions pallet-contracts rejects and the custom sections
+//! embedded by rustc and cargo-contract.
+//!
+//! [`WasmAnalysis::check`] turns the analysis into a list of issues, so
+//! contracts that would fail upload or that use deprecated host functions can
+//! be flagged before deployment.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use wasmparser::{
+    KnownCustom, Operator, Parser, Payload, TypeRef, ValType, Validator, WasmFeatures,
+};
+
+use glin_client::GlinClient;
+
+use crate::bundle::ContractBundle;
+use crate::chain_info;
+
+/// Entrypoints every contract must export
+const ENTRYPOINTS: [&str; 2] = ["deploy", "call"];
+
+/// Host functions with several API versions and their latest version
+const LATEST_VERSIONS: &[(&str, u32)] = &[
+    ("call", 2),
+    ("instantiate", 2),
+    ("set_storage", 2),
+    ("get_storage", 1),
+    ("contains_storage", 1),
+    ("clear_storage", 1),
+    ("terminate", 1),
+    ("gas_left", 1),
+    ("weight_to_fee", 1),
+];
+
+/// Host functions marked deprecated in pallet-contracts
+const DEPRECATED: &[&str] = &["random"];
+
+/// Host functions of the removed storage rent API
+const REMOVED: &[&str] = &[
+    "restore_to",
+    "set_rent_allowance",
+    "rent_allowance",
+    "tombstone_deposit",
+    "rent_params",
+];
+
+/// Upload limits of the target chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadLimits {
+    /// Maximum code size in bytes (`MaxCodeLen`)
+    pub max_code_len: usize,
+    /// Maximum number of 64 KiB memory pages
+    pub max_memory_pages: u64,
+}
+
+impl Default for UploadLimits {
+    /// Defaults of pallet-contracts
+    fn default() -> Self {
+        Self {
+            max_code_len: 123 * 1024,
+            max_memory_pages: 16,
+        }
+    }
+}
+
+/// A function imported from the contracts host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostFunction {
+    /// Import module, e.g. `seal1`
+    pub module: String,
+    pub name: String,
+    /// API version from a `seal<N>` module, `None` for other modules
+    pub version: Option<u32>,
+}
+
+impl HostFunction {
+    /// Name without the `seal_` prefix older ink! versions use
+    fn base_name(&self) -> &str {
+        self.name.strip_prefix("seal_").unwrap_or(&self.name)
+    }
+
+    /// Whether the function is deprecated or superseded by a newer version
+    pub fn is_deprecated(&self) -> bool {
+        let name = self.base_name();
+        if DEPRECATED.contains(&name) || REMOVED.contains(&name) {
+            return true;
+        }
+
+        LATEST_VERSIONS
+            .iter()
+            .any(|(n, latest)| *n == name && self.version.is_some_and(|v| v < *latest))
+    }
+
+    /// Whether current runtimes no longer provide the function
+    pub fn is_removed(&self) -> bool {
+        REMOVED.contains(&self.base_name())
+    }
+}
+
+impl fmt::Display for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.module, self.name)
+    }
+}
+
+/// Linear memory of the contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryLimits {
+    /// Initial size in 64 KiB pages
+    pub initial_pages: u64,
+    /// Maximum size in 64 KiB pages
+    pub maximum_pages: Option<u64>,
+    /// Whether the memory is imported (`env.memory`) rather than defined
+    pub imported: bool,
+    pub shared: bool,
+}
+
+/// A custom section and its size
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomSection {
+    pub name: String,
+    pub size: usize,
+}
+
+/// Entry of the `producers` section, e.g. `language: Rust 1.78.0`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Producer {
+    /// `language`, `processed-by` or `sdk`
+    pub field: String,
+    pub name: String,
+    pub version: String,
+}
+
+/// Severity of a [`WasmIssue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum IssueSeverity {
+    /// Works today but should be addressed
+    Warning,
+    /// Upload will fail
+    Error,
+}
+
+/// A problem found by [`WasmAnalysis::check`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmIssue {
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+impl WasmIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for WasmIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            IssueSeverity::Warning => "warning",
+            IssueSeverity::Error => "error",
+        };
+        write!(f, "[{}] {}", severity, self.message)
+    }
+}
+
+/// Result of analyzing a contract's wasm
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmAnalysis {
+    /// Code size in bytes
+    pub size: usize,
+    /// Imported host functions, in import order
+    pub host_functions: Vec<HostFunction>,
+    /// Imports other than host functions and `env.memory`, as `module::name`
+    pub other_imports: Vec<String>,
+    /// Names of all exports
+    pub exports: Vec<String>,
+    pub memory: Option<MemoryLimits>,
+    /// Whether the module has a start function
+    pub has_start: bool,
+    /// Number of floating point instructions
+    pub float_instructions: usize,
+    /// Whether function signatures, locals or globals use `f32`/`f64`
+    pub float_types: bool,
+    /// Instructions of wasm proposals pallet-contracts does not support
+    /// (SIMD, atomics, bulk memory, reference types, ...)
+    pub disallowed_instructions: BTreeSet<String>,
+    pub custom_sections: Vec<CustomSection>,
+    /// Contents of the `producers` section
+    pub producers: Vec<Producer>,
+    /// Validation error with the wasm features pallet-contracts enables
+    pub validation_error: Option<String>,
+}
+
+impl WasmAnalysis {
+    /// Analyze a wasm binary
+    pub fn analyze(wasm: &[u8]) -> Result<Self> {
+        let mut analysis = WasmAnalysis {
+            size: wasm.len(),
+            ..Default::default()
+        };
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.context("Invalid wasm")? {
+                Payload::TypeSection(reader) => {
+                    for func_type in reader.into_iter_err_on_gc_types() {
+                        let func_type = func_type?;
+                        analysis.float_types |= func_type
+                            .params()
+                            .iter()
+                            .chain(func_type.results())
+                            .any(is_float);
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        analysis.add_import(import?);
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        let memory = memory?;
+                        analysis.memory = Some(MemoryLimits {
+                            initial_pages: memory.initial,
+                            maximum_pages: memory.maximum,
+                            imported: false,
+                            shared: memory.shared,
+                        });
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        analysis.float_types |= is_float(&global?.ty.content_type);
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        analysis.exports.push(export?.name.to_string());
+                    }
+                }
+                Payload::StartSection { .. } => analysis.has_start = true,
+                Payload::CodeSectionEntry(body) => {
+                    for local in body.get_locals_reader()? {
+                        analysis.float_types |= is_float(&local?.1);
+                    }
+                    for operator in body.get_operators_reader()? {
+                        analysis.add_operator(&operator?);
+                    }
+                }
+                Payload::CustomSection(reader) => {
+                    analysis.custom_sections.push(CustomSection {
+                        name: reader.name().to_string(),
+                        size: reader.data().len(),
+                    });
+                    if let KnownCustom::Producers(producers) = reader.as_known() {
+                        for field in producers {
+                            let field = field?;
+                            for value in field.values {
+                                let value = value?;
+                                analysis.producers.push(Producer {
+                                    field: field.name.to_string(),
+                                    name: value.name.to_string(),
+                                    version: value.version.to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Deterministic execution rules out most post-MVP proposals
+        let features = WasmFeatures::WASM1 | WasmFeatures::SIGN_EXTENSION;
+        analysis.validation_error = Validator::new_with_features(features)
+            .validate_all(wasm)
+            .err()
+            .map(|e| e.to_string());
+
+        Ok(analysis)
+    }
+
+    /// Analyze the wasm of a `.contract` bundle
+    pub fn from_bundle(bundle: &ContractBundle) -> Result<Self> {
+        let wasm = bundle
+            .wasm()
+            .ok_or_else(|| anyhow!("Bundle has no wasm code"))?;
+        Self::analyze(wasm)
+    }
+
+    /// Whether `deploy` and `call` are exported
+    pub fn has_entrypoints(&self) -> bool {
+        ENTRYPOINTS
+            .iter()
+            .all(|entrypoint| self.exports.iter().any(|e| e == entrypoint))
+    }
+
+    /// Whether the code uses floating point instructions or types
+    pub fn uses_floats(&self) -> bool {
+        self.float_instructions > 0 || self.float_types
+    }
+
+    /// Deprecated or superseded host functions the code imports
+    pub fn deprecated_host_functions(&self) -> impl Iterator<Item = &HostFunction> {
+        self.host_functions.iter().filter(|f| f.is_deprecated())
+    }
+
+    /// Check the code against the rules pallet-contracts applies on upload
+    pub fn check(&self, limits: &UploadLimits) -> Vec<WasmIssue> {
+        let mut issues = Vec::new();
+
+        if self.size > limits.max_code_len {
+            issues.push(WasmIssue::error(format!(
+                "code size {} bytes exceeds the limit of {} bytes",
+                self.size, limits.max_code_len
+            )));
+        }
+
+        for entrypoint in ENTRYPOINTS {
+            if !self.exports.iter().any(|e| e == entrypoint) {
+                issues.push(WasmIssue::error(format!("missing `{}` export", entrypoint)));
+            }
+        }
+        for export in &self.exports {
+            if !ENTRYPOINTS.contains(&export.as_str()) {
+                issues.push(WasmIssue::error(format!(
+                    "unexpected export `{}`, only `deploy` and `call` are allowed",
+                    export
+                )));
+            }
+        }
+
+        if self.has_start {
+            issues.push(WasmIssue::error("start functions are not allowed"));
+        }
+
+        match &self.memory {
+            None => issues.push(WasmIssue::error("no `env.memory` import")),
+            Some(memory) => {
+                if !memory.imported {
+                    issues.push(WasmIssue::error(
+                        "memory must be imported as `env.memory`, not defined",
+                    ));
+                }
+                match memory.maximum_pages {
+                    None => issues.push(WasmIssue::error("memory has no maximum size")),
+                    Some(maximum) if maximum > limits.max_memory_pages => {
+                        issues.push(WasmIssue::error(format!(
+                            "memory maximum of {} pages exceeds the limit of {} pages",
+                            maximum, limits.max_memory_pages
+                        )))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        for import in &self.other_imports {
+            issues.push(WasmIssue::error(format!("unsupported import `{}`", import)));
+        }
+        for function in &self.host_functions {
+            if function.is_removed() {
+                issues.push(WasmIssue::error(format!(
+                    "host function `{}` has been removed",
+                    function
+                )));
+            } else if function.version.is_none() {
+                issues.push(WasmIssue::warning(format!(
+                    "host function `{}` is not part of the stable API",
+                    function
+                )));
+            } else if function.is_deprecated() {
+                issues.push(WasmIssue::warning(format!(
+                    "host function `{}` is deprecated",
+                    function
+                )));
+            }
+        }
+
+        if self.uses_floats() {
+            issues.push(WasmIssue::error(format!(
+                "floating point is not allowed ({} instructions)",
+                self.float_instructions
+            )));
+        }
+        if !self.disallowed_instructions.is_empty() {
+            let instructions: Vec<_> = self
+                .disallowed_instructions
+                .iter()
+                .map(String::as_str)
+                .collect();
+            issues.push(WasmIssue::error(format!(
+                "unsupported instructions: {}",
+                instructions.join(", ")
+            )));
+        } else if let Some(error) = &self.validation_error {
+            issues.push(WasmIssue::error(format!("invalid wasm: {}", error)));
+        }
+
+        issues
+    }
+
+    /// Whether [`check`](Self::check) finds an issue that makes upload fail
+    pub fn will_fail_upload(&self, limits: &UploadLimits) -> bool {
+        self.check(limits)
+            .iter()
+            .any(|issue| issue.severity == IssueSeverity::Error)
+    }
+
+    fn add_import(&mut self, import: wasmparser::Import<'_>) {
+        match import.ty {
+            TypeRef::Func(_) if is_host_module(import.module) => {
+                self.host_functions.push(HostFunction {
+                    module: import.module.to_string(),
+                    name: import.name.to_string(),
+                    version: import
+                        .module
+                        .strip_prefix("seal")
+                        .and_then(|v| v.parse().ok()),
+                });
+            }
+            TypeRef::Memory(memory) if import.module == "env" && import.name == "memory" => {
+                self.memory = Some(MemoryLimits {
+                    initial_pages: memory.initial,
+                    maximum_pages: memory.maximum,
+                    imported: true,
+                    shared: memory.shared,
+                });
+            }
+            _ => self
+                .other_imports
+                .push(format!("{}::{}", import.module, import.name)),
+        }
+    }
+
+    fn add_operator(&mut self, operator: &Operator<'_>) {
+        let name = operator_name(operator);
+        if is_disallowed(&name) {
+            self.disallowed_instructions.insert(name);
+        } else if name.contains("F32") || name.contains("F64") {
+            // Arithmetic, constants and conversions from or to floats
+            self.float_instructions += 1;
+        }
+    }
+}
+
+/// Analyze the code stored on-chain under `code_hash`
+pub async fn analyze_code(client: &GlinClient, code_hash: &[u8; 32]) -> Result<WasmAnalysis> {
+    let wasm = chain_info::get_pristine_code(client, code_hash).await?;
+    WasmAnalysis::analyze(&wasm)
+}
+
+fn is_host_module(module: &str) -> bool {
+    module == "__unstable__"
+        || module
+            .strip_prefix("seal")
+            .is_some_and(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn is_float(ty: &ValType) -> bool {
+    matches!(ty, ValType::F32 | ValType::F64)
+}
+
+/// Instruction name, e.g. `F32Add` or `MemoryFill`
+fn operator_name(operator: &Operator<'_>) -> String {
+    // The derived Debug output starts with the variant name
+    format!("{:?}", operator)
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+/// Whether an instruction belongs to a proposal pallet-contracts does not enable
+fn is_disallowed(name: &str) -> bool {
+    const BULK_MEMORY_AND_REFERENCE_TYPES: &[&str] = &[
+        "MemoryCopy",
+        "MemoryFill",
+        "MemoryInit",
+        "DataDrop",
+        "TableCopy",
+        "TableInit",
+        "ElemDrop",
+        "TableGet",
+        "TableSet",
+        "TableGrow",
+        "TableSize",
+        "TableFill",
+        "RefNull",
+        "RefIsNull",
+        "RefFunc",
+        "TypedSelect",
+    ];
+    const EXCEPTIONS_AND_TAIL_CALLS: &[&str] = &[
+        "Try",
+        "Catch",
+        "CatchAll",
+        "Throw",
+        "Rethrow",
+        "Delegate",
+        "TryTable",
+        "ThrowRef",
+        "ReturnCall",
+        "ReturnCallIndirect",
+    ];
+
+    // SIMD lanes are named like `I8x16` or `F32x4`
+    let simd = name.starts_with("V128")
+        || name
+            .get(1..)
+            .and_then(|rest| rest.split_once('x'))
+            .is_some_and(|(bits, _)| !bits.is_empty() && bits.bytes().all(|b| b.is_ascii_digit()));
+
+    simd || name.contains("Atomic")
+        || BULK_MEMORY_AND_REFERENCE_TYPES.contains(&name)
+        || EXCEPTIONS_AND_TAIL_CALLS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut section = vec![id, contents.len() as u8];
+        section.extend(contents);
+        section
+    }
+
+    fn name(name: &str) -> Vec<u8> {
+        let mut bytes = vec![name.len() as u8];
+        bytes.extend(name.as_bytes());
+        bytes
+    }
+
+    /// Contract importing `seal1::call`, `seal0::random` and `env.memory`,
+    /// exporting `deploy` and `call` with the given function bodies
+    fn contract(deploy: &[u8], call: &[u8]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.extend(section(1, &[1, 0x60, 0, 0])); // type 0: () -> ()
+
+        let mut imports = vec![3];
+        for (module, field) in [("seal1", "call"), ("seal0", "random")] {
+            imports.extend(name(module));
+            imports.extend(name(field));
+            imports.extend([0x00, 0]);
+        }
+        imports.extend(name("env"));
+        imports.extend(name("memory"));
+        imports.extend([0x02, 0x01, 2, 16]); // 2..16 pages
+        wasm.extend(section(2, &imports));
+
+        wasm.extend(section(3, &[2, 0, 0]));
+
+        let mut exports = vec![2];
+        for (export, index) in [("deploy", 2), ("call", 3)] {
+            exports.extend(name(export));
+            exports.extend([0x00, index]);
+        }
+        wasm.extend(section(7, &exports));
+
+        let mut code = vec![2];
+        for body in [deploy, call] {
+            code.extend([body.len() as u8 + 2, 0]);
+            code.extend(body);
+            code.push(0x0b);
+        }
+        wasm.extend(section(10, &code));
+
+        let mut producers = name("producers");
+        producers.push(1);
+        producers.extend(name("language"));
+        producers.push(1);
+        producers.extend(name("Rust"));
+        producers.extend(name("1.78.0"));
+        wasm.extend(section(0, &producers));
+
+        wasm
+    }
+
+    #[test]
+    fn test_analyze_contract() {
+        let analysis = WasmAnalysis::analyze(&contract(&[0x01], &[0x01])).unwrap();
+
+        assert!(analysis.has_entrypoints());
+        assert_eq!(analysis.exports, ["deploy", "call"]);
+        assert_eq!(
+            analysis
+                .host_functions
+                .iter()
+                .map(|f| (f.to_string(), f.version))
+                .collect::<Vec<_>>(),
+            [
+                ("seal1::call".to_string(), Some(1)),
+                ("seal0::random".to_string(), Some(0))
+            ]
+        );
+        assert_eq!(
+            analysis.memory,
+            Some(MemoryLimits {
+                initial_pages: 2,
+                maximum_pages: Some(16),
+                imported: true,
+                shared: false,
+            })
+        );
+        assert_eq!(
+            analysis.producers,
+            [Producer {
+                field: "language".into(),
+                name: "Rust".into(),
+                version: "1.78.0".into(),
+            }]
+        );
+        assert!(!analysis.uses_floats());
+        assert_eq!(analysis.validation_error, None);
+
+        // Both imports have newer versions, neither fails upload
+        assert_eq!(analysis.deprecated_host_functions().count(), 2);
+        let issues = analysis.check(&UploadLimits::default());
+        assert_eq!(issues.len(), 2);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.severity == IssueSeverity::Warning));
+        assert!(!analysis.will_fail_upload(&UploadLimits::default()));
+    }
+
+    #[test]
+    fn test_disallowed_code() {
+        // `f32.const 1.0; drop` and `memory.fill(0, 0, 0)`
+        let float = [0x43, 0, 0, 0x80, 0x3f, 0x1a];
+        let fill = [0x41, 0, 0x41, 0, 0x41, 0, 0xfc, 0x0b, 0x00];
+        let analysis = WasmAnalysis::analyze(&contract(&float, &fill)).unwrap();
+
+        assert_eq!(analysis.float_instructions, 1);
+        assert!(analysis.disallowed_instructions.contains("MemoryFill"));
+        assert!(analysis.validation_error.is_some());
+
+        let limits = UploadLimits {
+            max_code_len: 64,
+            ..Default::default()
+        };
+        let errors: Vec<_> = analysis
+            .check(&limits)
+            .into_iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("[error] code size"));
+        assert!(errors[2].contains("MemoryFill"));
+    }
+}